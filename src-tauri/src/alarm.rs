@@ -0,0 +1,189 @@
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// Furthest ahead an alarm can be set.
+pub const MAX_DAYS_AHEAD: i64 = 366;
+
+/// A wall-clock target such as "15:30 on 2024-03-10". It is kept as a local
+/// date and time, not an instant, so it still rings at 15:30 after the time
+/// zone or daylight saving changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alarm {
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    /// What the alarm is for, e.g. "standup".
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl Alarm {
+    /// An alarm at `time` ("HH:MM") on `date` ("YYYY-MM-DD"), or at the next
+    /// `time` after `now` when no date is given.
+    pub fn parse<Tz: TimeZone>(
+        time: &str,
+        date: Option<&str>,
+        now: &DateTime<Tz>,
+    ) -> Result<Self, String> {
+        let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .map_err(|_| format!("invalid time {time:?}, expected HH:MM"))?;
+        let today = now.naive_local().date();
+        let alarm = match date {
+            Some(date) => Self {
+                date: NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                    .map_err(|_| format!("invalid date {date:?}, expected YYYY-MM-DD"))?,
+                time,
+                name: None,
+            },
+            None => {
+                let alarm = Self {
+                    date: today,
+                    time,
+                    name: None,
+                };
+                if alarm.secs_until(now) > 0 {
+                    alarm
+                } else {
+                    Self {
+                        date: today + Duration::days(1),
+                        ..alarm
+                    }
+                }
+            }
+        };
+        if alarm.secs_until(now) == 0 {
+            return Err(format!("{} is in the past", alarm.label(today)));
+        }
+        if alarm.date > today + Duration::days(MAX_DAYS_AHEAD) {
+            return Err(format!("alarms can be at most {MAX_DAYS_AHEAD} days ahead"));
+        }
+        Ok(alarm)
+    }
+
+    /// When the alarm rings in `tz`. A time skipped by a daylight saving jump
+    /// rings when the clock jumps past it; a repeated one rings the first time.
+    pub fn instant<Tz: TimeZone>(&self, tz: &Tz) -> DateTime<Tz> {
+        let naive = NaiveDateTime::new(self.date, self.time);
+        let mut candidate = naive;
+        // Gaps are at most a few hours; step to the first wall time that exists.
+        for _ in 0..=24 * 60 {
+            match tz.from_local_datetime(&candidate) {
+                LocalResult::Single(at) => return at,
+                LocalResult::Ambiguous(earliest, _) => return earliest,
+                LocalResult::None => candidate += Duration::minutes(1),
+            }
+        }
+        tz.from_utc_datetime(&naive)
+    }
+
+    /// Whole seconds from `now` until the alarm rings, rounded up; zero once
+    /// it is due.
+    pub fn secs_until<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> u32 {
+        let millis = (self.instant(&now.timezone()) - now.clone()).num_milliseconds();
+        if millis <= 0 {
+            return 0;
+        }
+        u32::try_from((millis as u64).div_ceil(1000)).unwrap_or(u32::MAX)
+    }
+
+    /// "15:30", with the date when it is not `today`, e.g. "Mar 10 15:30".
+    pub fn label(&self, today: NaiveDate) -> String {
+        if self.date == today {
+            self.time.format("%H:%M").to_string()
+        } else {
+            format!(
+                "{} {}",
+                self.date.format("%b %-d"),
+                self.time.format("%H:%M")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use chrono_tz::America::New_York;
+    use chrono_tz::Tz;
+
+    fn ny(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Tz> {
+        New_York.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn later_today() {
+        let now = ny(2024, 3, 4, 15, 0);
+        let alarm = Alarm::parse("15:30", None, &now).unwrap();
+        assert_eq!(alarm.date, NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        assert_eq!(alarm.secs_until(&now), 30 * 60);
+        assert_eq!(alarm.label(now.date_naive()), "15:30");
+    }
+
+    #[test]
+    fn past_time_rolls_over_to_tomorrow() {
+        let now = ny(2024, 12, 31, 23, 0);
+        let alarm = Alarm::parse("07:00", None, &now).unwrap();
+        assert_eq!(alarm.date, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+        assert_eq!(alarm.secs_until(&now), 8 * 60 * 60);
+        assert_eq!(alarm.label(now.date_naive()), "Jan 1 07:00");
+        // The current minute counts as past.
+        let alarm = Alarm::parse("23:00", None, &now).unwrap();
+        assert_eq!(alarm.secs_until(&now), 24 * 60 * 60);
+    }
+
+    #[test]
+    fn explicit_date() {
+        let now = ny(2024, 3, 4, 15, 0);
+        let alarm = Alarm::parse("09:00", Some("2024-03-06"), &now).unwrap();
+        assert_eq!(alarm.secs_until(&now), (42 * 60) * 60);
+        assert!(Alarm::parse("09:00", Some("2024-03-04"), &now)
+            .unwrap_err()
+            .contains("in the past"));
+        assert!(Alarm::parse("09:00", Some("2026-03-04"), &now).is_err());
+        assert!(Alarm::parse("9", None, &now).is_err());
+        assert!(Alarm::parse("09:00", Some("tomorrow"), &now).is_err());
+    }
+
+    #[test]
+    fn counts_across_spring_forward() {
+        // 2024-03-10 02:00 EST jumps to 03:00 EDT: 22:00 to 07:00 is 8 hours.
+        let now = ny(2024, 3, 9, 22, 0);
+        let alarm = Alarm::parse("07:00", None, &now).unwrap();
+        assert_eq!(alarm.secs_until(&now), 8 * 60 * 60);
+    }
+
+    #[test]
+    fn skipped_time_rings_when_the_clock_jumps() {
+        let now = ny(2024, 3, 10, 1, 0);
+        let alarm = Alarm::parse("02:30", None, &now).unwrap();
+        // 01:00 EST to 03:00 EDT is one hour.
+        assert_eq!(alarm.secs_until(&now), 60 * 60);
+    }
+
+    #[test]
+    fn repeated_time_rings_the_first_time() {
+        // 01:30 happens twice on 2024-11-03; the first is 05:30 UTC.
+        let now = ny(2024, 11, 3, 0, 0);
+        let alarm = Alarm::parse("01:30", None, &now).unwrap();
+        let at = alarm.instant(&New_York).with_timezone(&Utc);
+        assert_eq!(at, Utc.with_ymd_and_hms(2024, 11, 3, 5, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn follows_a_time_zone_change() {
+        // Set in New York, then the machine moves to Berlin: still 15:30 local.
+        let now = ny(2024, 3, 4, 9, 0);
+        let alarm = Alarm::parse("15:30", None, &now).unwrap();
+        let berlin = now.with_timezone(&chrono_tz::Europe::Berlin);
+        // 09:00 in New York is 15:00 in Berlin.
+        assert_eq!(alarm.secs_until(&berlin), 30 * 60);
+    }
+
+    #[test]
+    fn rounds_up_to_whole_seconds() {
+        let now = ny(2024, 3, 4, 15, 29) + Duration::milliseconds(59_400);
+        let alarm = Alarm::parse("15:30", None, &ny(2024, 3, 4, 15, 0)).unwrap();
+        assert_eq!(alarm.secs_until(&now), 1);
+        assert_eq!(alarm.secs_until(&ny(2024, 3, 4, 15, 30)), 0);
+    }
+}
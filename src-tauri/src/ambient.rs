@@ -0,0 +1,316 @@
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+/// Seed used for the noise generator, so runs (and tests) are reproducible.
+pub const DEFAULT_SEED: u32 = 0x9E37_79B9;
+/// Time to fade fully in or out.
+pub const FADE_SECS: f32 = 2.0;
+
+const TICK_SECS: f32 = 0.012;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseKind {
+    #[default]
+    Off,
+    White,
+    Pink,
+    Brown,
+}
+
+/// Background sound played while a pomodoro work phase is running.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmbientSettings {
+    pub noise: NoiseKind,
+    /// Add a clock tick once per second.
+    pub ticking: bool,
+    pub volume: f32,
+}
+
+impl Default for AmbientSettings {
+    fn default() -> Self {
+        Self {
+            noise: NoiseKind::Off,
+            ticking: false,
+            volume: 0.3,
+        }
+    }
+}
+
+impl AmbientSettings {
+    pub fn is_audible(&self) -> bool {
+        (self.noise != NoiseKind::Off || self.ticking) && self.volume > 0.0
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err("volume must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// xorshift32; plenty for noise and fully deterministic.
+#[derive(Debug, Clone)]
+struct Rng(u32);
+
+impl Rng {
+    fn new(seed: u32) -> Self {
+        Self(seed.max(1))
+    }
+
+    /// Uniform in [-1, 1).
+    fn next_f32(&mut self) -> f32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        (x >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+    }
+}
+
+/// Synthesizes the ambient mix sample by sample and fades it in and out.
+#[derive(Debug, Clone)]
+pub struct AmbientGenerator {
+    settings: AmbientSettings,
+    sample_rate: u32,
+    rng: Rng,
+    pink: [f32; 3],
+    brown: f32,
+    frame: u64,
+    gain: f32,
+    target: f32,
+}
+
+impl AmbientGenerator {
+    pub fn new(sample_rate: u32, seed: u32) -> Self {
+        Self {
+            settings: AmbientSettings::default(),
+            sample_rate: sample_rate.max(1),
+            rng: Rng::new(seed),
+            pink: [0.0; 3],
+            brown: 0.0,
+            frame: 0,
+            gain: 0.0,
+            target: 0.0,
+        }
+    }
+
+    /// Apply new settings and whether the ambient sound should be heard.
+    /// Changes in `playing` fade rather than cut.
+    pub fn update(&mut self, settings: &AmbientSettings, playing: bool) {
+        if settings.noise != self.settings.noise {
+            self.pink = [0.0; 3];
+            self.brown = 0.0;
+        }
+        self.settings = settings.clone();
+        self.target = if playing && settings.is_audible() {
+            1.0
+        } else {
+            0.0
+        };
+    }
+
+    /// Fully faded out with nothing pending.
+    pub fn is_silent(&self) -> bool {
+        self.gain == 0.0 && self.target == 0.0
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        if self.is_silent() {
+            return 0.0;
+        }
+        let step = 1.0 / (FADE_SECS * self.sample_rate as f32);
+        if self.gain < self.target {
+            self.gain = (self.gain + step).min(self.target);
+        } else if self.gain > self.target {
+            self.gain = (self.gain - step).max(self.target);
+        }
+
+        let mut value = self.noise_sample();
+        if self.settings.ticking {
+            value += self.tick_sample();
+        }
+        self.frame += 1;
+        (value * self.settings.volume * self.gain).clamp(-1.0, 1.0)
+    }
+
+    fn noise_sample(&mut self) -> f32 {
+        match self.settings.noise {
+            NoiseKind::Off => 0.0,
+            NoiseKind::White => self.rng.next_f32() * 0.5,
+            NoiseKind::Pink => {
+                // Paul Kellet's economy pink filter.
+                let white = self.rng.next_f32();
+                self.pink[0] = 0.99765 * self.pink[0] + white * 0.099_046;
+                self.pink[1] = 0.963 * self.pink[1] + white * 0.296_516_4;
+                self.pink[2] = 0.57 * self.pink[2] + white * 1.052_691_3;
+                (self.pink.iter().sum::<f32>() + white * 0.1848) * 0.15
+            }
+            NoiseKind::Brown => {
+                let white = self.rng.next_f32();
+                self.brown = (self.brown + 0.02 * white) / 1.02;
+                self.brown * 3.5
+            }
+        }
+    }
+
+    /// A short decaying click at the top of each second, alternating tick and tock.
+    fn tick_sample(&self) -> f32 {
+        let rate = self.sample_rate as u64;
+        let second = self.frame / rate;
+        let t = (self.frame % rate) as f32 / rate as f32;
+        if t >= TICK_SECS {
+            return 0.0;
+        }
+        let freq = if second.is_multiple_of(2) {
+            2500.0
+        } else {
+            2000.0
+        };
+        (TAU * freq * t).sin() * (-t * 400.0).exp() * 0.6
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 8000;
+
+    fn settings(noise: NoiseKind, ticking: bool) -> AmbientSettings {
+        AmbientSettings {
+            noise,
+            ticking,
+            volume: 1.0,
+        }
+    }
+
+    fn render(generator: &mut AmbientGenerator, frames: usize) -> Vec<f32> {
+        (0..frames).map(|_| generator.next_sample()).collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    /// Mean absolute difference between neighbours; low for low-frequency noise.
+    fn roughness(samples: &[f32]) -> f32 {
+        let diffs: f32 = samples.windows(2).map(|w| (w[1] - w[0]).abs()).sum();
+        diffs / rms(samples) / samples.len() as f32
+    }
+
+    fn playing(noise: NoiseKind) -> AmbientGenerator {
+        let mut generator = AmbientGenerator::new(RATE, DEFAULT_SEED);
+        generator.update(&settings(noise, false), true);
+        // Skip the fade-in.
+        render(&mut generator, (FADE_SECS * RATE as f32) as usize);
+        generator
+    }
+
+    #[test]
+    fn same_seed_gives_same_output() {
+        let mut a = playing(NoiseKind::Pink);
+        let mut b = playing(NoiseKind::Pink);
+        assert_eq!(render(&mut a, 1000), render(&mut b, 1000));
+
+        let mut c = AmbientGenerator::new(RATE, 1);
+        c.update(&settings(NoiseKind::Pink, false), true);
+        render(&mut c, (FADE_SECS * RATE as f32) as usize);
+        assert_ne!(render(&mut a, 1000), render(&mut c, 1000));
+    }
+
+    #[test]
+    fn noise_colours_differ_in_roughness() {
+        let white = render(&mut playing(NoiseKind::White), RATE as usize);
+        let pink = render(&mut playing(NoiseKind::Pink), RATE as usize);
+        let brown = render(&mut playing(NoiseKind::Brown), RATE as usize);
+        for s in [&white, &pink, &brown] {
+            assert!(rms(s) > 0.05, "too quiet: {}", rms(s));
+            assert!(s.iter().all(|v| v.abs() <= 1.0));
+        }
+        assert!(roughness(&white) > roughness(&pink));
+        assert!(roughness(&pink) > roughness(&brown));
+    }
+
+    #[test]
+    fn off_is_silent_even_when_playing() {
+        let mut generator = AmbientGenerator::new(RATE, DEFAULT_SEED);
+        generator.update(&settings(NoiseKind::Off, false), true);
+        assert!(generator.is_silent());
+        assert!(render(&mut generator, 100).iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn fades_in_and_out() {
+        let mut generator = AmbientGenerator::new(RATE, DEFAULT_SEED);
+        generator.update(&settings(NoiseKind::White, false), true);
+        let fade_frames = (FADE_SECS * RATE as f32) as usize;
+        let fade_in = render(&mut generator, fade_frames);
+        assert!(rms(&fade_in[..400]) < rms(&fade_in[fade_frames - 400..]) / 4.0);
+
+        generator.update(&settings(NoiseKind::White, false), false);
+        assert!(!generator.is_silent());
+        let fade_out = render(&mut generator, fade_frames);
+        assert!(rms(&fade_out[..400]) > rms(&fade_out[fade_frames - 400..]) * 4.0);
+        assert!(generator.is_silent());
+        assert_eq!(generator.next_sample(), 0.0);
+    }
+
+    #[test]
+    fn resumes_after_pause() {
+        let mut generator = playing(NoiseKind::Brown);
+        generator.update(&settings(NoiseKind::Brown, false), false);
+        render(&mut generator, (FADE_SECS * RATE as f32) as usize);
+        assert!(generator.is_silent());
+        generator.update(&settings(NoiseKind::Brown, false), true);
+        render(&mut generator, (FADE_SECS * RATE as f32) as usize);
+        assert!(rms(&render(&mut generator, 4000)) > 0.05);
+    }
+
+    #[test]
+    fn ticks_once_per_second() {
+        let mut generator = AmbientGenerator::new(RATE, DEFAULT_SEED);
+        generator.update(&settings(NoiseKind::Off, true), true);
+        render(&mut generator, 2 * RATE as usize);
+        let out = render(&mut generator, RATE as usize);
+        let tick_len = (TICK_SECS * RATE as f32) as usize;
+        assert!(out[..tick_len].iter().any(|s| s.abs() > 0.1));
+        assert!(out[tick_len..].iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn volume_scales_output() {
+        let mut loud = playing(NoiseKind::White);
+        let mut quiet = playing(NoiseKind::White);
+        quiet.update(
+            &AmbientSettings {
+                volume: 0.25,
+                ..settings(NoiseKind::White, false)
+            },
+            true,
+        );
+        let a = render(&mut loud, 1000);
+        let b = render(&mut quiet, 1000);
+        for (x, y) in a.iter().zip(&b) {
+            assert!((x * 0.25 - y).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn validation_and_serialization() {
+        assert!(AmbientSettings::default().validate().is_ok());
+        assert!(!AmbientSettings::default().is_audible());
+        let bad = AmbientSettings {
+            volume: -0.1,
+            ..AmbientSettings::default()
+        };
+        assert!(bad.validate().is_err());
+        let parsed: AmbientSettings = serde_json::from_str(r#"{"noise": "brown"}"#).unwrap();
+        assert_eq!(parsed.noise, NoiseKind::Brown);
+        assert_eq!(parsed.volume, 0.3);
+    }
+}
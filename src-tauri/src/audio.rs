@@ -0,0 +1,420 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ambient::AmbientSettings;
+use crate::audio_decode::{self, BundledSound};
+use crate::audio_mixer::{Mixer, NullSink, OutputFormat, SharedMixer};
+use crate::audio_output::{self, DeviceOutput};
+
+pub const MAX_REPEAT: u32 = 10;
+
+/// Used for the null sink when no output device can be opened.
+const FALLBACK_FORMAT: OutputFormat = OutputFormat {
+    sample_rate: 44100,
+    channels: 2,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoundSource {
+    Bundled(BundledSound),
+    /// A WAV, Ogg Vorbis or FLAC file chosen by the user.
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventSound {
+    /// `None` keeps this event silent.
+    pub sound: Option<SoundSource>,
+    pub volume: f32,
+    pub repeat: u32,
+}
+
+impl EventSound {
+    fn bundled(sound: BundledSound, repeat: u32) -> Self {
+        Self {
+            sound: Some(SoundSource::Bundled(sound)),
+            volume: 0.8,
+            repeat,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err("volume must be between 0 and 1".to_string());
+        }
+        if !(1..=MAX_REPEAT).contains(&self.repeat) {
+            return Err(format!("repeat must be between 1 and {MAX_REPEAT}"));
+        }
+        if let Some(SoundSource::File(path)) = &self.sound {
+            if !audio_decode::is_supported_file(path) {
+                return Err(format!("{} is not a WAV, OGG or FLAC file", path.display()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for EventSound {
+    fn default() -> Self {
+        Self::bundled(BundledSound::Chime, 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundSettings {
+    pub enabled: bool,
+    pub timer_finished: EventSound,
+    pub work_finished: EventSound,
+    pub break_finished: EventSound,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timer_finished: EventSound::bundled(BundledSound::Bell, 3),
+            work_finished: EventSound::bundled(BundledSound::Chime, 1),
+            break_finished: EventSound::bundled(BundledSound::Digital, 1),
+        }
+    }
+}
+
+impl SoundSettings {
+    /// The sound to play for `event`, or `None` when it should stay silent.
+    pub fn for_event(&self, event: SoundEvent) -> Option<&EventSound> {
+        if !self.enabled {
+            return None;
+        }
+        let sound = match event {
+            SoundEvent::TimerFinished => &self.timer_finished,
+            SoundEvent::WorkFinished => &self.work_finished,
+            SoundEvent::BreakFinished => &self.break_finished,
+        };
+        sound.sound.as_ref().map(|_| sound)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, sound) in [
+            ("timer_finished", &self.timer_finished),
+            ("work_finished", &self.work_finished),
+            ("break_finished", &self.break_finished),
+        ] {
+            sound.validate().map_err(|e| format!("{name}: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundEvent {
+    TimerFinished,
+    WorkFinished,
+    BreakFinished,
+}
+
+impl SoundEvent {
+    /// Map a `timer:phase-change` payload to the sound event it triggers.
+    pub fn from_transition(from: &str, to: &str) -> Option<Self> {
+        match (from, to) {
+            ("timer", "finished") => Some(SoundEvent::TimerFinished),
+            ("Work", "ShortBreak" | "LongBreak") => Some(SoundEvent::WorkFinished),
+            ("ShortBreak" | "LongBreak", "Work") => Some(SoundEvent::BreakFinished),
+            _ => None,
+        }
+    }
+}
+
+enum AudioCommand {
+    Play(EventSound),
+    Ambient {
+        settings: AmbientSettings,
+        playing: bool,
+    },
+    #[cfg(test)]
+    Flush(Sender<()>),
+}
+
+/// Kept alive on the audio thread for as long as it runs.
+enum Output {
+    Device(DeviceOutput),
+    Null(NullSink),
+}
+
+/// Handle to the audio thread. Decoding and mixing happen there so the
+/// runner never blocks on file I/O.
+pub struct AudioPlayer {
+    tx: Sender<AudioCommand>,
+    /// Last ambient state sent, so the runner can call `update_ambient` every tick.
+    ambient: Mutex<Option<(AmbientSettings, bool)>>,
+}
+
+impl AudioPlayer {
+    /// Start the audio thread on the default output device, falling back to
+    /// a null sink when none is available.
+    pub fn spawn() -> Self {
+        let (tx, rx) = mpsc::channel();
+        let result = thread::Builder::new()
+            .name("audio".to_string())
+            .spawn(move || {
+                let output = match audio_output::open_default_device() {
+                    Ok(device) => Output::Device(device),
+                    Err(e) => {
+                        eprintln!("Audio disabled, using null output: {}", e);
+                        Output::Null(NullSink::new(Mixer::shared(FALLBACK_FORMAT)))
+                    }
+                };
+                let mixer = match &output {
+                    Output::Device(device) => device.mixer.clone(),
+                    Output::Null(sink) => sink.mixer(),
+                };
+                run(rx, mixer);
+            });
+        if let Err(e) = result {
+            eprintln!("Failed to start audio thread: {}", e);
+        }
+        Self::new(tx)
+    }
+
+    /// Start the audio thread on a null sink, returning the sink so the
+    /// mixed output can be rendered without a device.
+    pub fn with_null_sink(format: OutputFormat) -> (Self, NullSink) {
+        let (tx, rx) = mpsc::channel();
+        let mixer = Mixer::shared(format);
+        let sink = NullSink::new(mixer.clone());
+        thread::spawn(move || run(rx, mixer));
+        (Self::new(tx), sink)
+    }
+
+    fn new(tx: Sender<AudioCommand>) -> Self {
+        Self {
+            tx,
+            ambient: Mutex::new(None),
+        }
+    }
+
+    /// Set the ambient sound and whether it should be playing; the audio
+    /// thread fades between states. Repeated identical calls are ignored.
+    pub fn update_ambient(&self, settings: &AmbientSettings, playing: bool) {
+        let mut last = self.ambient.lock().unwrap();
+        let next = (settings.clone(), playing);
+        if last.as_ref() == Some(&next) {
+            return;
+        }
+        *last = Some(next);
+        let _ = self.tx.send(AudioCommand::Ambient {
+            settings: settings.clone(),
+            playing,
+        });
+    }
+
+    pub fn play(&self, sound: &EventSound) {
+        let _ = self.tx.send(AudioCommand::Play(sound.clone()));
+    }
+
+    /// Wait until every command sent so far has been handled.
+    #[cfg(test)]
+    fn flush(&self) {
+        let (tx, rx) = mpsc::channel();
+        self.tx.send(AudioCommand::Flush(tx)).unwrap();
+        rx.recv().unwrap();
+    }
+}
+
+fn run(rx: Receiver<AudioCommand>, mixer: SharedMixer) {
+    let rate = mixer.lock().unwrap().format().sample_rate;
+    // Bundled chimes are synthesized once; user files are decoded on every
+    // play so edits to them are picked up.
+    let mut bundled: HashMap<BundledSound, Arc<Vec<f32>>> = HashMap::new();
+
+    while let Ok(command) = rx.recv() {
+        match command {
+            AudioCommand::Play(sound) => {
+                let samples = match &sound.sound {
+                    None => continue,
+                    Some(SoundSource::Bundled(b)) => bundled
+                        .entry(*b)
+                        .or_insert_with(|| Arc::new(b.synthesize(rate).samples))
+                        .clone(),
+                    Some(SoundSource::File(path)) => match audio_decode::decode_file(path) {
+                        Ok(clip) => Arc::new(clip.resampled(rate)),
+                        Err(e) => {
+                            eprintln!("Failed to load sound: {}", e);
+                            continue;
+                        }
+                    },
+                };
+                mixer
+                    .lock()
+                    .unwrap()
+                    .play(samples, sound.volume, sound.repeat);
+            }
+            AudioCommand::Ambient { settings, playing } => {
+                mixer
+                    .lock()
+                    .unwrap()
+                    .ambient_mut()
+                    .update(&settings, playing);
+            }
+            #[cfg(test)]
+            AudioCommand::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ambient::NoiseKind;
+    use crate::audio_decode::tests::wav_bytes;
+
+    const FORMAT: OutputFormat = OutputFormat {
+        sample_rate: 8000,
+        channels: 1,
+    };
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0, |m, s| m.max(s.abs()))
+    }
+
+    #[test]
+    fn transitions_map_to_events() {
+        assert_eq!(
+            SoundEvent::from_transition("timer", "finished"),
+            Some(SoundEvent::TimerFinished)
+        );
+        assert_eq!(
+            SoundEvent::from_transition("Work", "LongBreak"),
+            Some(SoundEvent::WorkFinished)
+        );
+        assert_eq!(
+            SoundEvent::from_transition("ShortBreak", "Work"),
+            Some(SoundEvent::BreakFinished)
+        );
+        assert_eq!(SoundEvent::from_transition("Work", "Work"), None);
+    }
+
+    #[test]
+    fn disabled_or_silent_events_have_no_sound() {
+        let mut settings = SoundSettings::default();
+        assert!(settings.for_event(SoundEvent::WorkFinished).is_some());
+        settings.work_finished.sound = None;
+        assert!(settings.for_event(SoundEvent::WorkFinished).is_none());
+        settings.enabled = false;
+        assert!(settings.for_event(SoundEvent::TimerFinished).is_none());
+    }
+
+    #[test]
+    fn validation_rejects_bad_values() {
+        assert!(SoundSettings::default().validate().is_ok());
+
+        let mut settings = SoundSettings::default();
+        settings.break_finished.volume = 1.5;
+        assert_eq!(
+            settings.validate().unwrap_err(),
+            "break_finished: volume must be between 0 and 1"
+        );
+
+        let mut settings = SoundSettings::default();
+        settings.timer_finished.repeat = 0;
+        assert!(settings
+            .validate()
+            .unwrap_err()
+            .starts_with("timer_finished: repeat"));
+
+        let mut settings = SoundSettings::default();
+        settings.work_finished.sound = Some(SoundSource::File("/tmp/alert.mp3".into()));
+        assert!(settings
+            .validate()
+            .unwrap_err()
+            .contains("not a WAV, OGG or FLAC"));
+    }
+
+    #[test]
+    fn sound_source_serialization() {
+        let json = serde_json::to_string(&SoundSource::Bundled(BundledSound::Bell)).unwrap();
+        assert_eq!(json, r#"{"bundled":"bell"}"#);
+        let source: SoundSource = serde_json::from_str(r#"{"file":"/a/b.ogg"}"#).unwrap();
+        assert_eq!(source, SoundSource::File("/a/b.ogg".into()));
+    }
+
+    #[test]
+    fn player_mixes_bundled_sound_into_null_sink() {
+        let (player, sink) = AudioPlayer::with_null_sink(FORMAT);
+        player.play(&EventSound {
+            sound: Some(SoundSource::Bundled(BundledSound::Digital)),
+            volume: 0.5,
+            repeat: 1,
+        });
+        player.flush();
+        let out = sink.render(8000);
+        let p = peak(&out);
+        assert!(p > 0.2 && p <= 0.5, "peak {p}");
+    }
+
+    #[test]
+    fn player_decodes_and_resamples_user_file() {
+        let dir = std::env::temp_dir().join(format!("mac-timer-audio-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("beep.wav");
+        let frames: Vec<Vec<i16>> = (0..400).map(|_| vec![16384]).collect();
+        std::fs::write(&path, wav_bytes(4000, 1, &frames)).unwrap();
+
+        let (player, sink) = AudioPlayer::with_null_sink(FORMAT);
+        player.play(&EventSound {
+            sound: Some(SoundSource::File(path)),
+            volume: 1.0,
+            repeat: 2,
+        });
+        player.flush();
+        // 400 frames at 4 kHz become 800 at 8 kHz, then a 2000-frame gap.
+        let out = sink.render(3600);
+        assert!((out[0] - 0.5).abs() < 1e-3);
+        assert!((out[799] - 0.5).abs() < 1e-3);
+        assert_eq!(out[800], 0.0);
+        assert_eq!(out[2799], 0.0);
+        assert!((out[2800] - 0.5).abs() < 1e-3);
+        assert_eq!(sink.render(10), vec![0.0; 10]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn ambient_starts_and_stops_through_the_player() {
+        let (player, sink) = AudioPlayer::with_null_sink(FORMAT);
+        let ambient = AmbientSettings {
+            noise: NoiseKind::Pink,
+            ticking: false,
+            volume: 0.5,
+        };
+        player.update_ambient(&ambient, true);
+        player.flush();
+        let out = sink.render(16000);
+        assert!(peak(&out[8000..]) > 0.01);
+
+        player.update_ambient(&ambient, false);
+        player.flush();
+        sink.render(16000);
+        assert_eq!(sink.render(8), vec![0.0; 8]);
+    }
+
+    #[test]
+    fn unreadable_file_is_skipped() {
+        let (player, sink) = AudioPlayer::with_null_sink(FORMAT);
+        player.play(&EventSound {
+            sound: Some(SoundSource::File("/nonexistent/alert.wav".into())),
+            volume: 1.0,
+            repeat: 1,
+        });
+        player.flush();
+        assert_eq!(sink.render(16), vec![0.0; 16]);
+    }
+}
@@ -0,0 +1,314 @@
+use std::f32::consts::TAU;
+use std::fs::File;
+use std::io::Cursor;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// File extensions accepted for user sounds.
+pub const SUPPORTED_EXTENSIONS: [&str; 3] = ["wav", "ogg", "flac"];
+
+/// Decoded mono audio at its native sample rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl Clip {
+    /// Linearly resample to `rate`. Good enough for short alert sounds.
+    pub fn resampled(&self, rate: u32) -> Vec<f32> {
+        if rate == self.sample_rate || self.samples.is_empty() {
+            return self.samples.clone();
+        }
+        let ratio = self.sample_rate as f64 / rate as f64;
+        let len = ((self.samples.len() as f64) / ratio).round().max(1.0) as usize;
+        let last = self.samples.len() - 1;
+        (0..len)
+            .map(|i| {
+                let pos = i as f64 * ratio;
+                let idx = (pos.floor() as usize).min(last);
+                let next = (idx + 1).min(last);
+                let frac = (pos - idx as f64) as f32;
+                self.samples[idx] * (1.0 - frac) + self.samples[next] * frac
+            })
+            .collect()
+    }
+}
+
+/// Chimes synthesized at startup so the app ships without audio assets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundledSound {
+    /// Rising three-note arpeggio.
+    Chime,
+    /// Single struck bell with a long decay.
+    Bell,
+    /// Two short electronic beeps.
+    Digital,
+    /// A very short blip, used for countdown seconds.
+    Blip,
+}
+
+impl BundledSound {
+    pub fn synthesize(self, sample_rate: u32) -> Clip {
+        let mut samples = Vec::new();
+        match self {
+            BundledSound::Chime => {
+                for freq in [880.0, 1047.0, 1319.0] {
+                    append_tone(&mut samples, sample_rate, &[(freq, 1.0)], 0.15, 12.0);
+                }
+                append_tone(&mut samples, sample_rate, &[(1319.0, 1.0)], 0.45, 8.0);
+            }
+            BundledSound::Bell => {
+                let partials = [(660.0, 1.0), (1320.0, 0.5), (1980.0, 0.25), (2640.0, 0.12)];
+                append_tone(&mut samples, sample_rate, &partials, 1.2, 4.0);
+            }
+            BundledSound::Digital => {
+                for _ in 0..2 {
+                    append_tone(&mut samples, sample_rate, &[(2000.0, 1.0)], 0.08, 0.0);
+                    append_silence(&mut samples, sample_rate, 0.06);
+                }
+            }
+            BundledSound::Blip => {
+                append_tone(&mut samples, sample_rate, &[(1500.0, 1.0)], 0.05, 20.0);
+            }
+        }
+        Clip {
+            samples,
+            sample_rate,
+        }
+    }
+}
+
+/// Append a sum of sine partials with an exponential decay of `decay` per second.
+fn append_tone(
+    samples: &mut Vec<f32>,
+    sample_rate: u32,
+    partials: &[(f32, f32)],
+    secs: f32,
+    decay: f32,
+) {
+    let len = (secs * sample_rate as f32) as usize;
+    let weight: f32 = partials.iter().map(|(_, amp)| amp).sum();
+    // Short linear ramps at both ends avoid clicks.
+    let ramp = (sample_rate as f32 * 0.005) as usize;
+    for i in 0..len {
+        let t = i as f32 / sample_rate as f32;
+        let value: f32 = partials
+            .iter()
+            .map(|(freq, amp)| amp * (TAU * freq * t).sin())
+            .sum();
+        let edge = (i.min(len - 1 - i) as f32 / ramp.max(1) as f32).min(1.0);
+        samples.push(value / weight * (-decay * t).exp() * edge * 0.8);
+    }
+}
+
+fn append_silence(samples: &mut Vec<f32>, sample_rate: u32, secs: f32) {
+    samples.resize(samples.len() + (secs * sample_rate as f32) as usize, 0.0);
+}
+
+/// Decode a WAV, Ogg Vorbis or FLAC file, mixed down to mono.
+pub fn decode_file(path: &Path) -> Result<Clip, String> {
+    let file = File::open(path).map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+    let ext = path.extension().and_then(|e| e.to_str());
+    decode_source(Box::new(file), ext).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+pub fn decode_bytes(bytes: Vec<u8>, extension: Option<&str>) -> Result<Clip, String> {
+    decode_source(Box::new(Cursor::new(bytes)), extension)
+}
+
+fn decode_source(source: Box<dyn MediaSource>, extension: Option<&str>) -> Result<Clip, String> {
+    let mss = MediaSourceStream::new(source, Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = extension {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("unsupported audio: {e}"))?;
+    let mut format = probed.format;
+    let track = format
+        .default_track()
+        .ok_or_else(|| "no audio track".to_string())?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| "unknown sample rate".to_string())?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("unsupported codec: {e}"))?;
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(format!("failed to read audio: {e}")),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt packet is skipped rather than failing the whole sound.
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("failed to decode audio: {e}")),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend(
+            buffer
+                .samples()
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+    }
+
+    if samples.is_empty() {
+        return Err("audio file contains no samples".to_string());
+    }
+    Ok(Clip {
+        samples,
+        sample_rate,
+    })
+}
+
+/// Whether `path` has one of the [`SUPPORTED_EXTENSIONS`].
+pub fn is_supported_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| {
+            SUPPORTED_EXTENSIONS
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(ext))
+        })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build a 16-bit PCM WAV file in memory.
+    pub(crate) fn wav_bytes(sample_rate: u32, channels: u16, frames: &[Vec<i16>]) -> Vec<u8> {
+        let data_len = (frames.len() * channels as usize * 2) as u32;
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data_len).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        out.extend_from_slice(&16u32.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&channels.to_le_bytes());
+        out.extend_from_slice(&sample_rate.to_le_bytes());
+        out.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+        out.extend_from_slice(&(channels * 2).to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&data_len.to_le_bytes());
+        for frame in frames {
+            for sample in frame {
+                out.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn decodes_mono_wav() {
+        let frames: Vec<Vec<i16>> = [0, 16384, -16384, 32767].iter().map(|&s| vec![s]).collect();
+        let clip = decode_bytes(wav_bytes(8000, 1, &frames), Some("wav")).unwrap();
+        assert_eq!(clip.sample_rate, 8000);
+        assert_eq!(clip.samples.len(), 4);
+        assert!((clip.samples[1] - 0.5).abs() < 1e-3);
+        assert!((clip.samples[2] + 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn stereo_wav_is_mixed_down() {
+        let frames = vec![vec![16384, -16384], vec![16384, 16384]];
+        let clip = decode_bytes(wav_bytes(44100, 2, &frames), Some("wav")).unwrap();
+        assert_eq!(clip.samples.len(), 2);
+        assert!(clip.samples[0].abs() < 1e-3);
+        assert!((clip.samples[1] - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        assert!(decode_bytes(b"not audio at all".to_vec(), Some("wav")).is_err());
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        let err = decode_file(Path::new("/nonexistent/chime.wav")).unwrap_err();
+        assert!(err.starts_with("failed to open"));
+    }
+
+    #[test]
+    fn resample_changes_length_and_keeps_endpoints() {
+        let clip = Clip {
+            samples: vec![0.0, 1.0, 0.0, -1.0],
+            sample_rate: 4,
+        };
+        let up = clip.resampled(8);
+        assert_eq!(up.len(), 8);
+        assert_eq!(up[0], 0.0);
+        assert!((up[1] - 0.5).abs() < 1e-6);
+        assert_eq!(up[2], 1.0);
+        assert_eq!(clip.resampled(4), clip.samples);
+        assert_eq!(clip.resampled(2).len(), 2);
+    }
+
+    #[test]
+    fn bundled_sounds_are_audible_and_bounded() {
+        for sound in [
+            BundledSound::Chime,
+            BundledSound::Bell,
+            BundledSound::Digital,
+        ] {
+            let clip = sound.synthesize(48000);
+            assert!(clip.samples.len() > 4800, "{sound:?} too short");
+            let peak = clip.samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+            assert!(peak > 0.3 && peak <= 1.0, "{sound:?} peak {peak}");
+            assert!(
+                clip.samples[0].abs() < 1e-3,
+                "{sound:?} starts with a click"
+            );
+        }
+    }
+
+    #[test]
+    fn synthesis_is_deterministic() {
+        assert_eq!(
+            BundledSound::Chime.synthesize(22050),
+            BundledSound::Chime.synthesize(22050)
+        );
+    }
+
+    #[test]
+    fn supported_extensions() {
+        assert!(is_supported_file(Path::new("/a/b.wav")));
+        assert!(is_supported_file(Path::new("b.FLAC")));
+        assert!(is_supported_file(Path::new("b.ogg")));
+        assert!(!is_supported_file(Path::new("b.mp3")));
+        assert!(!is_supported_file(Path::new("wav")));
+    }
+}
@@ -0,0 +1,238 @@
+use std::sync::{Arc, Mutex};
+
+use crate::ambient::{AmbientGenerator, DEFAULT_SEED};
+
+/// Silence inserted between repeats of the same sound.
+pub const REPEAT_GAP_SECS: f32 = 0.25;
+/// Oldest voices are dropped beyond this many, so a stalled output can't grow without bound.
+pub const MAX_VOICES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+pub type SharedMixer = Arc<Mutex<Mixer>>;
+
+#[derive(Debug)]
+struct Voice {
+    samples: Arc<Vec<f32>>,
+    volume: f32,
+    /// Position within the current repeat; negative values count down the gap.
+    pos: isize,
+    repeats_left: u32,
+}
+
+/// Sums mono voices and the ambient bed into an interleaved output buffer.
+#[derive(Debug)]
+pub struct Mixer {
+    format: OutputFormat,
+    voices: Vec<Voice>,
+    ambient: AmbientGenerator,
+}
+
+impl Mixer {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            voices: Vec::new(),
+            ambient: AmbientGenerator::new(format.sample_rate, DEFAULT_SEED),
+        }
+    }
+
+    pub fn shared(format: OutputFormat) -> SharedMixer {
+        Arc::new(Mutex::new(Self::new(format)))
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Queue `samples` (mono, at the output rate) to play `repeat` times.
+    pub fn play(&mut self, samples: Arc<Vec<f32>>, volume: f32, repeat: u32) {
+        if samples.is_empty() || repeat == 0 {
+            return;
+        }
+        if self.voices.len() >= MAX_VOICES {
+            self.voices.remove(0);
+        }
+        self.voices.push(Voice {
+            samples,
+            volume: volume.clamp(0.0, 1.0),
+            pos: 0,
+            repeats_left: repeat,
+        });
+    }
+
+    pub fn ambient_mut(&mut self) -> &mut AmbientGenerator {
+        &mut self.ambient
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.voices.is_empty() && self.ambient.is_silent()
+    }
+
+    /// Fill an interleaved buffer; every channel gets the same mono mix.
+    pub fn fill(&mut self, out: &mut [f32]) {
+        let channels = self.format.channels.max(1) as usize;
+        let gap = (REPEAT_GAP_SECS * self.format.sample_rate as f32) as isize;
+        for frame in out.chunks_mut(channels) {
+            let mut value = self.ambient.next_sample();
+            for voice in &mut self.voices {
+                if voice.pos >= 0 {
+                    value += voice.samples[voice.pos as usize] * voice.volume;
+                }
+                voice.pos += 1;
+                if voice.pos >= 0 && voice.pos as usize == voice.samples.len() {
+                    voice.repeats_left -= 1;
+                    voice.pos = -gap;
+                }
+            }
+            self.voices.retain(|v| v.repeats_left > 0);
+            frame.fill(value.clamp(-1.0, 1.0));
+        }
+    }
+}
+
+/// Output that pulls from the mixer on demand instead of feeding a device.
+/// Used when no audio device is available and to inspect the mix in tests.
+pub struct NullSink {
+    mixer: SharedMixer,
+}
+
+impl NullSink {
+    pub fn new(mixer: SharedMixer) -> Self {
+        Self { mixer }
+    }
+
+    /// Render `frames` frames of interleaved output.
+    pub fn render(&self, frames: usize) -> Vec<f32> {
+        let mut mixer = self.mixer.lock().unwrap();
+        let mut out = vec![0.0; frames * mixer.format().channels.max(1) as usize];
+        mixer.fill(&mut out);
+        out
+    }
+
+    pub fn mixer(&self) -> SharedMixer {
+        self.mixer.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ambient::{AmbientSettings, NoiseKind};
+
+    const MONO: OutputFormat = OutputFormat {
+        sample_rate: 8,
+        channels: 1,
+    };
+
+    fn sink(format: OutputFormat) -> (SharedMixer, NullSink) {
+        let mixer = Mixer::shared(format);
+        let sink = NullSink::new(mixer.clone());
+        (mixer, sink)
+    }
+
+    #[test]
+    fn idle_mixer_renders_silence() {
+        let (_, sink) = sink(MONO);
+        assert_eq!(sink.render(4), vec![0.0; 4]);
+    }
+
+    #[test]
+    fn voice_is_scaled_by_volume_and_then_removed() {
+        let (mixer, sink) = sink(MONO);
+        mixer
+            .lock()
+            .unwrap()
+            .play(Arc::new(vec![1.0, -1.0, 0.5]), 0.5, 1);
+        assert_eq!(sink.render(4), vec![0.5, -0.5, 0.25, 0.0]);
+        assert!(mixer.lock().unwrap().is_idle());
+    }
+
+    #[test]
+    fn repeats_are_separated_by_a_gap() {
+        let (mixer, sink) = sink(MONO);
+        mixer.lock().unwrap().play(Arc::new(vec![1.0]), 1.0, 2);
+        // At 8 Hz the 0.25 s gap is two frames.
+        assert_eq!(sink.render(5), vec![1.0, 0.0, 0.0, 1.0, 0.0]);
+        assert!(mixer.lock().unwrap().is_idle());
+    }
+
+    #[test]
+    fn voices_sum_and_clip() {
+        let (mixer, sink) = sink(MONO);
+        {
+            let mut m = mixer.lock().unwrap();
+            m.play(Arc::new(vec![0.75, 0.25]), 1.0, 1);
+            m.play(Arc::new(vec![0.75, 0.25]), 1.0, 1);
+        }
+        assert_eq!(sink.render(2), vec![1.0, 0.5]);
+    }
+
+    #[test]
+    fn every_channel_gets_the_mix() {
+        let (mixer, sink) = sink(OutputFormat {
+            sample_rate: 8,
+            channels: 2,
+        });
+        mixer
+            .lock()
+            .unwrap()
+            .play(Arc::new(vec![0.5, 0.25]), 1.0, 1);
+        assert_eq!(sink.render(2), vec![0.5, 0.5, 0.25, 0.25]);
+    }
+
+    #[test]
+    fn empty_or_zero_repeat_is_ignored() {
+        let mut mixer = Mixer::new(MONO);
+        mixer.play(Arc::new(Vec::new()), 1.0, 1);
+        mixer.play(Arc::new(vec![1.0]), 1.0, 0);
+        assert!(mixer.is_idle());
+    }
+
+    #[test]
+    fn oldest_voice_is_dropped_past_the_limit() {
+        let mut mixer = Mixer::new(MONO);
+        for _ in 0..MAX_VOICES + 3 {
+            mixer.play(Arc::new(vec![0.1; 4]), 1.0, 1);
+        }
+        assert_eq!(mixer.voices.len(), MAX_VOICES);
+    }
+
+    #[test]
+    fn ambient_is_mixed_under_voices() {
+        let (mixer, sink) = sink(OutputFormat {
+            sample_rate: 8000,
+            channels: 1,
+        });
+        let ambient = AmbientSettings {
+            noise: NoiseKind::White,
+            ticking: false,
+            volume: 0.5,
+        };
+        mixer.lock().unwrap().ambient_mut().update(&ambient, true);
+        assert!(!mixer.lock().unwrap().is_idle());
+        let out = sink.render(16000);
+        assert!(out[15000..].iter().any(|s| s.abs() > 0.01));
+
+        mixer.lock().unwrap().ambient_mut().update(&ambient, false);
+        sink.render(16000);
+        assert!(mixer.lock().unwrap().is_idle());
+        assert_eq!(sink.render(4), vec![0.0; 4]);
+    }
+
+    #[test]
+    fn stop_all_silences_everything() {
+        let (mixer, sink) = sink(MONO);
+        mixer.lock().unwrap().play(Arc::new(vec![1.0; 4]), 1.0, 3);
+        mixer.lock().unwrap().stop_all();
+        assert_eq!(sink.render(2), vec![0.0, 0.0]);
+    }
+}
@@ -0,0 +1,66 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+
+use crate::audio_mixer::{Mixer, OutputFormat, SharedMixer};
+
+/// A running output stream on the default device, fed from a shared mixer.
+/// `cpal::Stream` is not `Send` on every platform, so this stays on the audio thread.
+pub struct DeviceOutput {
+    _stream: Stream,
+    pub mixer: SharedMixer,
+}
+
+pub fn open_default_device() -> Result<DeviceOutput, String> {
+    let host = cpal::default_host();
+    let device = host
+        .default_output_device()
+        .ok_or_else(|| "no output device".to_string())?;
+    let supported = device
+        .default_output_config()
+        .map_err(|e| format!("no output config: {e}"))?;
+    let sample_format = supported.sample_format();
+    let config: StreamConfig = supported.into();
+    let mixer = Mixer::shared(OutputFormat {
+        sample_rate: config.sample_rate.0,
+        channels: config.channels,
+    });
+
+    let stream = match sample_format {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, mixer.clone()),
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, mixer.clone()),
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, mixer.clone()),
+        other => return Err(format!("unsupported sample format {other}")),
+    }?;
+    stream
+        .play()
+        .map_err(|e| format!("failed to start output stream: {e}"))?;
+    Ok(DeviceOutput {
+        _stream: stream,
+        mixer,
+    })
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    mixer: SharedMixer,
+) -> Result<Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let mut scratch: Vec<f32> = Vec::new();
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                scratch.resize(data.len(), 0.0);
+                mixer.lock().unwrap().fill(&mut scratch);
+                for (out, sample) in data.iter_mut().zip(&scratch) {
+                    *out = T::from_sample(*sample);
+                }
+            },
+            |e| eprintln!("Audio output error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("failed to open output stream: {e}"))
+}
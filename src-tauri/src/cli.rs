@@ -0,0 +1,125 @@
+use tauri::AppHandle;
+
+use crate::commands;
+use crate::runner;
+
+/// Actions that can be sent to the running app from the command line, e.g.
+/// `mac-timer dismiss` or `mac-timer quick tea 4m`. A second launch forwards
+/// its arguments to the first instance through the single-instance plugin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    /// Acknowledge a finished timer.
+    Dismiss,
+    /// Run a quick command; see [`crate::quick::parse`].
+    Quick(String),
+}
+
+/// The names of the commands in [`CliCommand`].
+const COMMANDS: [&str; 2] = ["dismiss", "quick"];
+
+/// Whether `argv` (including the program name) starts with one of this app's
+/// commands, right or wrong.
+pub fn names_command<S: AsRef<str>>(argv: &[S]) -> bool {
+    argv.get(1)
+        .is_some_and(|arg| COMMANDS.contains(&arg.as_ref()))
+}
+
+/// Parse `argv` (including the program name). `Ok(None)` when no command was given.
+pub fn parse_args<S: AsRef<str>>(argv: &[S]) -> Result<Option<CliCommand>, String> {
+    let mut args = argv.iter().skip(1).map(|a| a.as_ref());
+    let Some(command) = args.next() else {
+        return Ok(None);
+    };
+    let command = match command {
+        "dismiss" => CliCommand::Dismiss,
+        "quick" => {
+            let phrase = args.collect::<Vec<_>>().join(" ");
+            if phrase.trim().is_empty() {
+                return Err("quick: expected a command, e.g. quick tea 4m".to_string());
+            }
+            return Ok(Some(CliCommand::Quick(phrase)));
+        }
+        other => return Err(format!("unknown command: {other}")),
+    };
+    if let Some(extra) = args.next() {
+        return Err(format!("unexpected argument: {extra}"));
+    }
+    Ok(Some(command))
+}
+
+/// Run the command in `argv`. Errors are printed and also shown as a
+/// notification, since arguments forwarded from a second launch run here,
+/// away from the terminal they were typed in. At startup, arguments that name
+/// none of the commands are ignored: the system or a launcher may pass its
+/// own, such as macOS's `-psn_…`.
+pub fn handle_args(app: &AppHandle, argv: &[String], at_startup: bool) {
+    if at_startup && !names_command(argv) {
+        return;
+    }
+    let result = parse_args(argv).and_then(|command| command.map_or(Ok(()), |c| run(app, c)));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        runner::alert_command_error(app, &e);
+    }
+}
+
+fn run(app: &AppHandle, command: CliCommand) -> Result<(), String> {
+    match command {
+        CliCommand::Dismiss => commands::acknowledge(app),
+        CliCommand::Quick(phrase) => {
+            commands::run_quick(app, &phrase).map_err(|e| format!("quick: {}", e.message))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_arguments_is_no_command() {
+        assert_eq!(parse_args(&["mac-timer"]), Ok(None));
+    }
+
+    #[test]
+    fn parses_dismiss() {
+        assert_eq!(
+            parse_args(&["mac-timer", "dismiss"]),
+            Ok(Some(CliCommand::Dismiss))
+        );
+    }
+
+    #[test]
+    fn parses_quick_phrases() {
+        assert_eq!(
+            parse_args(&["mac-timer", "quick", "pomodoro", "50/10", "x3"]),
+            Ok(Some(CliCommand::Quick("pomodoro 50/10 x3".to_string())))
+        );
+        assert_eq!(
+            parse_args(&["mac-timer", "quick", "tea 4m"]),
+            Ok(Some(CliCommand::Quick("tea 4m".to_string())))
+        );
+        assert!(parse_args(&["mac-timer", "quick"]).is_err());
+    }
+
+    #[test]
+    fn recognises_its_own_commands() {
+        assert!(names_command(&["mac-timer", "dismiss", "now"]));
+        assert!(names_command(&["mac-timer", "quick"]));
+        assert!(!names_command(&["mac-timer", "-psn_0_12345"]));
+        assert!(!names_command(&["mac-timer"]));
+    }
+
+    #[test]
+    fn rejects_unknown_and_extra_arguments() {
+        assert_eq!(
+            parse_args(&["mac-timer", "explode"]),
+            Err("unknown command: explode".to_string())
+        );
+        assert_eq!(
+            parse_args(&["mac-timer", "dismiss", "now"]),
+            Err("unexpected argument: now".to_string())
+        );
+    }
+}
@@ -1,31 +1,88 @@
+use chrono::{DateTime, Local, TimeZone};
 use serde::Serialize;
 use std::sync::Mutex;
 use tauri::{AppHandle, Emitter, Manager, State};
 
-use crate::pomodoro::{PomodoroConfig, PomodoroTimer};
+use crate::alarm::Alarm;
+use crate::ambient::AmbientSettings;
+use crate::audio::{AudioPlayer, EventSound};
+use crate::duration::{self, DurationStyle};
+use crate::flowtime::{FlowtimeProgress, FlowtimeStatus, FlowtimeTimer};
+use crate::history::{BreakEvent, BreakEventKind, BreakHistory, ComplianceStats};
+use crate::hotkeys;
+use crate::interval::{IntervalConfig, IntervalProgress, IntervalStatus, IntervalTimer};
+use crate::json_file;
+use crate::notification::NotificationAction;
+use crate::overlay::OverlayTracker;
+use crate::pomodoro::{Phase, PomodoroConfig, PomodoroStatus, PomodoroTimer};
+use crate::presets::{Preset, PresetFile, PresetKind};
+use crate::program::Program;
+use crate::quick::{self, QuickAction, QuickError, Suggestion};
+use crate::schedule::{Occurrence, ScheduleAction, ScheduleState, Upcoming};
+use crate::settings::Settings;
+use crate::shortcuts;
+use crate::snooze;
 use crate::timer::BasicTimer;
-use crate::tray::update_tray_title;
+use crate::tray::refresh_tray;
+use crate::tray_format::{format_tray_title, TitleContext, TrayTitleSettings};
+use crate::warnings::{Countdown, CountdownKey};
 
 #[derive(Debug)]
 pub enum ActiveTimer {
     Basic(BasicTimer),
     Pomodoro(PomodoroTimer),
+    Interval(IntervalTimer),
+    Flowtime(FlowtimeTimer),
+}
+
+/// The latest notification shown, so actions from it can be checked for staleness.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationEvent {
+    pub id: u64,
+    pub from: String,
+    pub to: String,
+    /// Offered as buttons; [`NotificationAction::Dismiss`] is always accepted too.
+    pub actions: Vec<NotificationAction>,
+    /// The countdown the event was raised for. Once it changes, the event is stale.
+    pub(crate) key: CountdownKey,
 }
 
 #[derive(Debug)]
 pub struct AppState {
     pub active: ActiveTimer,
+    pub settings: Settings,
+    pub notification: Option<NotificationEvent>,
+    pub next_notification_id: u64,
+    /// Set through `set_presenting`: breaks wait and overlays stay hidden.
+    pub presenting: bool,
+    /// The break held back while presenting, started once that ends.
+    pub deferred_break: Option<CountdownKey>,
+    /// Manual do-not-disturb; alerts are held back like in quiet hours.
+    pub do_not_disturb: bool,
+    /// The preset the current timer was started from, until the mode changes.
+    pub preset: Option<Preset>,
+    /// A phase change made by an action rather than a tick, e.g. a skip, as
+    /// `(from, to)`; the runner announces it like one from a tick.
+    pub pending_change: Option<(String, String)>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
             active: ActiveTimer::Pomodoro(PomodoroTimer::new(PomodoroConfig::default())),
+            settings: Settings::default(),
+            notification: None,
+            next_notification_id: 1,
+            presenting: false,
+            deferred_break: None,
+            do_not_disturb: false,
+            preset: None,
+            pending_change: None,
         }
     }
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TimerSnapshot {
     pub mode: String,
     pub display: String,
@@ -34,33 +91,87 @@ pub struct TimerSnapshot {
     pub is_running: bool,
     pub is_finished: bool,
     pub phase: Option<String>,
+    /// Name of the pomodoro program and of its current segment.
+    pub program: Option<String>,
+    pub segment: Option<String>,
     pub session_display: Option<String>,
     pub tray_title: String,
+    /// A finished timer whose alert has not been dismissed yet.
+    pub unacknowledged: bool,
+    pub finished_secs_ago: Option<u32>,
+    pub snooze_count: u32,
+    /// The pomodoro phase in `phase` ended its predecessor and waits to be started.
+    pub is_waiting: bool,
+    pub waiting_secs: Option<u32>,
+    pub total_waiting_secs: u32,
+    /// Round, set and time left in the whole workout, in interval mode.
+    pub interval: Option<IntervalProgress>,
+    /// Work so far, the break it earned and the sessions done, in Flowtime mode.
+    pub flowtime: Option<FlowtimeProgress>,
+    /// When the basic timer's alarm rings, e.g. "15:30" or "Mar 10 07:00".
+    pub alarm: Option<String>,
+    /// What the alarm is for, e.g. "standup".
+    pub alarm_name: Option<String>,
+    /// Label or name of the preset the timer was started from.
+    pub preset: Option<String>,
 }
 
 impl TimerSnapshot {
-    pub fn from_state(active: &ActiveTimer) -> Self {
-        match active {
-            ActiveTimer::Basic(t) => Self::from_basic(t),
-            ActiveTimer::Pomodoro(t) => Self::from_pomodoro(t),
+    pub fn from_state(state: &AppState) -> Self {
+        let title = &state.settings.tray_title;
+        let now = Local::now();
+        let mut snapshot = match &state.active {
+            ActiveTimer::Basic(t) => Self::from_basic(t, title, &now),
+            ActiveTimer::Pomodoro(t) => Self::from_pomodoro(t, title),
+            ActiveTimer::Interval(t) => Self::from_interval(t, title),
+            ActiveTimer::Flowtime(t) => Self::from_flowtime(t, title),
+        };
+        if let Some(preset) = &state.preset {
+            snapshot.preset = Some(preset.display_name().to_string());
+            if let Some(icon) = &preset.icon {
+                let mut ctx = match &state.active {
+                    ActiveTimer::Basic(t) => TitleContext::from_basic(t, &now),
+                    ActiveTimer::Pomodoro(t) => TitleContext::from_pomodoro(t),
+                    ActiveTimer::Interval(t) => TitleContext::from_interval(t),
+                    ActiveTimer::Flowtime(t) => TitleContext::from_flowtime(t),
+                };
+                // A program segment's own icon still wins
+                ctx.icon.get_or_insert_with(|| icon.clone());
+                snapshot.tray_title = format_tray_title(title, &ctx);
+            }
         }
+        snapshot
     }
 
-    pub fn from_basic(t: &BasicTimer) -> Self {
+    /// `now` decides whether the alarm label needs its date.
+    pub fn from_basic(t: &BasicTimer, title: &TrayTitleSettings, now: &DateTime<Local>) -> Self {
         Self {
             mode: "basic".to_string(),
             display: t.display(),
             remaining_secs: t.remaining_secs(),
-            total_secs: t.duration_secs(),
+            total_secs: t.run_secs(),
             is_running: t.status() == crate::timer::TimerStatus::Running,
             is_finished: t.is_finished(),
             phase: None,
+            program: None,
+            segment: None,
             session_display: None,
-            tray_title: format!("⏱ {}", t.display()),
+            tray_title: format_tray_title(title, &TitleContext::from_basic(t, now)),
+            unacknowledged: t.is_unacknowledged(),
+            finished_secs_ago: t.finished_secs_ago(),
+            snooze_count: t.snooze_count(),
+            is_waiting: false,
+            waiting_secs: None,
+            total_waiting_secs: 0,
+            interval: None,
+            flowtime: None,
+            alarm: t.alarm().map(|a| a.label(now.date_naive())),
+            alarm_name: t.alarm().and_then(|a| a.name.clone()),
+            preset: None,
         }
     }
 
-    pub fn from_pomodoro(t: &PomodoroTimer) -> Self {
+    pub fn from_pomodoro(t: &PomodoroTimer, title: &TrayTitleSettings) -> Self {
         Self {
             mode: "pomodoro".to_string(),
             display: t.display(),
@@ -69,8 +180,75 @@ impl TimerSnapshot {
             is_running: t.status() == crate::pomodoro::PomodoroStatus::Running,
             is_finished: false,
             phase: Some(format!("{:?}", t.phase())),
+            program: Some(t.program().name.clone()),
+            segment: t.segment().map(|s| s.name.clone()),
             session_display: Some(t.session_display()),
-            tray_title: t.tray_title(),
+            tray_title: format_tray_title(title, &TitleContext::from_pomodoro(t)),
+            unacknowledged: false,
+            finished_secs_ago: None,
+            snooze_count: t.snooze_count(),
+            is_waiting: t.status() == PomodoroStatus::Waiting,
+            waiting_secs: t.waiting_secs(),
+            total_waiting_secs: t.total_waiting_secs(),
+            interval: None,
+            flowtime: None,
+            alarm: None,
+            alarm_name: None,
+            preset: None,
+        }
+    }
+
+    pub fn from_interval(t: &IntervalTimer, title: &TrayTitleSettings) -> Self {
+        Self {
+            mode: "interval".to_string(),
+            display: t.display(),
+            remaining_secs: t.remaining_secs(),
+            total_secs: t.phase_duration_secs(),
+            is_running: t.status() == IntervalStatus::Running,
+            is_finished: t.is_finished(),
+            phase: Some(format!("{:?}", t.phase())),
+            program: None,
+            segment: Some(t.phase().label().to_string()),
+            session_display: None,
+            tray_title: format_tray_title(title, &TitleContext::from_interval(t)),
+            unacknowledged: false,
+            finished_secs_ago: None,
+            snooze_count: 0,
+            is_waiting: false,
+            waiting_secs: None,
+            total_waiting_secs: 0,
+            interval: Some(t.progress()),
+            flowtime: None,
+            alarm: None,
+            alarm_name: None,
+            preset: None,
+        }
+    }
+
+    pub fn from_flowtime(t: &FlowtimeTimer, title: &TrayTitleSettings) -> Self {
+        Self {
+            mode: "flowtime".to_string(),
+            display: t.display(),
+            remaining_secs: t.remaining_secs(),
+            total_secs: t.phase_duration_secs(),
+            is_running: t.status() == FlowtimeStatus::Running,
+            is_finished: false,
+            phase: Some(format!("{:?}", t.phase())),
+            program: None,
+            segment: None,
+            session_display: None,
+            tray_title: format_tray_title(title, &TitleContext::from_flowtime(t)),
+            unacknowledged: false,
+            finished_secs_ago: None,
+            snooze_count: 0,
+            is_waiting: false,
+            waiting_secs: None,
+            total_waiting_secs: 0,
+            interval: None,
+            flowtime: Some(t.progress()),
+            alarm: None,
+            alarm_name: None,
+            preset: None,
         }
     }
 }
@@ -81,56 +259,741 @@ pub(crate) fn do_start(state: &mut AppState) -> TimerSnapshot {
     match &mut state.active {
         ActiveTimer::Basic(t) => t.start(),
         ActiveTimer::Pomodoro(t) => t.start(),
+        ActiveTimer::Interval(t) => t.start(),
+        ActiveTimer::Flowtime(t) => t.start(),
     }
-    TimerSnapshot::from_state(&state.active)
+    TimerSnapshot::from_state(state)
 }
 
 pub(crate) fn do_pause(state: &mut AppState) -> TimerSnapshot {
     match &mut state.active {
         ActiveTimer::Basic(t) => t.pause(),
         ActiveTimer::Pomodoro(t) => t.pause(),
+        ActiveTimer::Interval(t) => t.pause(),
+        ActiveTimer::Flowtime(t) => t.pause(),
+    }
+    TimerSnapshot::from_state(state)
+}
+
+pub(crate) fn is_running(state: &AppState) -> bool {
+    match &state.active {
+        ActiveTimer::Basic(t) => t.status() == crate::timer::TimerStatus::Running,
+        ActiveTimer::Pomodoro(t) => t.status() == crate::pomodoro::PomodoroStatus::Running,
+        ActiveTimer::Interval(t) => t.status() == IntervalStatus::Running,
+        ActiveTimer::Flowtime(t) => t.status() == FlowtimeStatus::Running,
+    }
+}
+
+pub(crate) fn do_toggle(state: &mut AppState) -> TimerSnapshot {
+    if is_running(state) {
+        do_pause(state)
+    } else {
+        do_start(state)
     }
-    TimerSnapshot::from_state(&state.active)
 }
 
 pub(crate) fn do_reset(state: &mut AppState) -> TimerSnapshot {
+    state.notification = None;
     match &mut state.active {
         ActiveTimer::Basic(t) => t.reset(),
         ActiveTimer::Pomodoro(t) => t.reset(),
+        ActiveTimer::Interval(t) => t.reset(),
+        ActiveTimer::Flowtime(t) => t.reset(),
     }
-    TimerSnapshot::from_state(&state.active)
+    TimerSnapshot::from_state(state)
 }
 
 pub(crate) fn do_set_duration(state: &mut AppState, secs: u32) -> Option<TimerSnapshot> {
-    if let ActiveTimer::Basic(t) = &mut state.active {
-        t.set_duration(secs);
-        Some(TimerSnapshot::from_basic(t))
-    } else {
-        None
-    }
+    let ActiveTimer::Basic(t) = &mut state.active else {
+        return None;
+    };
+    t.set_duration(secs);
+    state.notification = None;
+    Some(TimerSnapshot::from_state(state))
+}
+
+/// Set the basic timer from text such as "1h30m" or "25:00"; see
+/// [`duration::parse`].
+pub(crate) fn do_set_duration_text(
+    state: &mut AppState,
+    input: &str,
+) -> Result<TimerSnapshot, String> {
+    let secs = duration::parse(input)?;
+    do_set_duration(state, secs).ok_or_else(|| "switch to the timer to set a duration".to_string())
 }
 
 pub(crate) fn do_switch_to_basic(state: &mut AppState) -> TimerSnapshot {
     state.active = ActiveTimer::Basic(BasicTimer::new(25 * 60));
-    TimerSnapshot::from_state(&state.active)
+    state.notification = None;
+    state.preset = None;
+    TimerSnapshot::from_state(state)
+}
+
+/// Ring at `time` ("HH:MM"), on `date` ("YYYY-MM-DD") or else the next time
+/// it comes round after `now`, named `name` if given. Runs as a started basic
+/// timer.
+pub(crate) fn do_set_alarm<Tz: TimeZone>(
+    state: &mut AppState,
+    time: &str,
+    date: Option<&str>,
+    name: Option<&str>,
+    now: &DateTime<Tz>,
+) -> Result<TimerSnapshot, String> {
+    let mut alarm = Alarm::parse(time, date, now)?;
+    alarm.name = name.map(str::to_string);
+    let mut timer = BasicTimer::with_alarm(alarm, now);
+    timer.start();
+    state.active = ActiveTimer::Basic(timer);
+    state.notification = None;
+    state.preset = None;
+    Ok(TimerSnapshot::from_state(state))
 }
 
 pub(crate) fn do_switch_to_pomodoro(state: &mut AppState) -> TimerSnapshot {
-    state.active = ActiveTimer::Pomodoro(PomodoroTimer::new(PomodoroConfig::default()));
-    TimerSnapshot::from_state(&state.active)
+    do_switch_to_pomodoro_with_config(state, PomodoroConfig::default())
+}
+
+pub(crate) fn do_switch_to_pomodoro_with_config(
+    state: &mut AppState,
+    config: PomodoroConfig,
+) -> TimerSnapshot {
+    state.active = ActiveTimer::Pomodoro(PomodoroTimer::new(config));
+    state.notification = None;
+    state.preset = None;
+    TimerSnapshot::from_state(state)
+}
+
+/// The built-in classic program followed by the ones from settings.
+pub(crate) fn do_get_programs(state: &AppState) -> Vec<Program> {
+    let mut programs = vec![Program::classic(PomodoroConfig::default())];
+    programs.extend(state.settings.programs.iter().cloned());
+    programs
+}
+
+pub(crate) fn do_switch_to_program(
+    state: &mut AppState,
+    name: &str,
+) -> Result<TimerSnapshot, String> {
+    let program = do_get_programs(state)
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("unknown program {name:?}"))?;
+    state.active = ActiveTimer::Pomodoro(PomodoroTimer::with_program(program));
+    state.notification = None;
+    state.preset = None;
+    Ok(TimerSnapshot::from_state(state))
+}
+
+/// Switch to interval mode with `config`, or the workout from settings.
+pub(crate) fn do_switch_to_interval(
+    state: &mut AppState,
+    config: Option<IntervalConfig>,
+) -> Result<TimerSnapshot, String> {
+    let config = config.unwrap_or(state.settings.interval.workout);
+    config.validate()?;
+    state.active = ActiveTimer::Interval(IntervalTimer::new(config));
+    state.notification = None;
+    state.preset = None;
+    Ok(TimerSnapshot::from_state(state))
+}
+
+pub(crate) fn do_switch_to_flowtime(state: &mut AppState) -> TimerSnapshot {
+    state.active = ActiveTimer::Flowtime(FlowtimeTimer::new(state.settings.flowtime.clone()));
+    state.notification = None;
+    state.preset = None;
+    TimerSnapshot::from_state(state)
+}
+
+/// End a Flowtime work phase and start the break it earned, leaving the
+/// change in `pending_change` to be announced like a skip.
+pub(crate) fn do_take_break(state: &mut AppState) -> Result<TimerSnapshot, String> {
+    let ActiveTimer::Flowtime(t) = &mut state.active else {
+        return Err("breaks are taken on demand only in Flowtime mode".to_string());
+    };
+    let Some(t) = t.take_break() else {
+        return Err("no work to take a break from".to_string());
+    };
+    state.pending_change = Some((format!("{:?}", t.from), format!("{:?}", t.to)));
+    Ok(TimerSnapshot::from_state(state))
+}
+
+/// Carry out a schedule's action for `occurrence`; an alarm rings at the
+/// occurrence, anything else starts right away. `None` when a timer is
+/// running or a break is waiting to start, which is left alone and the
+/// occurrence skipped; pausing a break already counts as leaving it.
+pub(crate) fn do_run_schedule<Tz: TimeZone>(
+    state: &mut AppState,
+    occurrence: &Occurrence,
+    now: &DateTime<Tz>,
+) -> Result<Option<TimerSnapshot>, String> {
+    let action = state
+        .settings
+        .schedules
+        .iter()
+        .find(|s| s.name == occurrence.schedule)
+        .map(|s| s.action.clone())
+        .ok_or_else(|| format!("unknown schedule {:?}", occurrence.schedule))?;
+    if is_running(state) || current_break(state).is_some() {
+        return Ok(None);
+    }
+    match action {
+        ScheduleAction::Alarm => {
+            let mut timer = BasicTimer::with_alarm(occurrence.alarm(), now);
+            timer.start();
+            state.active = ActiveTimer::Basic(timer);
+            state.notification = None;
+            state.preset = None;
+        }
+        ScheduleAction::Program { name } => {
+            do_switch_to_program(state, &name)?;
+        }
+        ScheduleAction::Interval => {
+            do_switch_to_interval(state, None)?;
+        }
+        ScheduleAction::Flowtime => {
+            do_switch_to_flowtime(state);
+        }
+    }
+    Ok(Some(do_start(state)))
+}
+
+/// Start the timer preset `id` describes, replacing the current one.
+pub(crate) fn do_start_preset(state: &mut AppState, id: &str) -> Result<TimerSnapshot, String> {
+    let preset = state
+        .settings
+        .presets
+        .get(id)
+        .cloned()
+        .ok_or_else(|| format!("unknown preset {id:?}"))?;
+    match &preset.kind {
+        PresetKind::Basic { secs } => {
+            do_switch_to_basic(state);
+            do_set_duration(state, *secs);
+        }
+        PresetKind::Pomodoro(config) => {
+            do_switch_to_pomodoro_with_config(state, *config);
+        }
+        PresetKind::Program { name } => {
+            do_switch_to_program(state, name)?;
+        }
+        PresetKind::Interval(config) => {
+            do_switch_to_interval(state, Some(*config))?;
+        }
+    }
+    state.preset = Some(preset);
+    Ok(do_start(state))
+}
+
+/// Apply `edit` to a copy of the settings, whose preset library it changes,
+/// and keep the result if they are still valid.
+pub(crate) fn do_edit_presets<T>(
+    state: &mut AppState,
+    edit: impl FnOnce(&mut Settings) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut settings = state.settings.clone();
+    let out = edit(&mut settings)?;
+    settings.validate()?;
+    state.settings = settings;
+    Ok(out)
+}
+
+/// Carry out a quick command; new timers start right away, named after the
+/// command's label if it has one.
+pub(crate) fn do_run_quick<Tz: TimeZone>(
+    state: &mut AppState,
+    action: &QuickAction,
+    now: &DateTime<Tz>,
+) -> Result<TimerSnapshot, String> {
+    let snapshot = match action {
+        QuickAction::Start => do_start(state),
+        QuickAction::Pause => do_pause(state),
+        QuickAction::Toggle => do_toggle(state),
+        QuickAction::Reset => do_reset(state),
+        QuickAction::Skip => {
+            do_skip_phase(state).ok_or_else(|| "the timer has no phase to skip".to_string())?
+        }
+        QuickAction::TakeBreak => do_take_break(state)?,
+        QuickAction::Dismiss => {
+            do_acknowledge(state).ok_or_else(|| "no finished timer to dismiss".to_string())?
+        }
+        QuickAction::Timer { secs, label } => {
+            do_switch_to_basic(state);
+            do_set_duration(state, *secs);
+            state.preset = label
+                .as_deref()
+                .map(|label| Preset::unsaved(label, PresetKind::Basic { secs: *secs }));
+            do_start(state)
+        }
+        QuickAction::Pomodoro { config } => {
+            do_switch_to_pomodoro_with_config(state, *config);
+            do_start(state)
+        }
+        QuickAction::Program { name } => {
+            do_switch_to_program(state, name)?;
+            do_start(state)
+        }
+        QuickAction::Interval { config } => {
+            do_switch_to_interval(state, Some(*config))?;
+            do_start(state)
+        }
+        QuickAction::Flowtime => {
+            do_switch_to_flowtime(state);
+            do_start(state)
+        }
+        QuickAction::Alarm { time, label } => {
+            do_set_alarm(state, time, None, label.as_deref(), now)?
+        }
+        QuickAction::Preset { id } => do_start_preset(state, id)?,
+    };
+    Ok(snapshot)
+}
+
+pub(crate) fn do_switch_mode(state: &mut AppState) -> TimerSnapshot {
+    match state.active {
+        ActiveTimer::Basic(_) => do_switch_to_pomodoro(state),
+        ActiveTimer::Pomodoro(_) | ActiveTimer::Interval(_) | ActiveTimer::Flowtime(_) => {
+            do_switch_to_basic(state)
+        }
+    }
+}
+
+/// Move on to the next phase, leaving the change in `pending_change` to be
+/// announced. `None` when the timer has no phase to skip.
+pub(crate) fn do_skip_phase(state: &mut AppState) -> Option<TimerSnapshot> {
+    let change = match &mut state.active {
+        ActiveTimer::Pomodoro(t) => {
+            let t = t.skip();
+            (format!("{:?}", t.from), format!("{:?}", t.to))
+        }
+        ActiveTimer::Interval(t) => {
+            let t = t.skip()?;
+            (format!("{:?}", t.from), format!("{:?}", t.to))
+        }
+        ActiveTimer::Flowtime(t) => {
+            let t = t.skip()?;
+            (format!("{:?}", t.from), format!("{:?}", t.to))
+        }
+        ActiveTimer::Basic(_) => return None,
+    };
+    state.pending_change = Some(change);
+    Some(TimerSnapshot::from_state(state))
+}
+
+/// Check `settings`, `save` them and only then apply them, so a failed save
+/// leaves the app running as it was.
+pub(crate) fn do_update_settings(
+    state: &mut AppState,
+    settings: Settings,
+    save: impl FnOnce(&Settings) -> Result<(), String>,
+) -> Result<TimerSnapshot, String> {
+    settings.validate()?;
+    save(&settings)?;
+    state.settings = settings;
+    Ok(TimerSnapshot::from_state(state))
+}
+
+pub(crate) fn do_set_ambient(
+    state: &mut AppState,
+    ambient: AmbientSettings,
+) -> Result<AmbientSettings, String> {
+    ambient.validate()?;
+    state.settings.ambient = ambient.clone();
+    Ok(ambient)
+}
+
+/// Ambient sound plays only while a pomodoro or Flowtime work phase is running.
+pub(crate) fn ambient_playing(state: &AppState) -> bool {
+    match &state.active {
+        ActiveTimer::Pomodoro(t) => {
+            t.phase() == Phase::Work && t.status() == PomodoroStatus::Running
+        }
+        ActiveTimer::Flowtime(t) => {
+            t.phase() == Phase::Work && t.status() == FlowtimeStatus::Running
+        }
+        ActiveTimer::Basic(_) | ActiveTimer::Interval(_) => false,
+    }
+}
+
+/// Dismiss a finished timer's alert. `None` when there is nothing to dismiss.
+pub(crate) fn do_acknowledge(state: &mut AppState) -> Option<TimerSnapshot> {
+    let ActiveTimer::Basic(t) = &mut state.active else {
+        return None;
+    };
+    if !t.acknowledge() {
+        return None;
+    }
+    Some(TimerSnapshot::from_state(state))
+}
+
+/// Snooze the event reported as `from` → `to` in `timer:phase-change`: restart a
+/// finished basic timer, or extend a break that just ended.
+pub(crate) fn do_snooze(
+    state: &mut AppState,
+    from: &str,
+    to: &str,
+    minutes: u32,
+) -> Result<TimerSnapshot, String> {
+    snooze::validate_minutes(minutes)?;
+    if !snooze::is_snoozable(from, to) {
+        return Err(format!("cannot snooze {from} → {to}"));
+    }
+    let secs = minutes * 60;
+    let snoozed = match &mut state.active {
+        ActiveTimer::Basic(t) if from == "timer" => t.snooze(secs),
+        ActiveTimer::Pomodoro(t) if from != "timer" => t.snooze_break(secs),
+        _ => false,
+    };
+    if !snoozed {
+        return Err(format!("{from} → {to} can no longer be snoozed"));
+    }
+    Ok(TimerSnapshot::from_state(state))
+}
+
+/// Actions offered for the phase change `from` → `to` in the current state.
+fn notification_actions(state: &AppState, from: &str, to: &str) -> Vec<NotificationAction> {
+    let snooze = state
+        .settings
+        .snooze
+        .options_minutes
+        .iter()
+        .map(|&minutes| NotificationAction::Snooze(minutes));
+    match (&state.active, from, to) {
+        (ActiveTimer::Basic(_), "timer", "finished") => snooze.collect(),
+        (ActiveTimer::Pomodoro(t), "Work", _) => {
+            let mut actions = Vec::new();
+            if t.status() != PomodoroStatus::Running {
+                actions.push(NotificationAction::StartBreak);
+            }
+            actions.push(NotificationAction::SkipBreak);
+            actions
+        }
+        (ActiveTimer::Pomodoro(t), _, "Work") => {
+            let mut actions: Vec<_> = if t.can_snooze_break() {
+                snooze.collect()
+            } else {
+                Vec::new()
+            };
+            if t.status() != PomodoroStatus::Running {
+                actions.push(NotificationAction::StartNextWork);
+            }
+            actions
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Record a new notification for `from` → `to`, replacing the previous one.
+pub(crate) fn begin_notification(state: &mut AppState, from: &str, to: &str) -> NotificationEvent {
+    let event = NotificationEvent {
+        actions: notification_actions(state, from, to),
+        ..info_notification(state, from, to)
+    };
+    state.notification = Some(event.clone());
+    event
+}
+
+/// A notification that only informs, e.g. the quiet hours summary. It gets
+/// its own id but offers no actions, and leaves the current notification and
+/// its actions in place.
+pub(crate) fn info_notification(state: &mut AppState, from: &str, to: &str) -> NotificationEvent {
+    let event = NotificationEvent {
+        id: state.next_notification_id,
+        from: from.to_string(),
+        to: to.to_string(),
+        actions: Vec::new(),
+        key: Countdown::from_state(state).key,
+    };
+    state.next_notification_id += 1;
+    event
+}
+
+/// Run `action` from notification `event_id`. Rejected when a newer
+/// notification replaced it, the timer has moved on, or it was already used.
+pub(crate) fn do_notification_action(
+    state: &mut AppState,
+    event_id: u64,
+    action: NotificationAction,
+) -> Result<TimerSnapshot, String> {
+    let event = match &state.notification {
+        Some(event) if event.id == event_id => event.clone(),
+        _ => return Err(format!("notification {event_id} is no longer current")),
+    };
+    if Countdown::from_state(state).key != event.key {
+        state.notification = None;
+        return Err(format!("notification {event_id} is no longer current"));
+    }
+    if action != NotificationAction::Dismiss && !event.actions.contains(&action) {
+        return Err(format!("\"{}\" is not available here", action.label()));
+    }
+    let snapshot = match action {
+        NotificationAction::StartBreak | NotificationAction::StartNextWork => do_start(state),
+        NotificationAction::SkipBreak => {
+            do_skip_phase(state).ok_or_else(|| "no break to skip".to_string())?
+        }
+        NotificationAction::Snooze(minutes) => do_snooze(state, &event.from, &event.to, minutes)?,
+        NotificationAction::Dismiss => {
+            do_acknowledge(state);
+            TimerSnapshot::from_state(state)
+        }
+    };
+    state.notification = None;
+    Ok(snapshot)
+}
+
+/// Start a pomodoro phase that is waiting for confirmation. `None` otherwise.
+pub(crate) fn do_confirm_waiting(state: &mut AppState) -> Option<TimerSnapshot> {
+    match &state.active {
+        ActiveTimer::Pomodoro(t) if t.status() == PomodoroStatus::Waiting => Some(do_start(state)),
+        _ => None,
+    }
+}
+
+/// Turn the presenting flag on or off. Turning it on while a break overlay is
+/// up (`overlay_open`) hides that break too. Turning it off brings back the
+/// break deferred or hidden meanwhile, starting it if it waits, unless it was
+/// skipped or reset. Returns whether a break is back for its overlay.
+pub(crate) fn do_set_presenting(
+    state: &mut AppState,
+    presenting: bool,
+    overlay_open: bool,
+) -> (bool, TimerSnapshot) {
+    state.presenting = presenting;
+    let resumed = match (presenting, state.deferred_break.take()) {
+        (false, Some(key)) if Countdown::from_state(state).key == key => {
+            do_confirm_waiting(state);
+            true
+        }
+        (true, key) => {
+            state.deferred_break = key.or_else(|| {
+                current_break(state)
+                    .filter(|_| overlay_open)
+                    .map(|_| Countdown::from_state(state).key)
+            });
+            false
+        }
+        _ => false,
+    };
+    (resumed, TimerSnapshot::from_state(state))
+}
+
+pub(crate) fn do_set_do_not_disturb(state: &mut AppState, enabled: bool) -> TimerSnapshot {
+    state.do_not_disturb = enabled;
+    TimerSnapshot::from_state(state)
+}
+
+/// The break in progress as `(phase, elapsed secs)`, if any: a segment with
+/// a break overlay (see `PomodoroTimer::break_elapsed_secs`).
+fn current_break(state: &AppState) -> Option<(String, u32)> {
+    let (phase, elapsed) = match &state.active {
+        ActiveTimer::Pomodoro(t) => (t.phase(), t.break_elapsed_secs()?),
+        ActiveTimer::Flowtime(t) => (t.phase(), t.break_elapsed_secs()?),
+        _ => return None,
+    };
+    Some((format!("{phase:?}"), elapsed))
+}
+
+/// Try to dismiss the break overlay. A waiting break starts first. In strict
+/// mode this is refused until `strict.min_break_secs` have passed. Returns
+/// whether the overlay may close; closing before the break ends is recorded,
+/// but starting a waiting break is not.
+pub(crate) fn do_dismiss_overlay(
+    state: &mut AppState,
+    history: &mut BreakHistory,
+    now: DateTime<Local>,
+) -> (bool, TimerSnapshot) {
+    let started = do_confirm_waiting(state).is_some();
+    let snapshot = TimerSnapshot::from_state(state);
+    let Some((phase, elapsed_secs)) = current_break(state) else {
+        return (true, snapshot);
+    };
+    if state.settings.strict.dismiss_wait_secs(elapsed_secs) > 0 {
+        return (false, snapshot);
+    }
+    if started {
+        return (true, snapshot);
+    }
+    history.record(BreakEvent {
+        at: now,
+        kind: BreakEventKind::DismissedEarly,
+        phase,
+        elapsed_secs,
+    });
+    (true, snapshot)
+}
+
+/// Run `action`, which ends or leaves the break in progress (pausing,
+/// resetting, skipping, starting something else), under the same rule as
+/// dismissing its overlay: refused in strict mode until
+/// `strict.min_break_secs` have passed, and recorded when the break is left.
+pub(crate) fn do_leave_break<T>(
+    state: &mut AppState,
+    history: &mut BreakHistory,
+    now: DateTime<Local>,
+    action: impl FnOnce(&mut AppState) -> T,
+) -> Result<T, String> {
+    let Some((phase, elapsed_secs)) = current_break(state) else {
+        return Ok(action(state));
+    };
+    let wait = state.settings.strict.dismiss_wait_secs(elapsed_secs);
+    if wait > 0 {
+        return Err(format!(
+            "strict mode: the break cannot end for another {}",
+            duration::format(wait, DurationStyle::Verbose)
+        ));
+    }
+    let out = action(state);
+    if current_break(state).is_none() {
+        history.record(BreakEvent {
+            at: now,
+            kind: BreakEventKind::DismissedEarly,
+            phase,
+            elapsed_secs,
+        });
+    }
+    Ok(out)
+}
+
+/// Push the current break back by `strict.postpone_minutes`, within the
+/// daily allowance.
+pub(crate) fn do_postpone_break(
+    state: &mut AppState,
+    history: &mut BreakHistory,
+    now: DateTime<Local>,
+) -> Result<TimerSnapshot, String> {
+    let strict = &state.settings.strict;
+    let used = history.count_on(now.date_naive(), BreakEventKind::Postponed);
+    if strict.postpones_left(used) == 0 {
+        return Err("no postpones left today".to_string());
+    }
+    let secs = strict.postpone_minutes * 60;
+    let current = current_break(state);
+    // Flowtime breaks are taken on demand, so only pomodoro breaks move
+    let (Some((phase, elapsed_secs)), ActiveTimer::Pomodoro(t)) = (current, &mut state.active)
+    else {
+        return Err("no break to postpone".to_string());
+    };
+    t.postpone_break(secs);
+    history.record(BreakEvent {
+        at: now,
+        kind: BreakEventKind::Postponed,
+        phase,
+        elapsed_secs,
+    });
+    Ok(TimerSnapshot::from_state(state))
 }
 
 pub(crate) fn do_get_snapshot(state: &AppState) -> TimerSnapshot {
-    TimerSnapshot::from_state(&state.active)
+    TimerSnapshot::from_state(state)
 }
 
 // --- Tauri command wrappers ---
 
-/// Emit snapshot to the window and update tray title immediately.
+/// Location of the persisted settings file, if the config dir can be resolved.
+pub(crate) fn settings_path(app: &AppHandle) -> Option<std::path::PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join("settings.json"))
+}
+
+pub(crate) fn history_path(app: &AppHandle) -> Option<std::path::PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join("break_history.json"))
+}
+
+pub(crate) fn schedule_state_path(app: &AppHandle) -> Option<std::path::PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join("schedule_state.json"))
+}
+
+pub(crate) fn save_schedule_state(app: &AppHandle, state: &ScheduleState) {
+    if let Some(path) = schedule_state_path(app) {
+        if let Err(e) = json_file::save(&path, state) {
+            eprintln!("Failed to save schedule state: {}", e);
+        }
+    }
+}
+
+fn save_history(app: &AppHandle, history: &BreakHistory) {
+    if let Some(path) = history_path(app) {
+        if let Err(e) = json_file::save(&path, history) {
+            eprintln!("Failed to save break history: {}", e);
+        }
+    }
+}
+
+/// Emit snapshot to the window and refresh the tray immediately.
 /// Must be called AFTER dropping the AppState lock.
-fn emit_and_update_tray(app: &AppHandle, snapshot: TimerSnapshot) {
+pub(crate) fn emit_and_update_tray(app: &AppHandle, snapshot: TimerSnapshot) {
     let _ = app.emit("timer:tick", &snapshot);
-    update_tray_title(app);
+    crate::runner::announce_pending_change(app);
+    refresh_tray(app);
+}
+
+/// Acknowledge the finished timer and close its notification window. Shared
+/// by the command, the tray menu and the command line.
+pub(crate) fn acknowledge(app: &AppHandle) {
+    let snapshot = {
+        let state = app.state::<Mutex<AppState>>();
+        let mut s = state.lock().unwrap();
+        do_acknowledge(&mut s)
+    };
+    if let Some(win) = app.get_webview_window("notification") {
+        let _ = win.close();
+    }
+    if let Some(snapshot) = snapshot {
+        emit_and_update_tray(app, snapshot);
+    }
+}
+
+/// Lock the state and run `action` on it, through [`do_leave_break`] when
+/// `leaves` says it ends or leaves a break in progress. Shared by the
+/// commands, the tray menu, global shortcuts and quick commands.
+pub(crate) fn with_break_rules<T>(
+    app: &AppHandle,
+    leaves: impl FnOnce(&AppState) -> bool,
+    action: impl FnOnce(&mut AppState) -> T,
+) -> Result<T, String> {
+    let state = app.state::<Mutex<AppState>>();
+    let mut s = state.lock().unwrap();
+    if !leaves(&s) {
+        return Ok(action(&mut s));
+    }
+    let history = app.state::<Mutex<BreakHistory>>();
+    let mut h = history.lock().unwrap();
+    let last = h.events.last().cloned();
+    let out = do_leave_break(&mut s, &mut h, Local::now(), action)?;
+    if h.events.last() != last.as_ref() {
+        save_history(app, &h);
+    }
+    Ok(out)
+}
+
+/// Parse and run a quick command. Shared by the command and the command line.
+pub(crate) fn run_quick(app: &AppHandle, input: &str) -> Result<TimerSnapshot, QuickError> {
+    let action = {
+        let state = app.state::<Mutex<AppState>>();
+        let s = state.lock().unwrap();
+        quick::parse(input, &s.settings)?
+    };
+    let snapshot = with_break_rules(
+        app,
+        |s| action.leaves_break(is_running(s)),
+        |s| do_run_quick(s, &action, &Local::now()),
+    )
+    .and_then(|result| result)
+    .map_err(|e| QuickError::new(e, input))?;
+    if action == QuickAction::Dismiss {
+        if let Some(win) = app.get_webview_window("notification") {
+            let _ = win.close();
+        }
+    }
+    emit_and_update_tray(app, snapshot.clone());
+    Ok(snapshot)
 }
 
 #[tauri::command]
@@ -142,51 +1005,131 @@ pub fn start_timer(app: AppHandle, state: State<'_, Mutex<AppState>>) {
     emit_and_update_tray(&app, snapshot);
 }
 
+/// Refused during a strict break; see [`do_leave_break`].
 #[tauri::command]
-pub fn pause_timer(app: AppHandle, state: State<'_, Mutex<AppState>>) {
-    let snapshot = {
-        let mut s = state.lock().unwrap();
-        do_pause(&mut s)
-    };
+pub fn pause_timer(app: AppHandle) -> Result<(), String> {
+    let snapshot = with_break_rules(&app, |_| true, do_pause)?;
     emit_and_update_tray(&app, snapshot);
+    Ok(())
 }
 
+/// Refused during a strict break; see [`do_leave_break`].
 #[tauri::command]
-pub fn reset_timer(app: AppHandle, state: State<'_, Mutex<AppState>>) {
-    let snapshot = {
-        let mut s = state.lock().unwrap();
-        do_reset(&mut s)
-    };
+pub fn reset_timer(app: AppHandle) -> Result<(), String> {
+    let snapshot = with_break_rules(&app, |_| true, do_reset)?;
     emit_and_update_tray(&app, snapshot);
+    Ok(())
 }
 
+/// Refused during a strict break; see [`do_leave_break`].
 #[tauri::command]
-pub fn set_duration(app: AppHandle, state: State<'_, Mutex<AppState>>, secs: u32) {
-    let snapshot = {
-        let mut s = state.lock().unwrap();
-        do_set_duration(&mut s, secs)
-    };
-    if let Some(s) = snapshot {
-        emit_and_update_tray(&app, s);
+pub fn set_duration(app: AppHandle, secs: u32) -> Result<(), String> {
+    if let Some(snapshot) = with_break_rules(&app, |_| true, |s| do_set_duration(s, secs))? {
+        emit_and_update_tray(&app, snapshot);
     }
+    Ok(())
 }
 
+/// Like `set_duration`, from text such as "25m", "1:30:00" or "in 20 minutes".
 #[tauri::command]
-pub fn switch_to_basic(app: AppHandle, state: State<'_, Mutex<AppState>>) {
-    let snapshot = {
-        let mut s = state.lock().unwrap();
-        do_switch_to_basic(&mut s)
-    };
+pub fn set_duration_text(app: AppHandle, input: String) -> Result<TimerSnapshot, String> {
+    let snapshot = with_break_rules(&app, |_| true, |s| do_set_duration_text(s, &input))??;
+    emit_and_update_tray(&app, snapshot.clone());
+    Ok(snapshot)
+}
+
+/// Seconds in a typed duration, to check input before using it.
+#[tauri::command]
+pub fn parse_duration(input: String) -> Result<u32, String> {
+    duration::parse(&input)
+}
+
+/// Refused during a strict break; see [`do_leave_break`].
+#[tauri::command]
+pub fn switch_to_basic(app: AppHandle) -> Result<(), String> {
+    let snapshot = with_break_rules(&app, |_| true, do_switch_to_basic)?;
     emit_and_update_tray(&app, snapshot);
+    Ok(())
 }
 
+/// Set an alarm for a wall-clock time, replacing the current timer. Refused
+/// during a strict break; see [`do_leave_break`].
 #[tauri::command]
-pub fn switch_to_pomodoro(app: AppHandle, state: State<'_, Mutex<AppState>>) {
-    let snapshot = {
-        let mut s = state.lock().unwrap();
-        do_switch_to_pomodoro(&mut s)
-    };
+pub fn set_alarm(
+    app: AppHandle,
+    time: String,
+    date: Option<String>,
+    name: Option<String>,
+) -> Result<TimerSnapshot, String> {
+    let snapshot = with_break_rules(
+        &app,
+        |_| true,
+        |s| do_set_alarm(s, &time, date.as_deref(), name.as_deref(), &Local::now()),
+    )??;
+    emit_and_update_tray(&app, snapshot.clone());
+    Ok(snapshot)
+}
+
+/// Refused during a strict break; see [`do_leave_break`].
+#[tauri::command]
+pub fn switch_to_pomodoro(app: AppHandle) -> Result<(), String> {
+    let snapshot = with_break_rules(&app, |_| true, do_switch_to_pomodoro)?;
+    emit_and_update_tray(&app, snapshot);
+    Ok(())
+}
+
+/// Switch to pomodoro mode running the named program. Refused during a
+/// strict break; see [`do_leave_break`].
+#[tauri::command]
+pub fn switch_to_program(app: AppHandle, name: String) -> Result<TimerSnapshot, String> {
+    let snapshot = with_break_rules(&app, |_| true, |s| do_switch_to_program(s, &name))??;
+    emit_and_update_tray(&app, snapshot.clone());
+    Ok(snapshot)
+}
+
+/// Switch to interval mode. Without `config`, runs the workout from settings.
+/// Refused during a strict break; see [`do_leave_break`].
+#[tauri::command]
+pub fn switch_to_interval(
+    app: AppHandle,
+    config: Option<IntervalConfig>,
+) -> Result<TimerSnapshot, String> {
+    let snapshot = with_break_rules(&app, |_| true, |s| do_switch_to_interval(s, config))??;
+    emit_and_update_tray(&app, snapshot.clone());
+    Ok(snapshot)
+}
+
+/// Refused during a strict break; see [`do_leave_break`].
+#[tauri::command]
+pub fn switch_to_flowtime(app: AppHandle) -> Result<(), String> {
+    let snapshot = with_break_rules(&app, |_| true, do_switch_to_flowtime)?;
     emit_and_update_tray(&app, snapshot);
+    Ok(())
+}
+
+/// Stop working in Flowtime mode and start the earned break.
+#[tauri::command]
+pub fn take_break(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<TimerSnapshot, String> {
+    let snapshot = do_take_break(&mut state.lock().unwrap())?;
+    emit_and_update_tray(&app, snapshot.clone());
+    Ok(snapshot)
+}
+
+#[tauri::command]
+pub fn get_programs(state: State<'_, Mutex<AppState>>) -> Vec<Program> {
+    do_get_programs(&state.lock().unwrap())
+}
+
+/// Refused during a strict break; see [`do_leave_break`].
+#[tauri::command]
+pub fn skip_phase(app: AppHandle) -> Result<(), String> {
+    if let Some(snapshot) = with_break_rules(&app, |_| true, do_skip_phase)? {
+        emit_and_update_tray(&app, snapshot);
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -195,6 +1138,61 @@ pub fn get_snapshot(state: State<'_, Mutex<AppState>>) -> TimerSnapshot {
     do_get_snapshot(&s)
 }
 
+#[tauri::command]
+pub fn get_settings(state: State<'_, Mutex<AppState>>) -> Settings {
+    state.lock().unwrap().settings.clone()
+}
+
+#[tauri::command]
+pub fn update_settings(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    audio: State<'_, AudioPlayer>,
+    settings: Settings,
+) -> Result<Settings, String> {
+    let (snapshot, playing, shortcuts_changed) = {
+        let mut s = state.lock().unwrap();
+        let shortcuts_changed = s.settings.shortcuts != settings.shortcuts;
+        let snapshot = do_update_settings(&mut s, settings.clone(), |settings| {
+            settings_path(&app).map_or(Ok(()), |path| json_file::save(&path, settings))
+        })?;
+        (snapshot, ambient_playing(&s), shortcuts_changed)
+    };
+    audio.update_ambient(&settings.ambient, playing);
+    if shortcuts_changed {
+        hotkeys::register(&app, &settings.shortcuts);
+    }
+    emit_and_update_tray(&app, snapshot);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn set_ambient(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    audio: State<'_, AudioPlayer>,
+    ambient: AmbientSettings,
+) -> Result<AmbientSettings, String> {
+    let (settings, playing) = {
+        let mut s = state.lock().unwrap();
+        do_set_ambient(&mut s, ambient.clone())?;
+        (s.settings.clone(), ambient_playing(&s))
+    };
+    if let Some(path) = settings_path(&app) {
+        json_file::save(&path, &settings)?;
+    }
+    audio.update_ambient(&ambient, playing);
+    Ok(ambient)
+}
+
+/// Play a sound once with the given settings, e.g. to preview a change before saving.
+#[tauri::command]
+pub fn test_sound(audio: State<'_, AudioPlayer>, sound: EventSound) -> Result<(), String> {
+    sound.validate()?;
+    audio.play(&sound);
+    Ok(())
+}
+
 #[tauri::command]
 pub fn toggle_always_on_top(window: tauri::Window) {
     if let Ok(is_on_top) = window.is_always_on_top() {
@@ -210,21 +1208,307 @@ pub fn dismiss_notification(app: tauri::AppHandle) {
 }
 
 #[tauri::command]
-pub fn dismiss_overlay(app: tauri::AppHandle) {
-    crate::runner::close_overlay_windows(&app);
+pub fn acknowledge_timer(app: AppHandle) {
+    acknowledge(&app);
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::timer::BasicTimer;
-
-    // --- TimerSnapshot conversion tests ---
-
-    #[test]
+/// Handle a click on a notification button. The window closes either way; a
+/// stale click only reports the error.
+#[tauri::command]
+pub fn notification_action(
+    app: AppHandle,
+    event_id: u64,
+    action: NotificationAction,
+) -> Result<TimerSnapshot, String> {
+    let result = with_break_rules(
+        &app,
+        |_| action == NotificationAction::SkipBreak,
+        |s| do_notification_action(s, event_id, action),
+    )
+    .and_then(|result| result);
+    if let Some(win) = app.get_webview_window("notification") {
+        let _ = win.close();
+    }
+    let snapshot = result?;
+    emit_and_update_tray(&app, snapshot.clone());
+    Ok(snapshot)
+}
+
+/// Close the break overlays if allowed; see [`do_dismiss_overlay`]. Its
+/// button is an explicit start, so a break waiting for confirmation begins.
+#[tauri::command]
+pub fn dismiss_overlay(
+    app: tauri::AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    history: State<'_, Mutex<BreakHistory>>,
+) -> bool {
+    let (allowed, snapshot) = {
+        let mut s = state.lock().unwrap();
+        let mut h = history.lock().unwrap();
+        let (allowed, snapshot) = do_dismiss_overlay(&mut s, &mut h, Local::now());
+        if allowed {
+            save_history(&app, &h);
+        }
+        (allowed, snapshot)
+    };
+    emit_and_update_tray(&app, snapshot);
+    if allowed {
+        app.state::<Mutex<OverlayTracker>>()
+            .lock()
+            .unwrap()
+            .dismiss();
+        crate::runner::close_overlay_windows(&app);
+    }
+    allowed
+}
+
+/// Set while presenting or in do-not-disturb: breaks that come due wait and
+/// their overlays are deferred until the flag clears. Turning it on also
+/// hides overlays that are up until then.
+#[tauri::command]
+pub fn set_presenting(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    overlay: State<'_, Mutex<OverlayTracker>>,
+    presenting: bool,
+) -> TimerSnapshot {
+    let snapshot = {
+        let mut s = state.lock().unwrap();
+        let mut tracker = overlay.lock().unwrap();
+        let (resumed, snapshot) = do_set_presenting(&mut s, presenting, tracker.is_open());
+        if presenting {
+            tracker.dismiss();
+        } else if resumed && s.settings.notifications.style.shows_window() {
+            tracker.open(&s);
+        }
+        snapshot
+    };
+    if presenting {
+        crate::runner::close_overlay_windows(&app);
+    }
+    emit_and_update_tray(&app, snapshot.clone());
+    snapshot
+}
+
+/// Turn manual do-not-disturb on or off. Alerts held back meanwhile are
+/// summarized in one notification on the tick after it ends.
+#[tauri::command]
+pub fn set_do_not_disturb(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    enabled: bool,
+) -> TimerSnapshot {
+    let snapshot = do_set_do_not_disturb(&mut state.lock().unwrap(), enabled);
+    emit_and_update_tray(&app, snapshot.clone());
+    snapshot
+}
+
+/// Postpone the current break; the overlays close on the next tick.
+#[tauri::command]
+pub fn postpone_break(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    history: State<'_, Mutex<BreakHistory>>,
+) -> Result<TimerSnapshot, String> {
+    let snapshot = {
+        let mut s = state.lock().unwrap();
+        let mut h = history.lock().unwrap();
+        let snapshot = do_postpone_break(&mut s, &mut h, Local::now())?;
+        save_history(&app, &h);
+        snapshot
+    };
+    emit_and_update_tray(&app, snapshot.clone());
+    Ok(snapshot)
+}
+
+/// Today's postpones and early dismissals.
+#[tauri::command]
+pub fn get_break_stats(history: State<'_, Mutex<BreakHistory>>) -> ComplianceStats {
+    history.lock().unwrap().stats_on(Local::now().date_naive())
+}
+
+/// Edit the presets through [`do_edit_presets`], save the settings and
+/// refresh the tray menu.
+fn edit_presets<T>(
+    app: &AppHandle,
+    state: &Mutex<AppState>,
+    edit: impl FnOnce(&mut Settings) -> Result<T, String>,
+) -> Result<T, String> {
+    let (out, settings) = {
+        let mut s = state.lock().unwrap();
+        let out = do_edit_presets(&mut s, edit)?;
+        (out, s.settings.clone())
+    };
+    if let Some(path) = settings_path(app) {
+        json_file::save(&path, &settings)?;
+    }
+    refresh_tray(app);
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn get_presets(state: State<'_, Mutex<AppState>>) -> Vec<Preset> {
+    state.lock().unwrap().settings.presets.list().to_vec()
+}
+
+/// Add a preset; its id is derived from `id` or the name and made unique.
+#[tauri::command]
+pub fn create_preset(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    preset: Preset,
+) -> Result<Preset, String> {
+    edit_presets(&app, &state, |s| Ok(s.presets.create(preset)))
+}
+
+/// Replace the preset with the same id.
+#[tauri::command]
+pub fn update_preset(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    preset: Preset,
+) -> Result<Preset, String> {
+    edit_presets(&app, &state, |s| s.presets.update(preset))
+}
+
+#[tauri::command]
+pub fn delete_preset(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    id: String,
+) -> Result<Preset, String> {
+    edit_presets(&app, &state, |s| s.presets.delete(&id))
+}
+
+/// Move a preset to position `index`; returns the reordered list.
+#[tauri::command]
+pub fn move_preset(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    id: String,
+    index: usize,
+) -> Result<Vec<Preset>, String> {
+    edit_presets(&app, &state, |s| {
+        s.presets.move_to(&id, index)?;
+        Ok(s.presets.list().to_vec())
+    })
+}
+
+#[tauri::command]
+pub fn set_preset_favorite(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    id: String,
+    favorite: bool,
+) -> Result<Preset, String> {
+    edit_presets(&app, &state, |s| s.presets.set_favorite(&id, favorite))
+}
+
+/// Refused during a strict break; see [`do_leave_break`].
+#[tauri::command]
+pub fn start_preset(app: AppHandle, id: String) -> Result<TimerSnapshot, String> {
+    let snapshot = with_break_rules(&app, |_| true, |s| do_start_preset(s, &id))??;
+    emit_and_update_tray(&app, snapshot.clone());
+    Ok(snapshot)
+}
+
+/// Write the presets with `ids` (all when omitted) to a shareable file,
+/// with the programs they run.
+#[tauri::command]
+pub fn export_presets(
+    state: State<'_, Mutex<AppState>>,
+    path: String,
+    ids: Option<Vec<String>>,
+) -> Result<(), String> {
+    let file = {
+        let settings = &state.lock().unwrap().settings;
+        settings
+            .presets
+            .export(ids.as_deref(), &settings.programs)?
+    };
+    std::fs::write(&path, file.to_json()).map_err(|e| format!("failed to write {path}: {e}"))
+}
+
+/// Add the presets from a file written by `export_presets`, and the programs
+/// they run.
+#[tauri::command]
+pub fn import_presets(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    path: String,
+) -> Result<Vec<Preset>, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("failed to read {path}: {e}"))?;
+    let file = PresetFile::from_json(&text)?;
+    edit_presets(&app, &state, |s| s.presets.import(file, &mut s.programs))
+}
+
+/// Read a quick command such as "tea 4m" without running it.
+#[tauri::command]
+pub fn parse_quick_command(
+    state: State<'_, Mutex<AppState>>,
+    input: String,
+) -> Result<QuickAction, QuickError> {
+    quick::parse(&input, &state.lock().unwrap().settings)
+}
+
+/// Completions for a partly typed quick command.
+#[tauri::command]
+pub fn suggest_quick_commands(state: State<'_, Mutex<AppState>>, input: String) -> Vec<Suggestion> {
+    quick::suggest(&input, &state.lock().unwrap().settings)
+}
+
+#[tauri::command]
+pub fn run_quick_command(app: AppHandle, input: String) -> Result<TimerSnapshot, QuickError> {
+    run_quick(&app, &input)
+}
+
+/// `accelerator` spelled the way it is saved, or why it cannot be used.
+#[tauri::command]
+pub fn check_shortcut(accelerator: String) -> Result<String, String> {
+    shortcuts::normalize(&accelerator)
+}
+
+/// The next `count` (default 10) scheduled occurrences, skipped ones included.
+#[tauri::command]
+pub fn get_upcoming_occurrences(
+    state: State<'_, Mutex<AppState>>,
+    schedules: State<'_, Mutex<ScheduleState>>,
+    count: Option<usize>,
+) -> Vec<Upcoming> {
+    let settings = state.lock().unwrap().settings.schedules.clone();
+    schedules
+        .lock()
+        .unwrap()
+        .upcoming(&settings, &Local::now(), count.unwrap_or(10))
+}
+
+/// Skip the next occurrence of schedule `name`; calling it again skips the
+/// one after.
+#[tauri::command]
+pub fn skip_next_occurrence(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    schedules: State<'_, Mutex<ScheduleState>>,
+    name: String,
+) -> Result<Occurrence, String> {
+    let settings = state.lock().unwrap().settings.schedules.clone();
+    let mut tracker = schedules.lock().unwrap();
+    let occurrence = tracker.skip_next(&settings, &name, &Local::now())?;
+    save_schedule_state(&app, &tracker);
+    Ok(occurrence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::BasicTimer;
+
+    // --- TimerSnapshot conversion tests ---
+
+    #[test]
     fn from_basic_has_correct_fields() {
         let t = BasicTimer::new(300);
-        let snap = TimerSnapshot::from_basic(&t);
+        let snap = TimerSnapshot::from_basic(&t, &TrayTitleSettings::default(), &noon());
         assert_eq!(snap.mode, "basic");
         assert!(!snap.is_running);
         assert!(!snap.is_finished);
@@ -239,7 +1523,7 @@ mod tests {
     #[test]
     fn from_pomodoro_has_correct_fields() {
         let t = PomodoroTimer::new(PomodoroConfig::default());
-        let snap = TimerSnapshot::from_pomodoro(&t);
+        let snap = TimerSnapshot::from_pomodoro(&t, &TrayTitleSettings::default());
         assert_eq!(snap.mode, "pomodoro");
         assert!(!snap.is_running);
         assert!(!snap.is_finished);
@@ -252,15 +1536,18 @@ mod tests {
 
     #[test]
     fn from_state_dispatches_to_basic() {
-        let active = ActiveTimer::Basic(BasicTimer::new(600));
-        let snap = TimerSnapshot::from_state(&active);
+        let state = AppState {
+            active: ActiveTimer::Basic(BasicTimer::new(600)),
+            ..AppState::default()
+        };
+        let snap = TimerSnapshot::from_state(&state);
         assert_eq!(snap.mode, "basic");
     }
 
     #[test]
     fn from_state_dispatches_to_pomodoro() {
-        let active = ActiveTimer::Pomodoro(PomodoroTimer::new(PomodoroConfig::default()));
-        let snap = TimerSnapshot::from_state(&active);
+        let state = AppState::default();
+        let snap = TimerSnapshot::from_state(&state);
         assert_eq!(snap.mode, "pomodoro");
     }
 
@@ -270,6 +1557,7 @@ mod tests {
     fn do_start_sets_running() {
         let mut state = AppState {
             active: ActiveTimer::Basic(BasicTimer::new(300)),
+            ..AppState::default()
         };
         let snap = do_start(&mut state);
         assert!(snap.is_running);
@@ -279,6 +1567,7 @@ mod tests {
     fn do_pause_stops_running() {
         let mut state = AppState {
             active: ActiveTimer::Basic(BasicTimer::new(300)),
+            ..AppState::default()
         };
         do_start(&mut state);
         let snap = do_pause(&mut state);
@@ -289,6 +1578,7 @@ mod tests {
     fn do_reset_restores_full_duration() {
         let mut state = AppState {
             active: ActiveTimer::Basic(BasicTimer::new(300)),
+            ..AppState::default()
         };
         do_start(&mut state);
         // Tick to reduce remaining
@@ -305,6 +1595,7 @@ mod tests {
     fn do_set_duration_on_basic_updates_duration() {
         let mut state = AppState {
             active: ActiveTimer::Basic(BasicTimer::new(300)),
+            ..AppState::default()
         };
         let snap = do_set_duration(&mut state, 600);
         assert!(snap.is_some());
@@ -320,6 +1611,22 @@ mod tests {
         assert!(snap.is_none());
     }
 
+    #[test]
+    fn do_set_duration_text_parses_input() {
+        let mut state = AppState {
+            active: ActiveTimer::Basic(BasicTimer::new(300)),
+            ..AppState::default()
+        };
+        let snap = do_set_duration_text(&mut state, "1h30m").unwrap();
+        assert_eq!(snap.remaining_secs, 5400);
+        assert_eq!(snap.display, "1:30:00");
+        let err = do_set_duration_text(&mut state, "5 parsecs").unwrap_err();
+        assert!(err.contains("unknown unit"));
+        assert_eq!(do_get_snapshot(&state).remaining_secs, 5400);
+        do_switch_to_pomodoro(&mut state);
+        assert!(do_set_duration_text(&mut state, "5m").is_err());
+    }
+
     #[test]
     fn do_switch_to_basic_creates_basic_timer() {
         let mut state = AppState::default(); // starts as pomodoro
@@ -332,6 +1639,7 @@ mod tests {
     fn do_switch_to_pomodoro_creates_pomodoro_timer() {
         let mut state = AppState {
             active: ActiveTimer::Basic(BasicTimer::new(300)),
+            ..AppState::default()
         };
         let snap = do_switch_to_pomodoro(&mut state);
         assert_eq!(snap.mode, "pomodoro");
@@ -343,6 +1651,7 @@ mod tests {
     fn start_pause_reset_sequence() {
         let mut state = AppState {
             active: ActiveTimer::Basic(BasicTimer::new(300)),
+            ..AppState::default()
         };
         let snap = do_start(&mut state);
         assert!(snap.is_running);
@@ -355,6 +1664,892 @@ mod tests {
         assert_eq!(snap.remaining_secs, 300);
     }
 
+    #[test]
+    fn do_toggle_starts_then_pauses() {
+        let mut state = AppState::default();
+        assert!(do_toggle(&mut state).is_running);
+        assert!(!do_toggle(&mut state).is_running);
+        assert!(do_toggle(&mut state).is_running);
+    }
+
+    #[test]
+    fn do_switch_mode_alternates() {
+        let mut state = AppState::default();
+        assert_eq!(do_switch_mode(&mut state).mode, "basic");
+        assert_eq!(do_switch_mode(&mut state).mode, "pomodoro");
+    }
+
+    #[test]
+    fn do_switch_to_pomodoro_with_config_uses_config() {
+        let mut state = AppState::default();
+        let config = PomodoroConfig {
+            work_secs: 50 * 60,
+            short_break_secs: 10 * 60,
+            long_break_secs: 30 * 60,
+            sessions_before_long_break: 3,
+        };
+        let snap = do_switch_to_pomodoro_with_config(&mut state, config);
+        assert_eq!(snap.remaining_secs, 50 * 60);
+        assert_eq!(snap.session_display, Some("○ ○ ○".to_string()));
+    }
+
+    #[test]
+    fn do_skip_phase_advances_pomodoro() {
+        let mut state = AppState::default();
+        let snap = do_skip_phase(&mut state).unwrap();
+        assert_eq!(snap.phase, Some("ShortBreak".to_string()));
+        assert_eq!(snap.remaining_secs, 5 * 60);
+        // Announced like a tick's transition, not dropped
+        assert_eq!(
+            state.pending_change,
+            Some(("Work".to_string(), "ShortBreak".to_string()))
+        );
+    }
+
+    #[test]
+    fn do_skip_phase_on_basic_returns_none() {
+        let mut state = AppState {
+            active: ActiveTimer::Basic(BasicTimer::new(300)),
+            ..AppState::default()
+        };
+        assert!(do_skip_phase(&mut state).is_none());
+        assert_eq!(state.pending_change, None);
+    }
+
+    #[test]
+    fn do_update_settings_changes_tray_title() {
+        let mut state = AppState::default();
+        let mut settings = Settings::default();
+        settings.tray_title.template = "{icon} {mm}m {sessions}".to_string();
+        settings.tray_title.hide_seconds = true;
+        let snap = do_update_settings(&mut state, settings, |_| Ok(())).unwrap();
+        assert_eq!(snap.tray_title, "🍅 25m 0/4");
+    }
+
+    #[test]
+    fn do_update_settings_rejects_invalid_template() {
+        let mut state = AppState::default();
+        let mut settings = Settings::default();
+        settings.tray_title.template = "{icon".to_string();
+        let save = |_: &Settings| -> Result<(), String> { panic!("invalid settings saved") };
+        assert!(do_update_settings(&mut state, settings, save).is_err());
+        assert_eq!(state.settings, Settings::default());
+    }
+
+    #[test]
+    fn do_update_settings_keeps_the_old_ones_when_saving_fails() {
+        let mut state = AppState::default();
+        let mut settings = Settings::default();
+        settings.tray_title.hide_seconds = true;
+        let save = |_: &Settings| Err("disk full".to_string());
+        assert_eq!(
+            do_update_settings(&mut state, settings, save).unwrap_err(),
+            "disk full"
+        );
+        assert_eq!(state.settings, Settings::default());
+    }
+
+    #[test]
+    fn do_set_ambient_stores_valid_settings() {
+        let mut state = AppState::default();
+        let ambient = AmbientSettings {
+            noise: crate::ambient::NoiseKind::Brown,
+            ticking: true,
+            volume: 0.4,
+        };
+        assert_eq!(
+            do_set_ambient(&mut state, ambient.clone()),
+            Ok(ambient.clone())
+        );
+        assert_eq!(state.settings.ambient, ambient);
+
+        let bad = AmbientSettings {
+            volume: 2.0,
+            ..ambient.clone()
+        };
+        assert!(do_set_ambient(&mut state, bad).is_err());
+        assert_eq!(state.settings.ambient, ambient);
+    }
+
+    #[test]
+    fn ambient_plays_only_during_running_work() {
+        let mut state = AppState::default();
+        assert!(!ambient_playing(&state));
+        do_start(&mut state);
+        assert!(ambient_playing(&state));
+        do_skip_phase(&mut state);
+        assert!(!ambient_playing(&state));
+        do_skip_phase(&mut state);
+        do_start(&mut state);
+        assert!(ambient_playing(&state));
+        do_pause(&mut state);
+        assert!(!ambient_playing(&state));
+        do_switch_to_basic(&mut state);
+        do_start(&mut state);
+        assert!(!ambient_playing(&state));
+    }
+
+    #[test]
+    fn do_acknowledge_dismisses_finished_timer_once() {
+        let mut state = AppState {
+            active: ActiveTimer::Basic(BasicTimer::new(1)),
+            ..AppState::default()
+        };
+        assert!(do_acknowledge(&mut state).is_none());
+        do_start(&mut state);
+        if let ActiveTimer::Basic(t) = &mut state.active {
+            t.tick();
+            t.tick();
+        }
+        let snap = do_get_snapshot(&state);
+        assert!(snap.unacknowledged);
+        assert_eq!(snap.finished_secs_ago, Some(1));
+
+        let snap = do_acknowledge(&mut state).unwrap();
+        assert!(!snap.unacknowledged);
+        assert!(snap.is_finished);
+        assert!(do_acknowledge(&mut state).is_none());
+    }
+
+    #[test]
+    fn pomodoro_is_never_unacknowledged() {
+        let mut state = AppState::default();
+        let snap = do_get_snapshot(&state);
+        assert!(!snap.unacknowledged);
+        assert_eq!(snap.finished_secs_ago, None);
+        assert!(do_acknowledge(&mut state).is_none());
+    }
+
+    fn finish_basic(secs: u32) -> AppState {
+        let mut state = AppState {
+            active: ActiveTimer::Basic(BasicTimer::new(secs)),
+            ..AppState::default()
+        };
+        do_start(&mut state);
+        for _ in 0..secs {
+            if let ActiveTimer::Basic(t) = &mut state.active {
+                t.tick();
+            }
+        }
+        state
+    }
+
+    #[test]
+    fn do_snooze_restarts_finished_basic_timer() {
+        let mut state = finish_basic(1);
+        let snap = do_snooze(&mut state, "timer", "finished", 5).unwrap();
+        assert!(snap.is_running);
+        assert_eq!(snap.remaining_secs, 300);
+        assert_eq!(snap.total_secs, 300);
+        assert_eq!(snap.snooze_count, 1);
+        assert!(do_snooze(&mut state, "timer", "finished", 5).is_err());
+    }
+
+    #[test]
+    fn do_snooze_extends_ended_break() {
+        let mut state = AppState::default();
+        do_skip_phase(&mut state);
+        do_skip_phase(&mut state);
+        let snap = do_snooze(&mut state, "ShortBreak", "Work", 10).unwrap();
+        assert_eq!(snap.phase.as_deref(), Some("ShortBreak"));
+        assert_eq!(snap.remaining_secs, 600);
+        assert!(snap.is_running);
+        assert_eq!(snap.snooze_count, 1);
+    }
+
+    #[test]
+    fn do_snooze_rejects_mismatched_events() {
+        let mut state = finish_basic(1);
+        assert!(do_snooze(&mut state, "ShortBreak", "Work", 5).is_err());
+        assert!(do_snooze(&mut state, "Work", "ShortBreak", 5).is_err());
+        assert!(do_snooze(&mut state, "timer", "finished", 0).is_err());
+        let mut pomodoro = AppState::default();
+        assert!(do_snooze(&mut pomodoro, "timer", "finished", 5).is_err());
+        assert!(do_snooze(&mut pomodoro, "ShortBreak", "Work", 5).is_err());
+    }
+
+    /// A pomodoro whose short break just ended into a running work phase.
+    fn break_ended() -> AppState {
+        let mut state = AppState::default();
+        do_start(&mut state);
+        do_skip_phase(&mut state);
+        do_skip_phase(&mut state);
+        state
+    }
+
+    #[test]
+    fn notification_actions_depend_on_event_and_state() {
+        let mut state = finish_basic(1);
+        let event = begin_notification(&mut state, "timer", "finished");
+        assert_eq!(
+            event.actions,
+            vec![
+                NotificationAction::Snooze(5),
+                NotificationAction::Snooze(10)
+            ]
+        );
+
+        let mut state = AppState::default();
+        do_start(&mut state);
+        do_skip_phase(&mut state);
+        let event = begin_notification(&mut state, "Work", "ShortBreak");
+        assert_eq!(event.actions, vec![NotificationAction::SkipBreak]);
+        do_pause(&mut state);
+        let event = begin_notification(&mut state, "Work", "ShortBreak");
+        assert_eq!(
+            event.actions,
+            vec![
+                NotificationAction::StartBreak,
+                NotificationAction::SkipBreak
+            ]
+        );
+
+        let mut state = break_ended();
+        do_pause(&mut state);
+        let event = begin_notification(&mut state, "ShortBreak", "Work");
+        assert_eq!(
+            event.actions.last(),
+            Some(&NotificationAction::StartNextWork)
+        );
+    }
+
+    #[test]
+    fn notification_ids_increase() {
+        let mut state = finish_basic(1);
+        let first = begin_notification(&mut state, "timer", "finished");
+        let second = begin_notification(&mut state, "timer", "finished");
+        assert!(second.id > first.id);
+        assert_eq!(state.notification, Some(second));
+    }
+
+    #[test]
+    fn notification_action_snoozes_once() {
+        let mut state = break_ended();
+        let event = begin_notification(&mut state, "ShortBreak", "Work");
+        let snap =
+            do_notification_action(&mut state, event.id, NotificationAction::Snooze(5)).unwrap();
+        assert_eq!(snap.phase.as_deref(), Some("ShortBreak"));
+        assert_eq!(snap.remaining_secs, 300);
+        assert!(
+            do_notification_action(&mut state, event.id, NotificationAction::Snooze(5)).is_err()
+        );
+    }
+
+    #[test]
+    fn notification_action_rejects_stale_events() {
+        let mut state = AppState::default();
+        do_start(&mut state);
+        do_skip_phase(&mut state);
+        let event = begin_notification(&mut state, "Work", "ShortBreak");
+        // The user skipped the break from the main window meanwhile.
+        do_skip_phase(&mut state);
+        let err = do_notification_action(&mut state, event.id, NotificationAction::SkipBreak)
+            .unwrap_err();
+        assert!(err.contains("no longer current"));
+        assert_eq!(do_get_snapshot(&state).phase.as_deref(), Some("Work"));
+
+        let mut state = finish_basic(1);
+        let old = begin_notification(&mut state, "timer", "finished");
+        begin_notification(&mut state, "timer", "finished");
+        assert!(do_notification_action(&mut state, old.id, NotificationAction::Dismiss).is_err());
+
+        let mut state = finish_basic(1);
+        let event = begin_notification(&mut state, "timer", "finished");
+        do_reset(&mut state);
+        assert!(
+            do_notification_action(&mut state, event.id, NotificationAction::Snooze(5)).is_err()
+        );
+    }
+
+    #[test]
+    fn info_notification_keeps_the_current_one() {
+        let mut state = finish_basic(1);
+        let event = begin_notification(&mut state, "timer", "finished");
+        let summary = info_notification(&mut state, "quiet", "ended");
+        assert_ne!(summary.id, event.id);
+        assert!(summary.actions.is_empty());
+        do_notification_action(&mut state, event.id, NotificationAction::Snooze(5)).unwrap();
+    }
+
+    #[test]
+    fn notification_action_must_be_offered() {
+        let mut state = finish_basic(1);
+        let event = begin_notification(&mut state, "timer", "finished");
+        let err = do_notification_action(&mut state, event.id, NotificationAction::Snooze(7))
+            .unwrap_err();
+        assert!(err.contains("+7 min"));
+        assert!(
+            do_notification_action(&mut state, event.id, NotificationAction::SkipBreak).is_err()
+        );
+    }
+
+    #[test]
+    fn notification_actions_skip_start_and_dismiss() {
+        let mut state = AppState::default();
+        do_start(&mut state);
+        do_skip_phase(&mut state);
+        do_pause(&mut state);
+        let event = begin_notification(&mut state, "Work", "ShortBreak");
+        let snap =
+            do_notification_action(&mut state, event.id, NotificationAction::StartBreak).unwrap();
+        assert!(snap.is_running);
+
+        let event = begin_notification(&mut state, "Work", "ShortBreak");
+        let snap =
+            do_notification_action(&mut state, event.id, NotificationAction::SkipBreak).unwrap();
+        assert_eq!(snap.phase.as_deref(), Some("Work"));
+
+        let mut state = finish_basic(1);
+        let event = begin_notification(&mut state, "timer", "finished");
+        let snap =
+            do_notification_action(&mut state, event.id, NotificationAction::Dismiss).unwrap();
+        assert!(!snap.unacknowledged);
+        assert_eq!(state.notification, None);
+    }
+
+    /// A pomodoro whose work phase just ended into a break waiting to be started.
+    fn break_waiting() -> AppState {
+        let mut state = AppState::default();
+        state.settings.auto_start.breaks = false;
+        do_start(&mut state);
+        let auto_start = state.settings.auto_start;
+        if let ActiveTimer::Pomodoro(t) = &mut state.active {
+            while t.tick_with(auto_start).is_none() {}
+            t.tick_with(auto_start);
+        }
+        state
+    }
+
+    #[test]
+    fn snapshot_shows_waiting_phase() {
+        let state = break_waiting();
+        let snap = do_get_snapshot(&state);
+        assert_eq!(snap.phase.as_deref(), Some("ShortBreak"));
+        assert!(snap.is_waiting);
+        assert!(!snap.is_running);
+        assert_eq!(snap.waiting_secs, Some(1));
+        assert_eq!(snap.total_waiting_secs, 1);
+        assert_eq!(snap.remaining_secs, 5 * 60);
+    }
+
+    #[test]
+    fn confirm_starts_only_a_waiting_phase() {
+        let mut state = break_waiting();
+        let snap = do_confirm_waiting(&mut state).unwrap();
+        assert!(snap.is_running);
+        assert!(!snap.is_waiting);
+        assert!(do_confirm_waiting(&mut state).is_none());
+        assert!(do_confirm_waiting(&mut AppState::default()).is_none());
+    }
+
+    #[test]
+    fn toggle_starts_a_waiting_phase() {
+        let mut state = break_waiting();
+        assert!(do_toggle(&mut state).is_running);
+    }
+
+    #[test]
+    fn waiting_break_offers_start_break() {
+        let mut state = break_waiting();
+        let event = begin_notification(&mut state, "Work", "ShortBreak");
+        assert_eq!(
+            event.actions,
+            vec![
+                NotificationAction::StartBreak,
+                NotificationAction::SkipBreak
+            ]
+        );
+        let snap =
+            do_notification_action(&mut state, event.id, NotificationAction::StartBreak).unwrap();
+        assert!(snap.is_running);
+    }
+
+    // --- Strict mode / postpone tests ---
+
+    fn noon() -> DateTime<Local> {
+        use chrono::TimeZone;
+        Local.with_ymd_and_hms(2024, 3, 4, 12, 0, 0).unwrap()
+    }
+
+    /// A running Flowtime break after 25 minutes of work, `elapsed` seconds in.
+    fn on_flowtime_break(elapsed: u32) -> AppState {
+        let mut state = AppState::default();
+        do_switch_to_flowtime(&mut state);
+        do_start(&mut state);
+        tick_flowtime(&mut state, 25 * 60);
+        do_take_break(&mut state).unwrap();
+        tick_flowtime(&mut state, elapsed);
+        state
+    }
+
+    fn tick_flowtime(state: &mut AppState, secs: u32) {
+        if let ActiveTimer::Flowtime(t) = &mut state.active {
+            for _ in 0..secs {
+                t.tick();
+            }
+        }
+    }
+
+    /// A running short break, `elapsed` seconds in.
+    fn on_break(elapsed: u32) -> AppState {
+        let mut state = AppState::default();
+        do_start(&mut state);
+        do_skip_phase(&mut state);
+        if let ActiveTimer::Pomodoro(t) = &mut state.active {
+            for _ in 0..elapsed {
+                t.tick();
+            }
+        }
+        state
+    }
+
+    #[test]
+    fn dismissing_a_break_records_it() {
+        let mut state = on_break(30);
+        let mut history = BreakHistory::default();
+        let (allowed, _) = do_dismiss_overlay(&mut state, &mut history, noon());
+        assert!(allowed);
+        assert_eq!(history.events.len(), 1);
+        assert_eq!(history.events[0].kind, BreakEventKind::DismissedEarly);
+        assert_eq!(history.events[0].phase, "ShortBreak");
+        assert_eq!(history.events[0].elapsed_secs, 30);
+    }
+
+    #[test]
+    fn strict_mode_blocks_early_dismissal() {
+        let mut state = on_break(30);
+        state.settings.strict.enabled = true;
+        let mut history = BreakHistory::default();
+        let (allowed, _) = do_dismiss_overlay(&mut state, &mut history, noon());
+        assert!(!allowed);
+        assert!(history.events.is_empty());
+
+        let mut state = on_break(120);
+        state.settings.strict.enabled = true;
+        let (allowed, _) = do_dismiss_overlay(&mut state, &mut history, noon());
+        assert!(allowed);
+        assert_eq!(history.events.len(), 1);
+    }
+
+    #[test]
+    fn strict_mode_blocks_leaving_a_break_early() {
+        let leave: [fn(&mut AppState); 3] = [
+            |s| {
+                do_skip_phase(s);
+            },
+            |s| {
+                do_reset(s);
+            },
+            |s| {
+                do_pause(s);
+            },
+        ];
+        for action in leave {
+            let mut state = on_break(30);
+            state.settings.strict.enabled = true;
+            let mut history = BreakHistory::default();
+            let err = do_leave_break(&mut state, &mut history, noon(), action).unwrap_err();
+            assert_eq!(
+                err,
+                "strict mode: the break cannot end for another 1 minute 30 seconds"
+            );
+            let snap = TimerSnapshot::from_state(&state);
+            assert_eq!(snap.phase.as_deref(), Some("ShortBreak"));
+            assert!(snap.is_running);
+            assert!(history.events.is_empty());
+
+            let mut state = on_break(120);
+            state.settings.strict.enabled = true;
+            do_leave_break(&mut state, &mut history, noon(), action).unwrap();
+            assert_eq!(history.events.len(), 1);
+            assert_eq!(history.events[0].kind, BreakEventKind::DismissedEarly);
+            assert_eq!(history.events[0].elapsed_secs, 120);
+        }
+    }
+
+    #[test]
+    fn strict_mode_covers_flowtime_breaks() {
+        let mut state = on_flowtime_break(30);
+        state.settings.strict.enabled = true;
+        let mut history = BreakHistory::default();
+        let skip = |s: &mut AppState| do_skip_phase(s);
+        assert!(do_leave_break(&mut state, &mut history, noon(), skip).is_err());
+        assert_eq!(
+            TimerSnapshot::from_state(&state).phase.as_deref(),
+            Some("ShortBreak")
+        );
+        tick_flowtime(&mut state, 90);
+        do_leave_break(&mut state, &mut history, noon(), skip).unwrap();
+        assert_eq!(history.events.len(), 1);
+        assert_eq!(history.events[0].elapsed_secs, 120);
+    }
+
+    #[test]
+    fn leaving_a_break_is_recorded_only_when_it_ends() {
+        let mut history = BreakHistory::default();
+        let mut state = on_break(10);
+        do_leave_break(&mut state, &mut history, noon(), do_start).unwrap();
+        assert!(history.events.is_empty());
+
+        let mut state = AppState::default();
+        do_start(&mut state);
+        do_leave_break(&mut state, &mut history, noon(), do_skip_phase).unwrap();
+        assert!(history.events.is_empty());
+    }
+
+    #[test]
+    fn dismissing_a_waiting_break_starts_it() {
+        let mut state = break_waiting();
+        let mut history = BreakHistory::default();
+        let (allowed, snap) = do_dismiss_overlay(&mut state, &mut history, noon());
+        assert!(allowed);
+        assert!(snap.is_running);
+        assert!(history.events.is_empty());
+    }
+
+    #[test]
+    fn postpone_runs_extra_work_until_the_limit() {
+        let mut history = BreakHistory::default();
+        for _ in 0..2 {
+            let mut state = on_break(10);
+            let snap = do_postpone_break(&mut state, &mut history, noon()).unwrap();
+            assert_eq!(snap.phase.as_deref(), Some("Work"));
+            assert_eq!(snap.remaining_secs, 5 * 60);
+            assert!(snap.is_running);
+        }
+        let mut state = on_break(10);
+        assert_eq!(
+            do_postpone_break(&mut state, &mut history, noon()).unwrap_err(),
+            "no postpones left today"
+        );
+        assert_eq!(
+            history.count_on(noon().date_naive(), BreakEventKind::Postponed),
+            2
+        );
+    }
+
+    #[test]
+    fn postpone_needs_a_break() {
+        let mut state = AppState::default();
+        do_start(&mut state);
+        let mut history = BreakHistory::default();
+        assert_eq!(
+            do_postpone_break(&mut state, &mut history, noon()).unwrap_err(),
+            "no break to postpone"
+        );
+        assert!(history.events.is_empty());
+    }
+
+    #[test]
+    fn flowtime_breaks_cannot_be_postponed() {
+        let mut state = on_flowtime_break(10);
+        let mut history = BreakHistory::default();
+        assert_eq!(
+            do_postpone_break(&mut state, &mut history, noon()).unwrap_err(),
+            "no break to postpone"
+        );
+        assert!(history.events.is_empty());
+        let snap = TimerSnapshot::from_state(&state);
+        assert_eq!(snap.phase.as_deref(), Some("ShortBreak"));
+        assert!(snap.is_running);
+    }
+
+    // --- Presenting tests ---
+
+    /// A break that came due while presenting and was deferred, as the tick
+    /// loop leaves it.
+    fn break_deferred() -> AppState {
+        let mut state = break_waiting();
+        state.presenting = true;
+        state.deferred_break = Some(Countdown::from_state(&state).key);
+        state
+    }
+
+    #[test]
+    fn deferred_break_starts_when_presenting_ends() {
+        let mut state = break_deferred();
+        let (resumed, snap) = do_set_presenting(&mut state, true, false);
+        assert!(!resumed);
+        assert!(snap.is_waiting);
+        let (resumed, snap) = do_set_presenting(&mut state, false, false);
+        assert!(resumed);
+        assert!(snap.is_running);
+        assert_eq!(snap.phase.as_deref(), Some("ShortBreak"));
+        assert_eq!(state.deferred_break, None);
+    }
+
+    #[test]
+    fn stale_deferred_break_is_dropped() {
+        let mut state = break_deferred();
+        do_skip_phase(&mut state);
+        let (resumed, _) = do_set_presenting(&mut state, false, false);
+        assert!(!resumed);
+        assert_eq!(state.deferred_break, None);
+
+        // A break started by hand while presenting keeps running and gets
+        // its overlay back.
+        let mut state = break_deferred();
+        do_confirm_waiting(&mut state);
+        let (resumed, snap) = do_set_presenting(&mut state, false, false);
+        assert!(resumed);
+        assert!(snap.is_running);
+    }
+
+    #[test]
+    fn break_on_screen_comes_back_when_presenting_ends() {
+        let mut state = on_break(30);
+        let (resumed, _) = do_set_presenting(&mut state, true, true);
+        assert!(!resumed);
+        assert!(state.deferred_break.is_some());
+        let (resumed, snap) = do_set_presenting(&mut state, false, false);
+        assert!(resumed);
+        assert!(snap.is_running);
+
+        // Without an overlay up there is nothing to bring back.
+        let mut state = on_break(30);
+        do_set_presenting(&mut state, true, false);
+        let (resumed, _) = do_set_presenting(&mut state, false, false);
+        assert!(!resumed);
+    }
+
+    // --- Program tests ---
+
+    fn user_program() -> Program {
+        serde_json::from_str(
+            r#"{"name": "50/10", "steps": [
+                {"segment": {"name": "Focus", "secs": 3000, "kind": "focus"}},
+                {"segment": {"name": "Rest", "secs": 600, "kind": "rest"}}
+            ]}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn programs_list_classic_first() {
+        let mut state = AppState::default();
+        state.settings.programs.push(user_program());
+        let names: Vec<String> = do_get_programs(&state)
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["classic".to_string(), "50/10".to_string()]);
+    }
+
+    #[test]
+    fn switch_to_program_runs_its_segments() {
+        let mut state = AppState::default();
+        state.settings.programs.push(user_program());
+        do_switch_to_basic(&mut state);
+        let snap = do_switch_to_program(&mut state, "50/10").unwrap();
+        assert_eq!(snap.mode, "pomodoro");
+        assert_eq!(snap.program.as_deref(), Some("50/10"));
+        assert_eq!(snap.segment.as_deref(), Some("Focus"));
+        assert_eq!(snap.remaining_secs, 50 * 60);
+        let snap = do_skip_phase(&mut state).unwrap();
+        assert_eq!(snap.segment.as_deref(), Some("Rest"));
+        assert_eq!(snap.phase.as_deref(), Some("ShortBreak"));
+        assert!(do_switch_to_program(&mut state, "nope").is_err());
+    }
+
+    #[test]
+    fn flowtime_break_is_earned_from_work() {
+        let mut state = AppState::default();
+        let snap = do_switch_to_flowtime(&mut state);
+        assert_eq!(snap.mode, "flowtime");
+        assert_eq!(snap.phase.as_deref(), Some("Work"));
+        assert!(do_take_break(&mut state).is_err());
+        do_start(&mut state);
+        if let ActiveTimer::Flowtime(t) = &mut state.active {
+            for _ in 0..25 * 60 {
+                t.tick();
+            }
+        }
+        assert!(ambient_playing(&state));
+        let snap = do_take_break(&mut state).unwrap();
+        assert_eq!(snap.phase.as_deref(), Some("ShortBreak"));
+        assert_eq!(snap.remaining_secs, 5 * 60);
+        assert_eq!(
+            state.pending_change,
+            Some(("Work".to_string(), "ShortBreak".to_string()))
+        );
+        let progress = snap.flowtime.unwrap();
+        assert_eq!(progress.completed_sessions, 1);
+        assert_eq!(progress.total_work_secs, 25 * 60);
+        assert!(!ambient_playing(&state));
+        do_switch_to_basic(&mut state);
+        assert!(do_take_break(&mut state).is_err());
+    }
+
+    #[test]
+    fn alarm_runs_as_a_basic_timer() {
+        let now = noon();
+        let mut state = AppState::default();
+        assert!(do_set_alarm(&mut state, "25:00", None, None, &now).is_err());
+        let in_an_hour = now + chrono::Duration::hours(1);
+        let time = in_an_hour.format("%H:%M").to_string();
+        let snap = do_set_alarm(&mut state, &time, None, Some("standup"), &now).unwrap();
+        assert_eq!(snap.mode, "basic");
+        assert!(snap.is_running);
+        assert!(snap.alarm.unwrap().ends_with(&time));
+        assert!((59 * 60..=60 * 60).contains(&snap.remaining_secs));
+        assert_eq!(snap.alarm_name.as_deref(), Some("standup"));
+        assert_eq!(snap.preset, None);
+        // It rings through the basic timer's finish path.
+        if let ActiveTimer::Basic(t) = &mut state.active {
+            t.tick_at(&(in_an_hour + chrono::Duration::minutes(1)));
+        }
+        assert!(do_get_snapshot(&state).unacknowledged);
+        assert!(do_acknowledge(&mut state).is_some());
+    }
+
+    #[test]
+    fn scheduled_actions_start_timers() {
+        let mut state = AppState::default();
+        state.settings.schedules = serde_json::from_str(
+            r#"[
+                {"name": "Focus", "time": "09:00", "action": {"kind": "program", "name": "classic"}},
+                {"name": "Lunch", "time": "12:30", "action": {"kind": "alarm"}},
+                {"name": "Gym", "time": "18:00", "action": {"kind": "interval"}}
+            ]"#,
+        )
+        .unwrap();
+        let now = noon();
+        let occurrence = |name: &str| Occurrence {
+            schedule: name.to_string(),
+            date: now.date_naive(),
+            time: now.time(),
+        };
+
+        let run = |state: &mut AppState, name: &str| {
+            do_run_schedule(state, &occurrence(name), &now).map(Option::unwrap)
+        };
+        let snap = run(&mut state, "Focus").unwrap();
+        assert_eq!(snap.mode, "pomodoro");
+        assert!(snap.is_running);
+
+        // A running timer is left alone.
+        assert!(matches!(
+            do_run_schedule(&mut state, &occurrence("Gym"), &now),
+            Ok(None)
+        ));
+        assert_eq!(do_get_snapshot(&state).mode, "pomodoro");
+        do_pause(&mut state);
+        let snap = run(&mut state, "Gym").unwrap();
+        assert_eq!(snap.mode, "interval");
+        assert!(snap.is_running);
+
+        // An alarm due now rings on the next tick.
+        do_pause(&mut state);
+        let snap = run(&mut state, "Lunch").unwrap();
+        assert_eq!(snap.mode, "basic");
+        assert!(snap.alarm.is_some());
+        if let ActiveTimer::Basic(t) = &mut state.active {
+            t.tick_at(&now);
+        }
+        assert!(do_get_snapshot(&state).unacknowledged);
+
+        assert!(run(&mut state, "Nap").is_err());
+
+        // So is a break waiting to start, which strict mode would not let go.
+        let mut waiting = break_waiting();
+        waiting.settings.strict.enabled = true;
+        waiting.settings.schedules = state.settings.schedules.clone();
+        assert!(matches!(
+            do_run_schedule(&mut waiting, &occurrence("Gym"), &now),
+            Ok(None)
+        ));
+        assert!(do_get_snapshot(&waiting).is_waiting);
+    }
+
+    #[test]
+    fn preset_starts_its_timer_and_names_it() {
+        let mut state = AppState::default();
+        let tea = do_edit_presets(&mut state, |settings| {
+            let mut tea: Preset = serde_json::from_str(
+                r#"{"name": "Tea", "kind": {"kind": "basic", "secs": 180}, "icon": "🍵"}"#,
+            )
+            .unwrap();
+            tea.label = Some("Steeping".to_string());
+            Ok(settings.presets.create(tea))
+        })
+        .unwrap();
+        let snap = do_start_preset(&mut state, &tea.id).unwrap();
+        assert_eq!(snap.mode, "basic");
+        assert_eq!(snap.remaining_secs, 180);
+        assert!(snap.is_running);
+        assert_eq!(snap.preset.as_deref(), Some("Steeping"));
+        assert!(snap.tray_title.starts_with("🍵"));
+
+        // Switching modes leaves the preset behind.
+        let snap = do_switch_to_pomodoro(&mut state);
+        assert_eq!(snap.preset, None);
+        assert!(state.preset.is_none());
+
+        let snap = do_start_preset(&mut state, "pomodoro-50-10").unwrap();
+        assert_eq!(snap.mode, "pomodoro");
+        assert_eq!(snap.remaining_secs, 50 * 60);
+        assert!(do_start_preset(&mut state, "nope").is_err());
+    }
+
+    #[test]
+    fn quick_commands_run_through_the_same_transitions() {
+        let now = noon();
+        let mut state = AppState::default();
+        let run = |state: &mut AppState, input: &str| {
+            let action = quick::parse(input, &state.settings).unwrap();
+            do_run_quick(state, &action, &now)
+        };
+        let snap = run(&mut state, "tea 4m").unwrap();
+        assert_eq!(snap.mode, "basic");
+        assert_eq!(snap.remaining_secs, 240);
+        assert!(snap.is_running);
+        assert_eq!(snap.preset.as_deref(), Some("tea"));
+        assert!(!run(&mut state, "pause").unwrap().is_running);
+        assert!(run(&mut state, "toggle").unwrap().is_running);
+        assert!(run(&mut state, "skip").is_err());
+        assert!(run(&mut state, "dismiss").is_err());
+
+        let snap = run(&mut state, "pomodoro 50/10 x3").unwrap();
+        assert_eq!(snap.mode, "pomodoro");
+        assert_eq!(snap.remaining_secs, 50 * 60);
+        assert_eq!(snap.preset, None);
+        assert_eq!(run(&mut state, "skip").unwrap().remaining_secs, 10 * 60);
+
+        let snap = run(&mut state, "stopwatch").unwrap();
+        assert!(snap.flowtime.is_some());
+        assert!(snap.is_running);
+
+        let time = (now + chrono::Duration::hours(1))
+            .format("%H:%M")
+            .to_string();
+        let snap = run(&mut state, &format!("alarm {time} standup")).unwrap();
+        assert!(snap.alarm.unwrap().ends_with(&time));
+        assert_eq!(snap.alarm_name.as_deref(), Some("standup"));
+        assert_eq!(snap.preset, None);
+
+        let snap = run(&mut state, "timer 5 min").unwrap();
+        assert_eq!(snap.preset.as_deref(), Some("Timer 5 min"));
+        assert_eq!(snap.remaining_secs, 300);
+    }
+
+    #[test]
+    fn invalid_preset_edits_are_rejected() {
+        let mut state = AppState::default();
+        let err = do_edit_presets(&mut state, |settings| {
+            let mut preset = settings.presets.get("timer-5").unwrap().clone();
+            preset.kind = PresetKind::Program {
+                name: "deep".to_string(),
+            };
+            settings.presets.update(preset)
+        })
+        .unwrap_err();
+        assert!(err.starts_with("presets[0].kind.name"));
+        assert_eq!(
+            state.settings.presets.get("timer-5").unwrap().kind,
+            PresetKind::Basic { secs: 300 }
+        );
+    }
+
     // --- AppState default test ---
 
     #[test]
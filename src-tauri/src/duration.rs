@@ -0,0 +1,287 @@
+use serde::{Deserialize, Serialize};
+
+/// How a length of time is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationStyle {
+    /// "05:00", or "1:30:00" from an hour up.
+    #[default]
+    Clock,
+    /// "1h30m", "45s".
+    Compact,
+    /// "1 hour 30 minutes", "45 seconds".
+    Verbose,
+    /// Whole minutes, rounded up so "0m" only shows once time is up: "2m".
+    Minutes,
+}
+
+/// Write `secs` in `style`.
+pub fn format(secs: u32, style: DurationStyle) -> String {
+    let (h, m, s) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    match style {
+        DurationStyle::Clock if h > 0 => format!("{h}:{m:02}:{s:02}"),
+        DurationStyle::Clock => format!("{m:02}:{s:02}"),
+        DurationStyle::Compact => {
+            let parts: Vec<String> = [(h, "h"), (m, "m"), (s, "s")]
+                .into_iter()
+                .filter(|(n, _)| *n > 0)
+                .map(|(n, unit)| format!("{n}{unit}"))
+                .collect();
+            if parts.is_empty() {
+                "0s".to_string()
+            } else {
+                parts.concat()
+            }
+        }
+        DurationStyle::Verbose => {
+            let plural = |n: u32, unit: &str| match n {
+                1 => format!("1 {unit}"),
+                n => format!("{n} {unit}s"),
+            };
+            let parts: Vec<String> = [(h, "hour"), (m, "minute"), (s, "second")]
+                .into_iter()
+                .filter(|(n, _)| *n > 0)
+                .map(|(n, unit)| plural(n, unit))
+                .collect();
+            if parts.is_empty() {
+                "0 seconds".to_string()
+            } else {
+                parts.join(" ")
+            }
+        }
+        DurationStyle::Minutes => format!("{}m", secs.div_ceil(60)),
+    }
+}
+
+/// Read a duration typed by a person, in whole seconds:
+///
+/// - units, in any order and each at most once: `1h30m`, `90s`, `2.5m`,
+///   `1 hour 5 minutes`
+/// - a clock: `mm:ss` or `h:mm:ss`, e.g. `1:30:00`
+/// - a bare number of minutes: `25`
+///
+/// A leading "in" or "for" is ignored, so `in 20 minutes` works too.
+pub fn parse(input: &str) -> Result<u32, String> {
+    let text = input.trim().to_lowercase();
+    let text = match text.split_once(char::is_whitespace) {
+        Some(("in" | "for", rest)) => rest.trim(),
+        _ => text.as_str(),
+    };
+    if text.is_empty() || text == "in" || text == "for" {
+        return Err("empty duration; try e.g. 25m, 1h30m, 90s or 1:30:00".to_string());
+    }
+    let secs = if text.contains(':') {
+        parse_clock(text)
+    } else if text.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        text.parse::<f64>()
+            .map_err(|_| format!("{text:?} is not a number"))
+            .and_then(|minutes| to_secs(minutes * 60.0))
+    } else {
+        parse_units(text)
+    }
+    .map_err(|e| format!("invalid duration {:?}: {e}", input.trim()))?;
+    if secs == 0 {
+        return Err(format!(
+            "invalid duration {:?}: must be at least 1 second",
+            input.trim()
+        ));
+    }
+    Ok(secs)
+}
+
+/// `mm:ss` or `h:mm:ss`; the parts after the first must be below 60.
+fn parse_clock(text: &str) -> Result<u32, String> {
+    let parts: Vec<&str> = text.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return Err("expected mm:ss or h:mm:ss".to_string());
+    }
+    let mut total: u64 = 0;
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+            return Err("expected mm:ss or h:mm:ss".to_string());
+        }
+        let n: u64 = part.parse().map_err(|_| "too long".to_string())?;
+        if i > 0 && (n >= 60 || part.len() != 2) {
+            return Err("minutes and seconds must be two digits below 60".to_string());
+        }
+        total = total * 60 + n;
+    }
+    u32::try_from(total).map_err(|_| "too long".to_string())
+}
+
+/// Runs of `<number><unit>`, optionally separated by spaces, commas or "and".
+fn parse_units(text: &str) -> Result<u32, String> {
+    let mut rest = text;
+    let mut seen: Vec<&str> = Vec::new();
+    let mut total = 0.0;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if let Some(after) = rest.strip_prefix("and ") {
+            rest = after.trim_start();
+        }
+        if rest.is_empty() {
+            break;
+        }
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        if number_len == 0 {
+            return Err(format!("expected a number before {rest:?}"));
+        }
+        let number: f64 = rest[..number_len]
+            .parse()
+            .map_err(|_| format!("{:?} is not a number", &rest[..number_len]))?;
+        rest = rest[number_len..].trim_start();
+        let unit_len = rest
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(rest.len());
+        let (unit, per_unit) = match &rest[..unit_len] {
+            "h" | "hr" | "hrs" | "hour" | "hours" => ("hours", 3600.0),
+            "m" | "min" | "mins" | "minute" | "minutes" => ("minutes", 60.0),
+            "s" | "sec" | "secs" | "second" | "seconds" => ("seconds", 1.0),
+            "" => return Err(format!("missing unit after {number}; use h, m or s")),
+            other => return Err(format!("unknown unit {other:?}; use h, m or s")),
+        };
+        if seen.contains(&unit) {
+            return Err(format!("{unit} given twice"));
+        }
+        seen.push(unit);
+        total += number * per_unit;
+        rest = &rest[unit_len..];
+    }
+    to_secs(total)
+}
+
+/// Round to the nearest second, rejecting what does not fit.
+fn to_secs(secs: f64) -> Result<u32, String> {
+    let secs = secs.round();
+    if !(0.0..=u32::MAX as f64).contains(&secs) {
+        return Err("too long".to_string());
+    }
+    Ok(secs as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_style_adds_hours_when_needed() {
+        assert_eq!(format(0, DurationStyle::Clock), "00:00");
+        assert_eq!(format(125, DurationStyle::Clock), "02:05");
+        assert_eq!(format(59 * 60 + 59, DurationStyle::Clock), "59:59");
+        assert_eq!(format(90 * 60, DurationStyle::Clock), "1:30:00");
+        assert_eq!(format(3661, DurationStyle::Clock), "1:01:01");
+    }
+
+    #[test]
+    fn compact_style_skips_zero_parts() {
+        assert_eq!(format(0, DurationStyle::Compact), "0s");
+        assert_eq!(format(45, DurationStyle::Compact), "45s");
+        assert_eq!(format(300, DurationStyle::Compact), "5m");
+        assert_eq!(format(5400, DurationStyle::Compact), "1h30m");
+        assert_eq!(format(3605, DurationStyle::Compact), "1h5s");
+    }
+
+    #[test]
+    fn verbose_style_pluralizes() {
+        assert_eq!(format(0, DurationStyle::Verbose), "0 seconds");
+        assert_eq!(format(1, DurationStyle::Verbose), "1 second");
+        assert_eq!(format(61, DurationStyle::Verbose), "1 minute 1 second");
+        assert_eq!(format(7200, DurationStyle::Verbose), "2 hours");
+        assert_eq!(
+            format(3600 + 120 + 3, DurationStyle::Verbose),
+            "1 hour 2 minutes 3 seconds"
+        );
+    }
+
+    #[test]
+    fn minutes_style_rounds_up() {
+        assert_eq!(format(0, DurationStyle::Minutes), "0m");
+        assert_eq!(format(1, DurationStyle::Minutes), "1m");
+        assert_eq!(format(60, DurationStyle::Minutes), "1m");
+        assert_eq!(format(61, DurationStyle::Minutes), "2m");
+        assert_eq!(format(90 * 60, DurationStyle::Minutes), "90m");
+    }
+
+    #[test]
+    fn style_serializes_as_snake_case() {
+        let json = serde_json::to_string(&DurationStyle::Minutes).unwrap();
+        assert_eq!(json, "\"minutes\"");
+        assert_eq!(DurationStyle::default(), DurationStyle::Clock);
+    }
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(parse("90s"), Ok(90));
+        assert_eq!(parse("5m"), Ok(300));
+        assert_eq!(parse("1h30m"), Ok(5400));
+        assert_eq!(parse("1h 30m 15s"), Ok(5415));
+        assert_eq!(parse("30m1h"), Ok(5400));
+        assert_eq!(parse("2.5m"), Ok(150));
+        assert_eq!(parse("1.5 hours"), Ok(5400));
+        assert_eq!(parse("1 hour, 5 minutes and 10 seconds"), Ok(3910));
+        assert_eq!(parse("  10 MIN  "), Ok(600));
+        assert_eq!(parse("1 sec"), Ok(1));
+    }
+
+    #[test]
+    fn parses_clocks() {
+        assert_eq!(parse("1:30"), Ok(90));
+        assert_eq!(parse("25:00"), Ok(1500));
+        assert_eq!(parse("1:30:00"), Ok(5400));
+        assert_eq!(parse("100:00"), Ok(6000));
+    }
+
+    #[test]
+    fn bare_numbers_are_minutes() {
+        assert_eq!(parse("25"), Ok(1500));
+        assert_eq!(parse("0.5"), Ok(30));
+    }
+
+    #[test]
+    fn ignores_in_and_for() {
+        assert_eq!(parse("in 20 minutes"), Ok(1200));
+        assert_eq!(parse("for 1h"), Ok(3600));
+        assert_eq!(parse("In 1:00"), Ok(60));
+    }
+
+    #[test]
+    fn reports_what_is_wrong() {
+        let err = |input: &str| parse(input).unwrap_err();
+        assert!(err("").starts_with("empty duration"));
+        assert!(err("in ").starts_with("empty duration"));
+        assert_eq!(
+            err("5x"),
+            "invalid duration \"5x\": unknown unit \"x\"; use h, m or s"
+        );
+        assert!(err("5m 10").contains("missing unit after 10"));
+        assert!(err("m").contains("expected a number before \"m\""));
+        assert!(err("1.2.3m").contains("\"1.2.3\" is not a number"));
+        assert!(err("5m 5m").contains("minutes given twice"));
+        assert!(err("1:75").contains("below 60"));
+        assert!(err("1:5").contains("two digits"));
+        assert!(err("1:2:3:4").contains("expected mm:ss or h:mm:ss"));
+        assert!(err("1::00").contains("expected mm:ss or h:mm:ss"));
+        assert!(err("0s").contains("at least 1 second"));
+        assert!(err("00:00").contains("at least 1 second"));
+        assert!(err("99999999h").contains("too long"));
+        assert!(err("-5m").contains("expected a number"));
+        assert!(err("-5").contains("expected a number"));
+        assert!(err("1.2.3").contains("\"1.2.3\" is not a number"));
+        assert!(err("inf").contains("expected a number"));
+    }
+
+    #[test]
+    fn formats_back_to_what_parses() {
+        for secs in [1, 59, 60, 61, 3599, 3600, 5415, 86_400] {
+            for style in [
+                DurationStyle::Clock,
+                DurationStyle::Compact,
+                DurationStyle::Verbose,
+            ] {
+                assert_eq!(parse(&format(secs, style)), Ok(secs), "{style:?} {secs}");
+            }
+        }
+    }
+}
@@ -0,0 +1,467 @@
+use serde::{Deserialize, Serialize};
+
+use crate::duration::{self, DurationStyle};
+use crate::pomodoro::{AutoStart, Phase, PhaseTransition};
+
+pub const MAX_BREAK_SECS: u32 = 2 * 60 * 60;
+pub const MAX_DIVISOR: u32 = 60;
+pub const MAX_TIERS: usize = 16;
+
+/// At least `work_secs` of work earns `break_secs` of break.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreakTier {
+    pub work_secs: u32,
+    pub break_secs: u32,
+}
+
+/// How a break's length follows from the work before it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BreakRule {
+    /// Work time divided by `divisor`, e.g. 5 for a 10-minute break after 50.
+    Ratio { divisor: u32 },
+    /// The last tier reached, or the first one for shorter work. Tiers are
+    /// sorted by `work_secs`.
+    Tiers { tiers: Vec<BreakTier> },
+}
+
+impl BreakRule {
+    fn break_secs(&self, worked_secs: u32) -> u32 {
+        match self {
+            BreakRule::Ratio { divisor } => worked_secs / (*divisor).max(1),
+            BreakRule::Tiers { tiers } => tiers
+                .iter()
+                .take_while(|t| t.work_secs <= worked_secs)
+                .last()
+                .or(tiers.first())
+                .map_or(0, |t| t.break_secs),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            BreakRule::Ratio { divisor } => {
+                if !(1..=MAX_DIVISOR).contains(divisor) {
+                    return Err(format!("divisor: must be between 1 and {MAX_DIVISOR}"));
+                }
+            }
+            BreakRule::Tiers { tiers } => {
+                if tiers.is_empty() || tiers.len() > MAX_TIERS {
+                    return Err(format!("tiers: must have 1 to {MAX_TIERS} entries"));
+                }
+                for (i, tier) in tiers.iter().enumerate() {
+                    if i > 0 && tier.work_secs <= tiers[i - 1].work_secs {
+                        return Err(format!("tiers[{i}].work_secs: must be increasing"));
+                    }
+                    if tier.break_secs > MAX_BREAK_SECS {
+                        return Err(format!(
+                            "tiers[{i}].break_secs: must be at most {MAX_BREAK_SECS}"
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How breaks are earned in Flowtime mode. The rule's result is kept between
+/// `min_break_secs` and `max_break_secs`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlowtimeSettings {
+    pub rule: BreakRule,
+    pub min_break_secs: u32,
+    pub max_break_secs: u32,
+}
+
+impl Default for FlowtimeSettings {
+    fn default() -> Self {
+        Self {
+            rule: BreakRule::Ratio { divisor: 5 },
+            min_break_secs: 60,
+            max_break_secs: 30 * 60,
+        }
+    }
+}
+
+impl FlowtimeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        self.rule.validate().map_err(|e| format!("rule.{e}"))?;
+        if self.max_break_secs == 0 || self.max_break_secs > MAX_BREAK_SECS {
+            return Err(format!(
+                "max_break_secs: must be between 1 and {MAX_BREAK_SECS}"
+            ));
+        }
+        if self.min_break_secs > self.max_break_secs {
+            return Err("min_break_secs: must not exceed max_break_secs".to_string());
+        }
+        Ok(())
+    }
+
+    /// The break earned by `worked_secs` of work; never zero.
+    pub fn break_for(&self, worked_secs: u32) -> u32 {
+        self.rule
+            .break_secs(worked_secs)
+            .clamp(self.min_break_secs, self.max_break_secs)
+            .max(1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum FlowtimeStatus {
+    Idle,
+    Running,
+    Paused,
+}
+
+/// Where a Flowtime session stands, for the window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FlowtimeProgress {
+    /// Work so far in the current work phase.
+    pub worked_secs: u32,
+    /// The break that work has earned so far, or the current break's length.
+    pub break_secs: u32,
+    pub completed_sessions: u32,
+    pub total_work_secs: u32,
+}
+
+/// Open-ended work phases that count up, each followed by a break earned
+/// from its length (see [`FlowtimeSettings::break_for`]).
+#[derive(Debug, Clone, Serialize)]
+pub struct FlowtimeTimer {
+    settings: FlowtimeSettings,
+    /// [`Phase::Work`] or [`Phase::ShortBreak`].
+    phase: Phase,
+    status: FlowtimeStatus,
+    worked_secs: u32,
+    /// Length of the current break.
+    break_secs: u32,
+    remaining_secs: u32,
+    completed_sessions: u32,
+    total_work_secs: u32,
+}
+
+impl FlowtimeTimer {
+    pub fn new(settings: FlowtimeSettings) -> Self {
+        Self {
+            settings,
+            phase: Phase::Work,
+            status: FlowtimeStatus::Idle,
+            worked_secs: 0,
+            break_secs: 0,
+            remaining_secs: 0,
+            completed_sessions: 0,
+            total_work_secs: 0,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn status(&self) -> FlowtimeStatus {
+        self.status
+    }
+
+    pub fn worked_secs(&self) -> u32 {
+        self.worked_secs
+    }
+
+    /// Time left in the current break; zero while working.
+    pub fn remaining_secs(&self) -> u32 {
+        self.remaining_secs
+    }
+
+    /// Length of the current break; zero while working, which has no end.
+    pub fn phase_duration_secs(&self) -> u32 {
+        if self.phase == Phase::Work {
+            0
+        } else {
+            self.break_secs
+        }
+    }
+
+    pub fn completed_sessions(&self) -> u32 {
+        self.completed_sessions
+    }
+
+    /// Seconds of the current break that have passed, while one is running.
+    pub fn break_elapsed_secs(&self) -> Option<u32> {
+        (self.phase != Phase::Work && self.status == FlowtimeStatus::Running)
+            .then(|| self.break_secs - self.remaining_secs)
+    }
+
+    /// The break a break taken now would last.
+    pub fn earned_break_secs(&self) -> u32 {
+        if self.worked_secs == 0 {
+            0
+        } else {
+            self.settings.break_for(self.worked_secs)
+        }
+    }
+
+    pub fn progress(&self) -> FlowtimeProgress {
+        FlowtimeProgress {
+            worked_secs: self.worked_secs,
+            break_secs: if self.phase == Phase::Work {
+                self.earned_break_secs()
+            } else {
+                self.break_secs
+            },
+            completed_sessions: self.completed_sessions,
+            total_work_secs: self.total_work_secs,
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.status = FlowtimeStatus::Running;
+    }
+
+    pub fn pause(&mut self) {
+        if self.status == FlowtimeStatus::Running {
+            self.status = FlowtimeStatus::Paused;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.settings.clone());
+    }
+
+    /// Tick with work starting automatically after a break.
+    pub fn tick(&mut self) -> Option<PhaseTransition> {
+        self.tick_with(AutoStart::default())
+    }
+
+    /// Count work up, or a break down, by one second. When a break ends, the
+    /// next work phase starts unless `auto_start.work` is off, in which case
+    /// it waits for [`FlowtimeTimer::start`].
+    pub fn tick_with(&mut self, auto_start: AutoStart) -> Option<PhaseTransition> {
+        if self.status != FlowtimeStatus::Running {
+            return None;
+        }
+        if self.phase == Phase::Work {
+            self.worked_secs = self.worked_secs.saturating_add(1);
+            return None;
+        }
+        self.remaining_secs = self.remaining_secs.saturating_sub(1);
+        if self.remaining_secs > 0 {
+            return None;
+        }
+        let transition = self.end_break();
+        if !auto_start.work {
+            self.status = FlowtimeStatus::Idle;
+        }
+        Some(transition)
+    }
+
+    /// End the work phase and start the break it earned. `None` outside work
+    /// or before any work was done.
+    pub fn take_break(&mut self) -> Option<PhaseTransition> {
+        if self.phase != Phase::Work || self.worked_secs == 0 {
+            return None;
+        }
+        self.break_secs = self.earned_break_secs();
+        self.remaining_secs = self.break_secs;
+        self.completed_sessions += 1;
+        self.total_work_secs += self.worked_secs;
+        self.worked_secs = 0;
+        self.phase = Phase::ShortBreak;
+        self.status = FlowtimeStatus::Running;
+        Some(PhaseTransition {
+            from: Phase::Work,
+            to: Phase::ShortBreak,
+        })
+    }
+
+    /// Take a break while working, which starts it like [`Self::take_break`],
+    /// or end the break early, keeping the running/paused status.
+    pub fn skip(&mut self) -> Option<PhaseTransition> {
+        if self.phase == Phase::Work {
+            self.take_break()
+        } else {
+            Some(self.end_break())
+        }
+    }
+
+    fn end_break(&mut self) -> PhaseTransition {
+        self.phase = Phase::Work;
+        self.break_secs = 0;
+        self.remaining_secs = 0;
+        PhaseTransition {
+            from: Phase::ShortBreak,
+            to: Phase::Work,
+        }
+    }
+
+    /// Time worked while working, time left during a break.
+    pub fn display_secs(&self) -> u32 {
+        if self.phase == Phase::Work {
+            self.worked_secs
+        } else {
+            self.remaining_secs
+        }
+    }
+
+    pub fn display(&self) -> String {
+        duration::format(self.display_secs(), DurationStyle::Clock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn work_for(timer: &mut FlowtimeTimer, secs: u32) {
+        timer.start();
+        for _ in 0..secs {
+            assert_eq!(timer.tick(), None);
+        }
+    }
+
+    #[test]
+    fn work_counts_up_until_a_break_is_taken() {
+        let mut timer = FlowtimeTimer::new(FlowtimeSettings::default());
+        assert_eq!(timer.take_break(), None);
+        work_for(&mut timer, 50 * 60);
+        assert_eq!(timer.worked_secs(), 50 * 60);
+        assert_eq!(timer.display(), "50:00");
+        assert_eq!(timer.earned_break_secs(), 10 * 60);
+        assert_eq!(
+            timer.take_break(),
+            Some(PhaseTransition {
+                from: Phase::Work,
+                to: Phase::ShortBreak
+            })
+        );
+        assert_eq!(timer.remaining_secs(), 10 * 60);
+        assert_eq!(timer.phase_duration_secs(), 10 * 60);
+        assert_eq!(timer.completed_sessions(), 1);
+        assert_eq!(timer.progress().total_work_secs, 50 * 60);
+    }
+
+    #[test]
+    fn break_counts_down_back_to_open_ended_work() {
+        let mut timer = FlowtimeTimer::new(FlowtimeSettings::default());
+        work_for(&mut timer, 5 * 60);
+        assert_eq!(timer.break_elapsed_secs(), None);
+        timer.take_break();
+        for _ in 1..60 {
+            assert_eq!(timer.tick(), None);
+        }
+        assert_eq!(timer.break_elapsed_secs(), Some(59));
+        assert_eq!(
+            timer.tick(),
+            Some(PhaseTransition {
+                from: Phase::ShortBreak,
+                to: Phase::Work
+            })
+        );
+        assert_eq!(timer.status(), FlowtimeStatus::Running);
+        assert_eq!(timer.worked_secs(), 0);
+        timer.tick();
+        assert_eq!(timer.worked_secs(), 1);
+    }
+
+    #[test]
+    fn work_can_wait_after_a_break() {
+        let mut timer = FlowtimeTimer::new(FlowtimeSettings::default());
+        work_for(&mut timer, 60);
+        timer.take_break();
+        let manual = AutoStart {
+            breaks: true,
+            work: false,
+        };
+        while timer.tick_with(manual).is_none() {}
+        assert_eq!(timer.status(), FlowtimeStatus::Idle);
+        timer.tick_with(manual);
+        assert_eq!(timer.worked_secs(), 0);
+    }
+
+    #[test]
+    fn ratio_is_clamped() {
+        let settings = FlowtimeSettings::default();
+        assert_eq!(settings.break_for(60), 60);
+        assert_eq!(settings.break_for(25 * 60), 5 * 60);
+        assert_eq!(settings.break_for(4 * 60 * 60), 30 * 60);
+    }
+
+    #[test]
+    fn tiers_pick_the_last_reached() {
+        let settings: FlowtimeSettings = serde_json::from_str(
+            r#"{"rule": {"kind": "tiers", "tiers": [
+                {"work_secs": 0, "break_secs": 300},
+                {"work_secs": 1500, "break_secs": 480},
+                {"work_secs": 3000, "break_secs": 600},
+                {"work_secs": 5400, "break_secs": 900}
+            ]}}"#,
+        )
+        .unwrap();
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.break_for(10 * 60), 300);
+        assert_eq!(settings.break_for(25 * 60), 480);
+        assert_eq!(settings.break_for(89 * 60), 600);
+        assert_eq!(settings.break_for(3 * 60 * 60), 900);
+    }
+
+    #[test]
+    fn skip_takes_or_ends_the_break() {
+        let mut timer = FlowtimeTimer::new(FlowtimeSettings::default());
+        assert_eq!(timer.skip(), None);
+        work_for(&mut timer, 120);
+        timer.pause();
+        assert_eq!(timer.skip().map(|t| t.to), Some(Phase::ShortBreak));
+        timer.pause();
+        assert_eq!(timer.skip().map(|t| t.to), Some(Phase::Work));
+        assert_eq!(timer.status(), FlowtimeStatus::Paused);
+    }
+
+    #[test]
+    fn reset_clears_the_sessions() {
+        let mut timer = FlowtimeTimer::new(FlowtimeSettings::default());
+        work_for(&mut timer, 120);
+        timer.take_break();
+        timer.reset();
+        assert_eq!(timer.phase(), Phase::Work);
+        assert_eq!(timer.status(), FlowtimeStatus::Idle);
+        assert_eq!(timer.completed_sessions(), 0);
+    }
+
+    #[test]
+    fn validation() {
+        assert!(FlowtimeSettings::default().validate().is_ok());
+        let zero = FlowtimeSettings {
+            rule: BreakRule::Ratio { divisor: 0 },
+            ..FlowtimeSettings::default()
+        };
+        assert!(zero.validate().unwrap_err().starts_with("rule.divisor:"));
+        let unsorted = FlowtimeSettings {
+            rule: BreakRule::Tiers {
+                tiers: vec![
+                    BreakTier {
+                        work_secs: 600,
+                        break_secs: 60,
+                    },
+                    BreakTier {
+                        work_secs: 600,
+                        break_secs: 120,
+                    },
+                ],
+            },
+            ..FlowtimeSettings::default()
+        };
+        assert_eq!(
+            unsorted.validate().unwrap_err(),
+            "rule.tiers[1].work_secs: must be increasing"
+        );
+        let inverted = FlowtimeSettings {
+            min_break_secs: 600,
+            max_break_secs: 300,
+            ..FlowtimeSettings::default()
+        };
+        assert!(inverted
+            .validate()
+            .unwrap_err()
+            .starts_with("min_break_secs:"));
+    }
+}
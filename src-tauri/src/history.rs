@@ -0,0 +1,125 @@
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Events older than this are dropped when a new one is recorded.
+pub const KEEP_DAYS: i64 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakEventKind {
+    /// The break was pushed back with "postpone".
+    Postponed,
+    /// The overlay was dismissed before the break ended.
+    DismissedEarly,
+}
+
+/// A break that was not taken in full.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreakEvent {
+    pub at: DateTime<Local>,
+    pub kind: BreakEventKind,
+    pub phase: String,
+    /// How much of the break had passed.
+    pub elapsed_secs: u32,
+}
+
+/// Postpones and early dismissals, persisted as JSON next to the settings
+/// (see [`crate::json_file`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreakHistory {
+    pub events: Vec<BreakEvent>,
+}
+
+/// Payload of the `get_break_stats` command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComplianceStats {
+    pub date: NaiveDate,
+    pub postponed: u32,
+    pub dismissed_early: u32,
+}
+
+impl BreakHistory {
+    pub fn record(&mut self, event: BreakEvent) {
+        let cutoff = event.at - Duration::days(KEEP_DAYS);
+        self.events.retain(|e| e.at >= cutoff);
+        self.events.push(event);
+    }
+
+    pub fn count_on(&self, date: NaiveDate, kind: BreakEventKind) -> u32 {
+        self.events
+            .iter()
+            .filter(|e| e.kind == kind && e.at.date_naive() == date)
+            .count() as u32
+    }
+
+    pub fn stats_on(&self, date: NaiveDate) -> ComplianceStats {
+        ComplianceStats {
+            date,
+            postponed: self.count_on(date, BreakEventKind::Postponed),
+            dismissed_early: self.count_on(date, BreakEventKind::DismissedEarly),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_file;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap()
+    }
+
+    fn event(at: DateTime<Local>, kind: BreakEventKind) -> BreakEvent {
+        BreakEvent {
+            at,
+            kind,
+            phase: "ShortBreak".to_string(),
+            elapsed_secs: 30,
+        }
+    }
+
+    #[test]
+    fn counts_per_local_day() {
+        let mut history = BreakHistory::default();
+        history.record(event(at(4, 9), BreakEventKind::Postponed));
+        history.record(event(at(4, 15), BreakEventKind::Postponed));
+        history.record(event(at(4, 16), BreakEventKind::DismissedEarly));
+        history.record(event(at(5, 9), BreakEventKind::Postponed));
+        let stats = history.stats_on(at(4, 12).date_naive());
+        assert_eq!(stats.postponed, 2);
+        assert_eq!(stats.dismissed_early, 1);
+        assert_eq!(
+            history.count_on(at(5, 0).date_naive(), BreakEventKind::Postponed),
+            1
+        );
+    }
+
+    #[test]
+    fn old_events_are_pruned() {
+        let mut history = BreakHistory::default();
+        history.record(event(at(1, 9), BreakEventKind::Postponed));
+        history.record(event(
+            at(1, 9) + Duration::days(KEEP_DAYS + 1),
+            BreakEventKind::Postponed,
+        ));
+        assert_eq!(history.events.len(), 1);
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let path = std::env::temp_dir()
+            .join(format!("mac-timer-history-{}", std::process::id()))
+            .join("break_history.json");
+        let mut history = BreakHistory::default();
+        history.record(event(at(4, 9), BreakEventKind::DismissedEarly));
+        json_file::save(&path, &history).unwrap();
+        assert_eq!(
+            json_file::load::<BreakHistory>(&path, "break history"),
+            history
+        );
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{plugin::TauriPlugin, AppHandle, Emitter, Manager, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::shortcuts::{ShortcutAction, ShortcutSettings};
+use crate::tray;
+
+/// The action of each registered shortcut by [`Shortcut::id`], filled in by
+/// [`register`] so a press is only a lookup.
+#[derive(Debug, Default)]
+pub struct HotkeyBindings(HashMap<u32, ShortcutAction>);
+
+/// The global shortcut plugin, running the action bound to each shortcut
+/// when it is pressed.
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, shortcut, event| {
+            if event.state() != ShortcutState::Pressed {
+                return;
+            }
+            if let Some(action) = action_for(app, shortcut) {
+                run(app, action);
+            }
+        })
+        .build()
+}
+
+fn action_for(app: &AppHandle, pressed: &Shortcut) -> Option<ShortcutAction> {
+    let bindings = app.state::<Mutex<HotkeyBindings>>();
+    let bindings = bindings.lock().unwrap();
+    bindings.0.get(&pressed.id()).copied()
+}
+
+/// Run `action` through the same code as the tray menu.
+fn run(app: &AppHandle, action: ShortcutAction) {
+    match action.tray_action() {
+        Some(tray_action) => tray::run_action(app, tray_action),
+        None => {
+            if let Some(window) = app.get_webview_window("main") {
+                if !window.is_visible().unwrap_or(false) {
+                    tray::toggle_window(app, None);
+                }
+                let _ = window.set_focus();
+            }
+            let _ = app.emit("quick:open", ());
+        }
+    }
+}
+
+/// Replace the registered shortcuts with the ones in `settings`. Shortcuts
+/// the system refuses, e.g. because another app holds them, are reported
+/// through the `shortcuts:failed` event.
+pub fn register(app: &AppHandle, settings: &ShortcutSettings) {
+    let manager = app.global_shortcut();
+    if let Err(e) = manager.unregister_all() {
+        eprintln!("Failed to unregister shortcuts: {e}");
+    }
+    let mut bound = HashMap::new();
+    let mut failed = Vec::new();
+    for (action, accelerator) in settings.bindings() {
+        let registered = accelerator
+            .parse::<Shortcut>()
+            .map_err(|e| e.to_string())
+            .and_then(|shortcut| {
+                manager.register(shortcut).map_err(|e| e.to_string())?;
+                Ok(shortcut)
+            });
+        match registered {
+            Ok(shortcut) => {
+                bound.insert(shortcut.id(), action);
+            }
+            Err(error) => failed.push(format!("{accelerator}: {error}")),
+        }
+    }
+    *app.state::<Mutex<HotkeyBindings>>().lock().unwrap() = HotkeyBindings(bound);
+    if !failed.is_empty() {
+        eprintln!("Failed to register shortcuts: {}", failed.join(", "));
+        let _ = app.emit("shortcuts:failed", &failed);
+    }
+}
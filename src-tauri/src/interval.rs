@@ -0,0 +1,546 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{EventSound, SoundSource};
+use crate::audio_decode::BundledSound;
+use crate::duration::{self, DurationStyle};
+
+pub const MAX_SEGMENT_SECS: u32 = 60 * 60;
+pub const MAX_ROUNDS: u32 = 100;
+pub const MAX_SETS: u32 = 20;
+/// Seconds before a segment ends that each get a countdown beep.
+pub const COUNTDOWN_SECS: u32 = 3;
+
+/// Shape of an interval workout. Zero-length warm-up, rests, set rests and
+/// cool-down are left out. The default is a single Tabata set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntervalConfig {
+    pub warmup_secs: u32,
+    pub work_secs: u32,
+    pub rest_secs: u32,
+    pub rounds: u32,
+    pub sets: u32,
+    /// Rest between sets, in place of the last round's rest.
+    pub set_rest_secs: u32,
+    pub cooldown_secs: u32,
+}
+
+impl Default for IntervalConfig {
+    fn default() -> Self {
+        Self {
+            warmup_secs: 60,
+            work_secs: 20,
+            rest_secs: 10,
+            rounds: 8,
+            sets: 1,
+            set_rest_secs: 60,
+            cooldown_secs: 60,
+        }
+    }
+}
+
+impl IntervalConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_SEGMENT_SECS).contains(&self.work_secs) {
+            return Err(format!(
+                "work_secs: must be between 1 and {MAX_SEGMENT_SECS}"
+            ));
+        }
+        for (name, secs) in [
+            ("warmup_secs", self.warmup_secs),
+            ("rest_secs", self.rest_secs),
+            ("set_rest_secs", self.set_rest_secs),
+            ("cooldown_secs", self.cooldown_secs),
+        ] {
+            if secs > MAX_SEGMENT_SECS {
+                return Err(format!("{name}: must be at most {MAX_SEGMENT_SECS}"));
+            }
+        }
+        if !(1..=MAX_ROUNDS).contains(&self.rounds) {
+            return Err(format!("rounds: must be between 1 and {MAX_ROUNDS}"));
+        }
+        if !(1..=MAX_SETS).contains(&self.sets) {
+            return Err(format!("sets: must be between 1 and {MAX_SETS}"));
+        }
+        Ok(())
+    }
+
+    /// The segments in the order they run.
+    fn segments(&self) -> Vec<IntervalSegment> {
+        let mut out = Vec::new();
+        let mut push = |phase, secs, round, set| {
+            if secs > 0 {
+                out.push(IntervalSegment {
+                    phase,
+                    secs,
+                    round,
+                    set,
+                });
+            }
+        };
+        push(IntervalPhase::Warmup, self.warmup_secs, 1, 1);
+        for set in 1..=self.sets {
+            for round in 1..=self.rounds {
+                push(IntervalPhase::Work, self.work_secs, round, set);
+                if round < self.rounds {
+                    push(IntervalPhase::Rest, self.rest_secs, round, set);
+                }
+            }
+            if set < self.sets {
+                push(IntervalPhase::SetRest, self.set_rest_secs, self.rounds, set);
+            }
+        }
+        push(
+            IntervalPhase::Cooldown,
+            self.cooldown_secs,
+            self.rounds,
+            self.sets,
+        );
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum IntervalPhase {
+    Warmup,
+    Work,
+    Rest,
+    SetRest,
+    Cooldown,
+    /// The cool-down, or the last segment, has ended.
+    Done,
+}
+
+impl IntervalPhase {
+    pub fn label(self) -> &'static str {
+        match self {
+            IntervalPhase::Warmup => "Warm-up",
+            IntervalPhase::Work => "Work",
+            IntervalPhase::Rest => "Rest",
+            IntervalPhase::SetRest => "Set Rest",
+            IntervalPhase::Cooldown => "Cool-down",
+            IntervalPhase::Done => "Done",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct IntervalTransition {
+    pub from: IntervalPhase,
+    pub to: IntervalPhase,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum IntervalStatus {
+    Idle,
+    Running,
+    Paused,
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+struct IntervalSegment {
+    phase: IntervalPhase,
+    secs: u32,
+    /// 1-based round and set the segment belongs to.
+    round: u32,
+    set: u32,
+}
+
+/// Where a workout stands, for the window and the tray.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IntervalProgress {
+    pub round: u32,
+    pub rounds: u32,
+    pub set: u32,
+    pub sets: u32,
+    /// Time left in the whole workout, including the current segment.
+    pub total_remaining_secs: u32,
+}
+
+/// Runs an [`IntervalConfig`] once from warm-up to cool-down.
+#[derive(Debug, Clone, Serialize)]
+pub struct IntervalTimer {
+    config: IntervalConfig,
+    /// Never empty: every workout has at least one work segment.
+    segments: Vec<IntervalSegment>,
+    /// The current segment; `segments.len()` once done.
+    index: usize,
+    remaining_secs: u32,
+    status: IntervalStatus,
+}
+
+impl IntervalTimer {
+    pub fn new(config: IntervalConfig) -> Self {
+        let segments = config.segments();
+        let remaining_secs = segments.first().map_or(0, |s| s.secs);
+        Self {
+            config,
+            segments,
+            index: 0,
+            remaining_secs,
+            status: IntervalStatus::Idle,
+        }
+    }
+
+    pub fn config(&self) -> IntervalConfig {
+        self.config
+    }
+
+    pub fn status(&self) -> IntervalStatus {
+        self.status
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status == IntervalStatus::Finished
+    }
+
+    fn current(&self) -> Option<&IntervalSegment> {
+        self.segments.get(self.index)
+    }
+
+    pub fn segment_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn phase(&self) -> IntervalPhase {
+        self.current().map_or(IntervalPhase::Done, |s| s.phase)
+    }
+
+    pub fn remaining_secs(&self) -> u32 {
+        self.remaining_secs
+    }
+
+    pub fn phase_duration_secs(&self) -> u32 {
+        self.current().map_or(0, |s| s.secs)
+    }
+
+    pub fn total_secs(&self) -> u32 {
+        self.segments.iter().map(|s| s.secs).sum()
+    }
+
+    pub fn total_remaining_secs(&self) -> u32 {
+        let later: u32 = self
+            .segments
+            .iter()
+            .skip(self.index + 1)
+            .map(|s| s.secs)
+            .sum();
+        self.remaining_secs + later
+    }
+
+    pub fn progress(&self) -> IntervalProgress {
+        let (round, set) = self
+            .current()
+            .map_or((self.config.rounds, self.config.sets), |s| (s.round, s.set));
+        IntervalProgress {
+            round,
+            rounds: self.config.rounds,
+            set,
+            sets: self.config.sets,
+            total_remaining_secs: self.total_remaining_secs(),
+        }
+    }
+
+    /// A finished workout starts over.
+    pub fn start(&mut self) {
+        if self.is_finished() {
+            self.reset();
+        }
+        self.status = IntervalStatus::Running;
+    }
+
+    pub fn pause(&mut self) {
+        if self.status == IntervalStatus::Running {
+            self.status = IntervalStatus::Paused;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.remaining_secs = self.phase_duration_secs();
+        self.status = IntervalStatus::Idle;
+    }
+
+    /// Count down one second, moving to the next segment at a boundary. The
+    /// last segment moves to [`IntervalPhase::Done`] and finishes the workout.
+    pub fn tick(&mut self) -> Option<IntervalTransition> {
+        if self.status != IntervalStatus::Running {
+            return None;
+        }
+        self.remaining_secs = self.remaining_secs.saturating_sub(1);
+        if self.remaining_secs > 0 {
+            return None;
+        }
+        Some(self.advance())
+    }
+
+    /// End the current segment early, keeping the running/paused status.
+    /// `None` once the workout is done.
+    pub fn skip(&mut self) -> Option<IntervalTransition> {
+        (!self.is_finished()).then(|| self.advance())
+    }
+
+    fn advance(&mut self) -> IntervalTransition {
+        let from = self.phase();
+        self.index += 1;
+        self.remaining_secs = self.phase_duration_secs();
+        if self.current().is_none() {
+            self.status = IntervalStatus::Finished;
+        }
+        IntervalTransition {
+            from,
+            to: self.phase(),
+        }
+    }
+
+    /// The last [`COUNTDOWN_SECS`] of a running segment, one beep per second.
+    pub fn in_countdown(&self) -> bool {
+        self.status == IntervalStatus::Running
+            && (1..=COUNTDOWN_SECS).contains(&self.remaining_secs)
+    }
+
+    pub fn display(&self) -> String {
+        duration::format(self.remaining_secs, DurationStyle::Clock)
+    }
+
+    /// The current round out of the rounds per set, e.g. "3/8".
+    pub fn round_display(&self) -> String {
+        let progress = self.progress();
+        format!("{}/{}", progress.round, progress.rounds)
+    }
+}
+
+/// Workout shape and the cues played during it. Cues are loud by default so
+/// they carry over music and breathing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntervalSettings {
+    pub workout: IntervalConfig,
+    /// Played at every segment change.
+    pub cue: EventSound,
+    /// Played each second of a segment's last [`COUNTDOWN_SECS`].
+    pub countdown: EventSound,
+}
+
+impl Default for IntervalSettings {
+    fn default() -> Self {
+        Self {
+            workout: IntervalConfig::default(),
+            cue: EventSound {
+                sound: Some(SoundSource::Bundled(BundledSound::Digital)),
+                volume: 1.0,
+                repeat: 1,
+            },
+            countdown: EventSound {
+                sound: Some(SoundSource::Bundled(BundledSound::Blip)),
+                volume: 1.0,
+                repeat: 1,
+            },
+        }
+    }
+}
+
+impl IntervalSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        self.workout
+            .validate()
+            .map_err(|e| format!("workout.{e}"))?;
+        self.cue.validate().map_err(|e| format!("cue: {e}"))?;
+        self.countdown
+            .validate()
+            .map_err(|e| format!("countdown: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small() -> IntervalConfig {
+        IntervalConfig {
+            warmup_secs: 3,
+            work_secs: 2,
+            rest_secs: 1,
+            rounds: 2,
+            sets: 2,
+            set_rest_secs: 4,
+            cooldown_secs: 3,
+        }
+    }
+
+    /// Tick until the next transition, returning it and the ticks it took.
+    fn run_to_transition(timer: &mut IntervalTimer) -> (IntervalTransition, u32) {
+        let mut ticks = 0;
+        loop {
+            ticks += 1;
+            if let Some(t) = timer.tick() {
+                return (t, ticks);
+            }
+            assert!(ticks < 10_000, "no transition");
+        }
+    }
+
+    #[test]
+    fn default_is_a_tabata_set() {
+        let timer = IntervalTimer::new(IntervalConfig::default());
+        assert_eq!(timer.phase(), IntervalPhase::Warmup);
+        assert_eq!(timer.status(), IntervalStatus::Idle);
+        // 60 warm-up + 8 × 20 work + 7 × 10 rest + 60 cool-down.
+        assert_eq!(timer.total_secs(), 60 + 160 + 70 + 60);
+        assert_eq!(timer.round_display(), "1/8");
+    }
+
+    #[test]
+    fn runs_through_every_segment_in_order() {
+        let mut timer = IntervalTimer::new(small());
+        timer.start();
+        use IntervalPhase::*;
+        let expected = [
+            (Warmup, Work, 3),
+            (Work, Rest, 2),
+            (Rest, Work, 1),
+            (Work, SetRest, 2),
+            (SetRest, Work, 4),
+            (Work, Rest, 2),
+            (Rest, Work, 1),
+            (Work, Cooldown, 2),
+            (Cooldown, Done, 3),
+        ];
+        for (from, to, secs) in expected {
+            let (t, ticks) = run_to_transition(&mut timer);
+            assert_eq!(t, IntervalTransition { from, to });
+            assert_eq!(ticks, secs);
+        }
+        assert!(timer.is_finished());
+        assert_eq!(timer.tick(), None);
+        assert_eq!(timer.total_remaining_secs(), 0);
+    }
+
+    #[test]
+    fn tracks_round_and_set() {
+        let mut timer = IntervalTimer::new(small());
+        timer.start();
+        run_to_transition(&mut timer); // warm-up → work
+        assert_eq!(timer.progress().round, 1);
+        run_to_transition(&mut timer); // → rest
+        run_to_transition(&mut timer); // → work
+        assert_eq!(timer.round_display(), "2/2");
+        run_to_transition(&mut timer); // → set rest
+        run_to_transition(&mut timer); // → work
+        let progress = timer.progress();
+        assert_eq!((progress.round, progress.set), (1, 2));
+    }
+
+    #[test]
+    fn zero_length_segments_are_skipped() {
+        let config = IntervalConfig {
+            warmup_secs: 0,
+            rest_secs: 0,
+            cooldown_secs: 0,
+            sets: 1,
+            ..small()
+        };
+        let mut timer = IntervalTimer::new(config);
+        assert_eq!(timer.phase(), IntervalPhase::Work);
+        timer.start();
+        let (t, _) = run_to_transition(&mut timer);
+        assert_eq!(t.to, IntervalPhase::Work);
+        let (t, _) = run_to_transition(&mut timer);
+        assert_eq!(t.to, IntervalPhase::Done);
+    }
+
+    #[test]
+    fn total_remaining_counts_down_with_each_tick() {
+        let mut timer = IntervalTimer::new(small());
+        let total = timer.total_secs();
+        assert_eq!(timer.total_remaining_secs(), total);
+        timer.start();
+        for elapsed in 1..=total {
+            timer.tick();
+            assert_eq!(timer.total_remaining_secs(), total - elapsed);
+        }
+    }
+
+    #[test]
+    fn countdown_covers_the_last_seconds_while_running() {
+        let mut timer = IntervalTimer::new(IntervalConfig {
+            warmup_secs: 5,
+            ..small()
+        });
+        assert!(!timer.in_countdown());
+        timer.start();
+        timer.tick();
+        assert!(!timer.in_countdown()); // 4 left
+        timer.tick();
+        assert!(timer.in_countdown()); // 3 left
+        timer.pause();
+        assert!(!timer.in_countdown());
+    }
+
+    #[test]
+    fn pause_stops_the_countdown() {
+        let mut timer = IntervalTimer::new(small());
+        timer.start();
+        timer.tick();
+        timer.pause();
+        assert_eq!(timer.tick(), None);
+        assert_eq!(timer.remaining_secs(), 2);
+    }
+
+    #[test]
+    fn skip_keeps_status_and_stops_when_done() {
+        let mut timer = IntervalTimer::new(IntervalConfig {
+            sets: 1,
+            rounds: 1,
+            ..small()
+        });
+        assert_eq!(
+            timer.skip(),
+            Some(IntervalTransition {
+                from: IntervalPhase::Warmup,
+                to: IntervalPhase::Work
+            })
+        );
+        assert_eq!(timer.status(), IntervalStatus::Idle);
+        timer.skip();
+        timer.skip();
+        assert!(timer.is_finished());
+        assert_eq!(timer.skip(), None);
+    }
+
+    #[test]
+    fn start_after_finishing_starts_over() {
+        let mut timer = IntervalTimer::new(small());
+        while !timer.is_finished() {
+            timer.skip();
+        }
+        timer.start();
+        assert_eq!(timer.phase(), IntervalPhase::Warmup);
+        assert_eq!(timer.status(), IntervalStatus::Running);
+        assert_eq!(timer.remaining_secs(), 3);
+    }
+
+    #[test]
+    fn validation() {
+        assert!(IntervalSettings::default().validate().is_ok());
+        let mut settings = IntervalSettings::default();
+        settings.workout.work_secs = 0;
+        assert!(settings
+            .validate()
+            .unwrap_err()
+            .starts_with("workout.work_secs:"));
+        settings.workout = IntervalConfig {
+            rounds: MAX_ROUNDS + 1,
+            ..IntervalConfig::default()
+        };
+        assert!(settings
+            .validate()
+            .unwrap_err()
+            .starts_with("workout.rounds:"));
+        let parsed: IntervalConfig =
+            serde_json::from_str(r#"{"work_secs": 40, "rest_secs": 20}"#).unwrap();
+        assert_eq!(parsed.rounds, 8);
+        assert_eq!(parsed.work_secs, 40);
+    }
+}
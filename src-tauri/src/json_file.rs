@@ -0,0 +1,57 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Read a `T` from the JSON file at `path`; `what` names it in error
+/// messages. A missing file gives the default, and so does one that cannot
+/// be read or parsed, after reporting why.
+pub fn load<T: DeserializeOwned + Default>(path: &Path, what: &str) -> T {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return T::default(),
+        Err(e) => {
+            eprintln!("Failed to read {what} {}: {}", path.display(), e);
+            return T::default();
+        }
+    };
+    serde_json::from_str(&text).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {what} {}: {}", path.display(), e);
+        T::default()
+    })
+}
+
+/// Write `value` to `path` as JSON, creating its directory if needed.
+pub fn save<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+    }
+    let text = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    fs::write(path, text).map_err(|e| format!("failed to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let path = std::env::temp_dir()
+            .join(format!("mac-timer-json-file-{}", std::process::id()))
+            .join("nested")
+            .join("data.json");
+        let value = BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+        save(&path, &value).unwrap();
+        assert_eq!(load::<BTreeMap<String, u32>>(&path, "data"), value);
+        fs::write(&path, "not json").unwrap();
+        assert!(load::<BTreeMap<String, u32>>(&path, "data").is_empty());
+        let _ = fs::remove_dir_all(path.parent().unwrap().parent().unwrap());
+    }
+
+    #[test]
+    fn missing_file_loads_the_default() {
+        let path = std::env::temp_dir().join("mac-timer-json-file-missing.json");
+        assert_eq!(load::<Vec<u32>>(&path, "data"), Vec::<u32>::new());
+    }
+}
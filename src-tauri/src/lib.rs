@@ -1,35 +1,144 @@
+pub mod alarm;
+pub mod ambient;
+pub mod audio;
+pub mod audio_decode;
+pub mod audio_mixer;
+pub mod audio_output;
+pub mod cli;
 pub mod commands;
+pub mod duration;
+pub mod flowtime;
+pub mod history;
+pub mod hotkeys;
+pub mod interval;
+pub mod json_file;
+pub mod notification;
+pub mod overlay;
+pub mod placement;
 pub mod pomodoro;
+pub mod presets;
+pub mod program;
+pub mod quick;
+pub mod quiet_hours;
+pub mod reminders;
 pub mod runner;
+pub mod schedule;
+pub mod settings;
+pub mod shortcuts;
+pub mod snooze;
+pub mod strict;
 pub mod timer;
 pub mod tray;
+pub mod tray_format;
+pub mod tray_icon;
+pub mod tray_menu;
+pub mod warnings;
 
 use std::sync::Mutex;
+use tauri::Manager;
 
+use audio::AudioPlayer;
 use commands::AppState;
+use history::BreakHistory;
+use hotkeys::HotkeyBindings;
+use overlay::OverlayTracker;
+use quiet_hours::QuietTracker;
+use schedule::ScheduleState;
+use tray_icon::TrayIconCache;
+use tray_menu::TrayMenuCache;
+use warnings::WarningTracker;
 
 pub fn run() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            cli::handle_args(app, &argv, false);
+        }))
         .plugin(tauri_plugin_notification::init())
+        .plugin(hotkeys::plugin())
         .manage(Mutex::new(AppState::default()))
+        .manage(Mutex::new(TrayIconCache::default()))
+        .manage(Mutex::new(TrayMenuCache::default()))
+        .manage(Mutex::new(WarningTracker::default()))
+        .manage(Mutex::new(OverlayTracker::default()))
+        .manage(Mutex::new(BreakHistory::default()))
+        .manage(Mutex::new(QuietTracker::default()))
+        .manage(Mutex::new(ScheduleState::default()))
+        .manage(Mutex::new(HotkeyBindings::default()))
+        .manage(AudioPlayer::spawn())
         .invoke_handler(tauri::generate_handler![
             commands::start_timer,
             commands::pause_timer,
             commands::reset_timer,
             commands::set_duration,
+            commands::set_duration_text,
+            commands::parse_duration,
             commands::switch_to_basic,
+            commands::set_alarm,
             commands::switch_to_pomodoro,
+            commands::switch_to_program,
+            commands::switch_to_interval,
+            commands::switch_to_flowtime,
+            commands::take_break,
+            commands::get_programs,
+            commands::skip_phase,
             commands::get_snapshot,
+            commands::get_settings,
+            commands::update_settings,
+            commands::test_sound,
+            commands::set_ambient,
             commands::toggle_always_on_top,
             commands::dismiss_notification,
+            commands::acknowledge_timer,
+            commands::notification_action,
             commands::dismiss_overlay,
+            commands::postpone_break,
+            commands::set_presenting,
+            commands::set_do_not_disturb,
+            commands::get_break_stats,
+            commands::parse_quick_command,
+            commands::suggest_quick_commands,
+            commands::run_quick_command,
+            commands::check_shortcut,
+            commands::get_upcoming_occurrences,
+            commands::skip_next_occurrence,
+            commands::get_presets,
+            commands::create_preset,
+            commands::update_preset,
+            commands::delete_preset,
+            commands::move_preset,
+            commands::set_preset_favorite,
+            commands::start_preset,
+            commands::export_presets,
+            commands::import_presets,
         ])
         .setup(|app| {
             // Hide from Dock by setting activation policy to Accessory
+            #[cfg(target_os = "macos")]
             app.set_activation_policy(tauri::ActivationPolicy::Accessory);
 
+            if let Some(path) = commands::settings_path(app.handle()) {
+                let state = app.state::<Mutex<AppState>>();
+                state.lock().unwrap().settings = settings::load(&path);
+            }
+            let shortcuts = {
+                let state = app.state::<Mutex<AppState>>();
+                let s = state.lock().unwrap();
+                s.settings.shortcuts.clone()
+            };
+            hotkeys::register(app.handle(), &shortcuts);
+            if let Some(path) = commands::history_path(app.handle()) {
+                let history = app.state::<Mutex<BreakHistory>>();
+                *history.lock().unwrap() = json_file::load(&path, "break history");
+            }
+            if let Some(path) = commands::schedule_state_path(app.handle()) {
+                let schedules = app.state::<Mutex<ScheduleState>>();
+                *schedules.lock().unwrap() = json_file::load(&path, "schedule state");
+            }
+
             tray::setup_tray(app.handle())?;
             runner::start_tick_loop(app.handle().clone());
+            runner::start_scheduler(app.handle().clone());
+            cli::handle_args(app.handle(), &std::env::args().collect::<Vec<_>>(), true);
             Ok(())
         })
         .run(tauri::generate_context!())
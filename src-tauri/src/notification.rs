@@ -0,0 +1,409 @@
+use serde::{Deserialize, Serialize};
+
+use crate::duration::{self, DurationStyle};
+use crate::quiet_hours::SuppressedAlerts;
+use crate::schedule::Occurrence;
+
+/// Where phase-change alerts are shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationStyle {
+    /// System notification center only.
+    Native,
+    /// The app's own notification window (and the break overlay).
+    Window,
+    #[default]
+    Both,
+}
+
+impl NotificationStyle {
+    pub fn shows_native(self) -> bool {
+        matches!(self, NotificationStyle::Native | NotificationStyle::Both)
+    }
+
+    pub fn shows_window(self) -> bool {
+        matches!(self, NotificationStyle::Window | NotificationStyle::Both)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub style: NotificationStyle,
+}
+
+/// Something the user can do from a notification, chosen in Rust per event.
+/// Serialized as `"start_break"` or `{"snooze": 5}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationAction {
+    StartBreak,
+    SkipBreak,
+    /// Snooze for this many minutes.
+    Snooze(u32),
+    StartNextWork,
+    /// Close the notification, acknowledging a finished timer. Always
+    /// available, so it is not offered as a button.
+    Dismiss,
+}
+
+impl NotificationAction {
+    pub fn label(self) -> String {
+        match self {
+            NotificationAction::StartBreak => "Start break".to_string(),
+            NotificationAction::SkipBreak => "Skip break".to_string(),
+            NotificationAction::Snooze(minutes) => format!("+{minutes} min"),
+            NotificationAction::StartNextWork => "Start next work".to_string(),
+            NotificationAction::Dismiss => "Dismiss".to_string(),
+        }
+    }
+}
+
+/// A button on the notification window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ActionButton {
+    pub action: NotificationAction,
+    pub label: String,
+}
+
+impl From<NotificationAction> for ActionButton {
+    fn from(action: NotificationAction) -> Self {
+        Self {
+            action,
+            label: action.label(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NotificationMessage {
+    pub title: String,
+    pub body: String,
+}
+
+impl NotificationMessage {
+    fn new(title: &str, body: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            body: body.to_string(),
+        }
+    }
+}
+
+/// Title and body for a phase change, as reported in `timer:phase-change`.
+pub fn notification_message(from: &str, to: &str) -> Option<NotificationMessage> {
+    match (from, to) {
+        ("timer", "finished") => Some(NotificationMessage::new(
+            "Timer Finished!",
+            "Your timer has completed.",
+        )),
+        ("Work", "ShortBreak") => Some(NotificationMessage::new(
+            "Break Time!",
+            "Take a short break.",
+        )),
+        ("Work", "LongBreak") => Some(NotificationMessage::new(
+            "Long Break!",
+            "Great work! Take a longer break.",
+        )),
+        ("ShortBreak" | "LongBreak", "Work") => {
+            Some(NotificationMessage::new("Back to Work!", "Time to focus."))
+        }
+        // Interval segment changes are announced by sound only, except the end.
+        (_, "Done") => Some(NotificationMessage::new(
+            "Workout Complete!",
+            "Nice work. Time to recover.",
+        )),
+        _ => None,
+    }
+}
+
+/// Title and body for entering a program segment that has no standard
+/// message, e.g. an "other" segment.
+pub fn segment_message(name: &str) -> NotificationMessage {
+    NotificationMessage::new(name, &format!("{name} has started."))
+}
+
+/// Title and body for a command that could not run, e.g. one sent from the
+/// command line.
+pub fn command_error_message(error: &str) -> NotificationMessage {
+    NotificationMessage::new("Command Failed", error)
+}
+
+/// Title and body for a pre-end warning. `phase` is `"timer"` or a pomodoro phase.
+pub fn warning_message(phase: &str, remaining_secs: u32) -> NotificationMessage {
+    let title = format!(
+        "{} left",
+        duration::format(remaining_secs, DurationStyle::Verbose)
+    );
+    let body = match phase {
+        "Work" => "Work session ends soon.",
+        "ShortBreak" | "LongBreak" => "Break ends soon.",
+        _ => "Timer ends soon.",
+    };
+    NotificationMessage::new(&title, body)
+}
+
+/// Title and body for a repeated alert on a finished, undismissed timer.
+pub fn reminder_message(finished_secs_ago: u32) -> NotificationMessage {
+    // Whole minutes, so a reminder does not count seconds
+    let body = match finished_secs_ago / 60 {
+        0 => "Finished just now.".to_string(),
+        m => format!(
+            "Finished {} ago.",
+            duration::format(m * 60, DurationStyle::Verbose)
+        ),
+    };
+    NotificationMessage::new("Timer Finished!", &body)
+}
+
+/// Title and body summarizing alerts held back during quiet hours or
+/// do-not-disturb.
+pub fn quiet_summary_message(summary: &SuppressedAlerts) -> NotificationMessage {
+    fn count(n: u32, one: &str, many: &str) -> Option<String> {
+        match n {
+            0 => None,
+            1 => Some(format!("1 {one}")),
+            n => Some(format!("{n} {many}")),
+        }
+    }
+    let parts: Vec<String> = [
+        count(summary.phase_changes, "phase change", "phase changes"),
+        count(summary.reminders, "reminder", "reminders"),
+        count(summary.warnings, "warning", "warnings"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let mut body = format!("Missed {}.", parts.join(", "));
+    match summary.last_phase.as_deref() {
+        Some("finished") => body.push_str(" Your timer has finished."),
+        Some("Work") => body.push_str(" Now: work."),
+        Some("ShortBreak") => body.push_str(" Now: short break."),
+        Some("LongBreak") => body.push_str(" Now: long break."),
+        _ => {}
+    }
+    NotificationMessage::new("While you were away", &body)
+}
+
+/// Title and body for schedule occurrences that were not run in time.
+pub fn missed_schedules_message(missed: &[Occurrence]) -> NotificationMessage {
+    let body = match missed {
+        [one] => format!(
+            "{} at {} did not run on time.",
+            one.schedule,
+            one.time.format("%H:%M")
+        ),
+        _ => {
+            let mut names: Vec<&str> = Vec::new();
+            for o in missed {
+                if !names.contains(&o.schedule.as_str()) {
+                    names.push(&o.schedule);
+                }
+            }
+            format!(
+                "{} scheduled items did not run: {}.",
+                missed.len(),
+                names.join(", ")
+            )
+        }
+    };
+    NotificationMessage::new("Missed Schedules", &body)
+}
+
+/// Percent-encode a query string value (RFC 3986 unreserved characters pass through).
+pub fn encode_query_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(from: &str, to: &str) -> Option<(String, String)> {
+        notification_message(from, to).map(|m| (m.title, m.body))
+    }
+
+    #[test]
+    fn basic_timer_finish() {
+        assert_eq!(
+            message("timer", "finished"),
+            Some((
+                "Timer Finished!".to_string(),
+                "Your timer has completed.".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn work_to_short_break() {
+        assert_eq!(
+            message("Work", "ShortBreak"),
+            Some(("Break Time!".to_string(), "Take a short break.".to_string()))
+        );
+    }
+
+    #[test]
+    fn work_to_long_break() {
+        assert_eq!(
+            message("Work", "LongBreak"),
+            Some((
+                "Long Break!".to_string(),
+                "Great work! Take a longer break.".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn breaks_back_to_work() {
+        let expected = Some(("Back to Work!".to_string(), "Time to focus.".to_string()));
+        assert_eq!(message("ShortBreak", "Work"), expected);
+        assert_eq!(message("LongBreak", "Work"), expected);
+    }
+
+    #[test]
+    fn only_the_end_of_a_workout_has_a_message() {
+        assert_eq!(message("Work", "Rest"), None);
+        assert_eq!(
+            message("Cooldown", "Done"),
+            Some((
+                "Workout Complete!".to_string(),
+                "Nice work. Time to recover.".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn unknown_transition_has_no_message() {
+        assert_eq!(message("unknown", "unknown"), None);
+    }
+
+    #[test]
+    fn warning_messages() {
+        let m = warning_message("Work", 300);
+        assert_eq!(m.title, "5 minutes left");
+        assert_eq!(m.body, "Work session ends soon.");
+        assert_eq!(warning_message("timer", 60).title, "1 minute left");
+        assert_eq!(
+            warning_message("timer", 90).title,
+            "1 minute 30 seconds left"
+        );
+        assert_eq!(
+            warning_message("Work", 90 * 60).title,
+            "1 hour 30 minutes left"
+        );
+        assert_eq!(warning_message("LongBreak", 30).title, "30 seconds left");
+        assert_eq!(warning_message("LongBreak", 30).body, "Break ends soon.");
+        assert_eq!(warning_message("timer", 1).title, "1 second left");
+    }
+
+    #[test]
+    fn reminder_messages() {
+        assert_eq!(reminder_message(30).body, "Finished just now.");
+        assert_eq!(reminder_message(60).body, "Finished 1 minute ago.");
+        let m = reminder_message(240);
+        assert_eq!(m.title, "Timer Finished!");
+        assert_eq!(m.body, "Finished 4 minutes ago.");
+        assert_eq!(
+            reminder_message(90 * 60 + 30).body,
+            "Finished 1 hour 30 minutes ago."
+        );
+    }
+
+    #[test]
+    fn segment_message_names_the_segment() {
+        let m = segment_message("Plan");
+        assert_eq!(m.title, "Plan");
+        assert_eq!(m.body, "Plan has started.");
+    }
+
+    #[test]
+    fn command_error_message_carries_the_error() {
+        let m = command_error_message("quick: unknown command \"explode\"");
+        assert_eq!(m.title, "Command Failed");
+        assert_eq!(m.body, "quick: unknown command \"explode\"");
+    }
+
+    #[test]
+    fn quiet_summary_lists_what_was_missed() {
+        let summary = SuppressedAlerts {
+            phase_changes: 2,
+            reminders: 1,
+            warnings: 0,
+            last_phase: Some("ShortBreak".to_string()),
+        };
+        let m = quiet_summary_message(&summary);
+        assert_eq!(m.title, "While you were away");
+        assert_eq!(
+            m.body,
+            "Missed 2 phase changes, 1 reminder. Now: short break."
+        );
+        let warnings_only = SuppressedAlerts {
+            warnings: 3,
+            ..SuppressedAlerts::default()
+        };
+        assert_eq!(
+            quiet_summary_message(&warnings_only).body,
+            "Missed 3 warnings."
+        );
+    }
+
+    #[test]
+    fn missed_schedules_name_each_schedule_once() {
+        let at = |schedule: &str, day: u32| Occurrence {
+            schedule: schedule.to_string(),
+            date: chrono::NaiveDate::from_ymd_opt(2024, 3, day).unwrap(),
+            time: chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        };
+        let m = missed_schedules_message(&[at("Focus", 4)]);
+        assert_eq!(m.title, "Missed Schedules");
+        assert_eq!(m.body, "Focus at 09:00 did not run on time.");
+        let m = missed_schedules_message(&[at("Focus", 4), at("Lunch", 4), at("Focus", 5)]);
+        assert_eq!(m.body, "3 scheduled items did not run: Focus, Lunch.");
+    }
+
+    #[test]
+    fn style_defaults_to_both() {
+        let style = NotificationStyle::default();
+        assert!(style.shows_native());
+        assert!(style.shows_window());
+        assert!(!NotificationStyle::Native.shows_window());
+        assert!(!NotificationStyle::Window.shows_native());
+    }
+
+    #[test]
+    fn style_serializes_as_snake_case() {
+        let json = serde_json::to_string(&NotificationStyle::Native).unwrap();
+        assert_eq!(json, "\"native\"");
+        let style: NotificationStyle = serde_json::from_str("\"window\"").unwrap();
+        assert_eq!(style, NotificationStyle::Window);
+    }
+
+    #[test]
+    fn actions_serialize_for_the_window() {
+        let json = serde_json::to_string(&NotificationAction::SkipBreak).unwrap();
+        assert_eq!(json, "\"skip_break\"");
+        let json =
+            serde_json::to_string(&ActionButton::from(NotificationAction::Snooze(5))).unwrap();
+        assert_eq!(json, r#"{"action":{"snooze":5},"label":"+5 min"}"#);
+        let action: NotificationAction = serde_json::from_str(r#"{"snooze":10}"#).unwrap();
+        assert_eq!(action, NotificationAction::Snooze(10));
+        let action: NotificationAction = serde_json::from_str("\"start_next_work\"").unwrap();
+        assert_eq!(action.label(), "Start next work");
+    }
+
+    #[test]
+    fn encodes_query_values() {
+        assert_eq!(encode_query_value("Break Time!"), "Break%20Time%21");
+        assert_eq!(encode_query_value("a&b=c"), "a%26b%3Dc");
+        assert_eq!(encode_query_value("☕"), "%E2%98%95");
+    }
+}
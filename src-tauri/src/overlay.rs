@@ -0,0 +1,582 @@
+use serde::{Deserialize, Serialize};
+
+use crate::commands::{ActiveTimer, AppState};
+use crate::notification::{ActionButton, NotificationAction};
+use crate::pomodoro::{Phase, PomodoroStatus};
+use crate::warnings::{Countdown, CountdownKey};
+
+pub const MAX_TEXT_CHARS: usize = 200;
+pub const MIN_OPACITY_PERCENT: u8 = 10;
+
+/// Logical size of a banner overlay and its distance from the screen edges.
+pub const BANNER_WIDTH: f64 = 360.0;
+pub const BANNER_HEIGHT: f64 = 140.0;
+pub const BANNER_MARGIN: f64 = 24.0;
+
+/// Which monitors get a break overlay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayTarget {
+    #[default]
+    All,
+    Primary,
+    /// The monitor the main window is on.
+    MainWindow,
+    /// The monitor under the mouse cursor.
+    Cursor,
+}
+
+impl OverlayTarget {
+    /// The monitors to cover out of `monitors`. A single-monitor target stays
+    /// on the monitor in `current` while it is connected, so the overlay does
+    /// not follow the cursor around. Otherwise `chosen` looks up the monitor
+    /// the target points at; when that is unknown or no longer connected, the
+    /// first monitor is used.
+    pub fn pick(
+        self,
+        monitors: &[String],
+        current: &[String],
+        chosen: impl FnOnce() -> Option<String>,
+    ) -> Vec<String> {
+        if self == OverlayTarget::All {
+            return monitors.to_vec();
+        }
+        if let Some(kept) = current.iter().find(|m| monitors.contains(m)) {
+            return vec![kept.clone()];
+        }
+        if monitors.is_empty() {
+            return Vec::new();
+        }
+        chosen()
+            .filter(|id| monitors.contains(id))
+            .or_else(|| monitors.first().cloned())
+            .into_iter()
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayStyle {
+    /// Covers the whole monitor.
+    #[default]
+    Fullscreen,
+    /// A small window in the top-right corner.
+    Banner,
+}
+
+/// A window frame in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl OverlayStyle {
+    /// The overlay window frame on a monitor with the given frame.
+    pub fn frame(self, monitor: Rect) -> Rect {
+        match self {
+            OverlayStyle::Fullscreen => monitor,
+            OverlayStyle::Banner => Rect {
+                x: monitor.x + (monitor.width - BANNER_WIDTH - BANNER_MARGIN).max(0.0),
+                y: monitor.y + BANNER_MARGIN,
+                width: BANNER_WIDTH.min(monitor.width),
+                height: BANNER_HEIGHT.min(monitor.height),
+            },
+        }
+    }
+}
+
+/// How and where the break overlay is shown, and what it says.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlaySettings {
+    /// Replaces the default "take a break" line.
+    pub message: Option<String>,
+    /// Suggestions rotated through, one per break.
+    pub exercises: Vec<String>,
+    pub target: OverlayTarget,
+    pub style: OverlayStyle,
+    /// Background opacity, from `MIN_OPACITY_PERCENT` to 100.
+    pub opacity_percent: u8,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            message: None,
+            exercises: Vec::new(),
+            target: OverlayTarget::All,
+            style: OverlayStyle::Fullscreen,
+            opacity_percent: 92,
+        }
+    }
+}
+
+impl OverlaySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_OPACITY_PERCENT..=100).contains(&self.opacity_percent) {
+            return Err(format!(
+                "opacity_percent: must be between {MIN_OPACITY_PERCENT} and 100"
+            ));
+        }
+        if let Some(message) = &self.message {
+            validate_text(message).map_err(|e| format!("message: {e}"))?;
+        }
+        for exercise in &self.exercises {
+            validate_text(exercise).map_err(|e| format!("exercises: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_text(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    if text.chars().count() > MAX_TEXT_CHARS {
+        return Err(format!("must be at most {MAX_TEXT_CHARS} characters"));
+    }
+    Ok(())
+}
+
+/// Payload of the `overlay:update` event, sent every tick while overlays are up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OverlayPayload {
+    pub phase: String,
+    pub display: String,
+    pub remaining_secs: u32,
+    pub total_secs: u32,
+    /// The break has not been started yet (see `auto_start.breaks`).
+    pub waiting: bool,
+    pub message: Option<String>,
+    pub exercise: Option<String>,
+    /// Seconds until strict mode lets the overlay be dismissed; 0 when it can be now.
+    pub dismiss_in_secs: u32,
+    pub postpones_left: u32,
+    pub postpone_minutes: u32,
+    pub opacity_percent: u8,
+    /// The phase change notification the overlay replaces, for
+    /// `notification_action`, and its actions other than starting the break,
+    /// which the overlay's own button does.
+    pub event_id: Option<u64>,
+    pub actions: Vec<ActionButton>,
+}
+
+/// Which overlay windows to open and close to match the connected monitors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OverlayPlan {
+    /// `(monitor id, window label)` pairs to open.
+    pub open: Vec<(String, String)>,
+    /// Window labels to close.
+    pub close: Vec<String>,
+}
+
+/// Owns the break overlay lifecycle: which break it belongs to and which
+/// monitors currently have a window.
+#[derive(Debug, Default)]
+pub struct OverlayTracker {
+    /// The break the overlays were opened for.
+    key: Option<CountdownKey>,
+    /// `(monitor id, window label)` of each open overlay window.
+    windows: Vec<(String, String)>,
+    next_label: u32,
+}
+
+impl OverlayTracker {
+    /// Show overlays for the break the state is in now.
+    pub fn open(&mut self, state: &AppState) {
+        self.key = Some(Countdown::from_state(state).key);
+    }
+
+    /// The user closed the overlays; keep them closed for this break.
+    pub fn dismiss(&mut self) {
+        self.key = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// What the overlays should show, or `None` once they should be gone: the
+    /// break ended or was skipped, the timer was paused or reset, or the mode
+    /// changed. A `None` is final for this break.
+    pub fn reconcile(
+        &mut self,
+        state: &AppState,
+        postpones_used_today: u32,
+    ) -> Option<OverlayPayload> {
+        let key = self.key.as_ref()?;
+        // (phase, display, remaining, total, waiting, sessions, elapsed) of a
+        // break that is on or waiting to start
+        let current = match &state.active {
+            ActiveTimer::Pomodoro(t)
+                if t.phase() != Phase::Work
+                    && matches!(
+                        t.status(),
+                        PomodoroStatus::Running | PomodoroStatus::Waiting
+                    ) =>
+            {
+                Some((
+                    t.phase(),
+                    t.display(),
+                    t.remaining_secs(),
+                    t.phase_duration_secs(),
+                    t.status() == PomodoroStatus::Waiting,
+                    t.completed_sessions(),
+                    t.break_elapsed_secs().unwrap_or(0),
+                ))
+            }
+            ActiveTimer::Flowtime(t) => t.break_elapsed_secs().map(|elapsed| {
+                (
+                    t.phase(),
+                    t.display(),
+                    t.remaining_secs(),
+                    t.phase_duration_secs(),
+                    false,
+                    t.completed_sessions(),
+                    elapsed,
+                )
+            }),
+            _ => None,
+        };
+        let payload = current
+            .filter(|_| Countdown::from_state(state).key == *key)
+            .map(
+                |(phase, display, remaining_secs, total_secs, waiting, sessions, elapsed)| {
+                    let overlay = &state.settings.overlay;
+                    let strict = &state.settings.strict;
+                    let event = state.notification.as_ref().filter(|e| e.key == *key);
+                    let actions = event
+                        .iter()
+                        .flat_map(|e| &e.actions)
+                        .filter(|&&a| a != NotificationAction::StartBreak)
+                        .map(|&a| a.into())
+                        .collect();
+                    let exercise = (!overlay.exercises.is_empty()).then(|| {
+                        let index = sessions as usize % overlay.exercises.len();
+                        overlay.exercises[index].clone()
+                    });
+                    OverlayPayload {
+                        phase: format!("{phase:?}"),
+                        display,
+                        remaining_secs,
+                        total_secs,
+                        waiting,
+                        message: overlay.message.clone(),
+                        exercise,
+                        dismiss_in_secs: strict.dismiss_wait_secs(elapsed),
+                        // Only pomodoro breaks can be pushed back
+                        postpones_left: match state.active {
+                            ActiveTimer::Pomodoro(_) => strict.postpones_left(postpones_used_today),
+                            _ => 0,
+                        },
+                        postpone_minutes: strict.postpone_minutes,
+                        opacity_percent: overlay.opacity_percent,
+                        event_id: event.map(|e| e.id),
+                        actions,
+                    }
+                },
+            );
+        if payload.is_none() {
+            self.key = None;
+        }
+        payload
+    }
+
+    /// Windows to open for newly connected monitors and to close for
+    /// disconnected ones, or all of them once the overlay is closed.
+    pub fn plan(&mut self, monitors: &[String]) -> OverlayPlan {
+        let mut plan = OverlayPlan::default();
+        let wanted: &[String] = if self.is_open() { monitors } else { &[] };
+        self.windows.retain(|(monitor, label)| {
+            let keep = wanted.contains(monitor);
+            if !keep {
+                plan.close.push(label.clone());
+            }
+            keep
+        });
+        for monitor in wanted {
+            if !self.windows.iter().any(|(m, _)| m == monitor) {
+                let label = format!("overlay-{}", self.next_label);
+                self.next_label += 1;
+                self.windows.push((monitor.clone(), label.clone()));
+                plan.open.push((monitor.clone(), label));
+            }
+        }
+        plan
+    }
+
+    /// Monitors that currently have an overlay window.
+    pub fn monitors(&self) -> Vec<String> {
+        self.windows.iter().map(|(m, _)| m.clone()).collect()
+    }
+
+    /// Forget a window that failed to open or was closed behind our back, so
+    /// the next plan retries it.
+    pub fn forget(&mut self, label: &str) {
+        self.windows.retain(|(_, l)| l != label);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{
+        begin_notification, do_pause, do_reset, do_skip_phase, do_start, do_switch_to_basic,
+        do_switch_to_flowtime, do_take_break,
+    };
+
+    /// A running short break with overlays open.
+    fn on_break() -> (AppState, OverlayTracker) {
+        let mut state = AppState::default();
+        do_start(&mut state);
+        do_skip_phase(&mut state);
+        let mut tracker = OverlayTracker::default();
+        tracker.open(&state);
+        (state, tracker)
+    }
+
+    fn monitors(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn payload_follows_the_break() {
+        let (mut state, mut tracker) = on_break();
+        state.settings.overlay.message = Some("Stand up".to_string());
+        let payload = tracker.reconcile(&state, 0).unwrap();
+        assert_eq!(payload.phase, "ShortBreak");
+        assert_eq!(payload.remaining_secs, 5 * 60);
+        assert_eq!(payload.total_secs, 5 * 60);
+        assert_eq!(payload.display, "05:00");
+        assert!(!payload.waiting);
+        assert_eq!(payload.message.as_deref(), Some("Stand up"));
+        assert_eq!(payload.exercise, None);
+    }
+
+    #[test]
+    fn exercises_rotate_per_break() {
+        let (mut state, mut tracker) = on_break();
+        state.settings.overlay.exercises = vec!["Stretch".to_string(), "Walk".to_string()];
+        let first = tracker.reconcile(&state, 0).unwrap().exercise;
+        do_skip_phase(&mut state);
+        do_skip_phase(&mut state);
+        tracker.open(&state);
+        let second = tracker.reconcile(&state, 0).unwrap().exercise;
+        assert_eq!(first.as_deref(), Some("Walk"));
+        assert_eq!(second.as_deref(), Some("Stretch"));
+    }
+
+    #[test]
+    fn closes_when_the_break_ends() {
+        let (mut state, mut tracker) = on_break();
+        do_skip_phase(&mut state);
+        assert_eq!(tracker.reconcile(&state, 0), None);
+        assert!(!tracker.is_open());
+    }
+
+    #[test]
+    fn closes_on_pause_reset_and_mode_switch() {
+        let (mut state, mut tracker) = on_break();
+        do_pause(&mut state);
+        assert_eq!(tracker.reconcile(&state, 0), None);
+        // Resuming does not bring a closed overlay back.
+        do_start(&mut state);
+        assert_eq!(tracker.reconcile(&state, 0), None);
+
+        let (mut state, mut tracker) = on_break();
+        do_reset(&mut state);
+        assert_eq!(tracker.reconcile(&state, 0), None);
+
+        let (mut state, mut tracker) = on_break();
+        do_switch_to_basic(&mut state);
+        assert_eq!(tracker.reconcile(&state, 0), None);
+    }
+
+    #[test]
+    fn covers_flowtime_breaks() {
+        let mut state = AppState::default();
+        do_switch_to_flowtime(&mut state);
+        do_start(&mut state);
+        if let ActiveTimer::Flowtime(t) = &mut state.active {
+            for _ in 0..10 * 60 {
+                t.tick();
+            }
+        }
+        do_take_break(&mut state).unwrap();
+        let mut tracker = OverlayTracker::default();
+        tracker.open(&state);
+        let payload = tracker.reconcile(&state, 0).unwrap();
+        assert_eq!(payload.phase, "ShortBreak");
+        assert_eq!(payload.remaining_secs, 2 * 60);
+        assert_eq!(payload.total_secs, 2 * 60);
+        assert!(!payload.waiting);
+        state.settings.strict.enabled = true;
+        assert_eq!(tracker.reconcile(&state, 0).unwrap().postpones_left, 0);
+        do_skip_phase(&mut state);
+        assert_eq!(tracker.reconcile(&state, 0), None);
+    }
+
+    #[test]
+    fn payload_carries_the_notification_actions() {
+        let (mut state, mut tracker) = on_break();
+        assert_eq!(tracker.reconcile(&state, 0).unwrap().event_id, None);
+        let event = begin_notification(&mut state, "Work", "ShortBreak");
+        let payload = tracker.reconcile(&state, 0).unwrap();
+        assert_eq!(payload.event_id, Some(event.id));
+        assert_eq!(
+            payload.actions,
+            vec![ActionButton::from(NotificationAction::SkipBreak)]
+        );
+    }
+
+    #[test]
+    fn payload_carries_strict_limits() {
+        let (mut state, mut tracker) = on_break();
+        state.settings.strict.enabled = true;
+        let payload = tracker.reconcile(&state, 1).unwrap();
+        assert_eq!(payload.dismiss_in_secs, 120);
+        assert_eq!(payload.postpones_left, 1);
+        assert_eq!(payload.postpone_minutes, 5);
+    }
+
+    #[test]
+    fn dismiss_keeps_it_closed() {
+        let (state, mut tracker) = on_break();
+        tracker.dismiss();
+        assert_eq!(tracker.reconcile(&state, 0), None);
+    }
+
+    #[test]
+    fn plan_opens_one_window_per_monitor() {
+        let (_, mut tracker) = on_break();
+        let plan = tracker.plan(&monitors(&["a", "b"]));
+        assert_eq!(
+            plan.open,
+            vec![
+                ("a".to_string(), "overlay-0".to_string()),
+                ("b".to_string(), "overlay-1".to_string())
+            ]
+        );
+        assert!(plan.close.is_empty());
+        assert_eq!(tracker.plan(&monitors(&["a", "b"])), OverlayPlan::default());
+    }
+
+    #[test]
+    fn plan_follows_monitor_changes() {
+        let (_, mut tracker) = on_break();
+        tracker.plan(&monitors(&["a"]));
+        let plan = tracker.plan(&monitors(&["a", "c"]));
+        assert_eq!(plan.open, vec![("c".to_string(), "overlay-1".to_string())]);
+        let plan = tracker.plan(&monitors(&["c"]));
+        assert_eq!(plan.close, vec!["overlay-0".to_string()]);
+        assert!(plan.open.is_empty());
+    }
+
+    #[test]
+    fn plan_closes_everything_once_closed() {
+        let (_, mut tracker) = on_break();
+        tracker.plan(&monitors(&["a", "b"]));
+        tracker.dismiss();
+        let plan = tracker.plan(&monitors(&["a", "b"]));
+        assert_eq!(plan.close.len(), 2);
+        assert!(tracker.plan(&monitors(&["a"])).close.is_empty());
+    }
+
+    #[test]
+    fn forgotten_windows_are_reopened() {
+        let (_, mut tracker) = on_break();
+        tracker.plan(&monitors(&["a"]));
+        tracker.forget("overlay-0");
+        let plan = tracker.plan(&monitors(&["a"]));
+        assert_eq!(plan.open, vec![("a".to_string(), "overlay-1".to_string())]);
+    }
+
+    #[test]
+    fn target_picks_monitors() {
+        let all = monitors(&["a", "b", "c"]);
+        let b = || Some("b".to_string());
+        assert_eq!(OverlayTarget::All.pick(&all, &[], b), all);
+        assert_eq!(OverlayTarget::Cursor.pick(&all, &[], b), monitors(&["b"]));
+        // An unknown or disconnected monitor falls back to the first one.
+        assert_eq!(
+            OverlayTarget::Primary.pick(&all, &[], || None),
+            monitors(&["a"])
+        );
+        assert_eq!(
+            OverlayTarget::MainWindow.pick(&all, &[], || Some("gone".to_string())),
+            monitors(&["a"])
+        );
+        assert!(OverlayTarget::Primary.pick(&[], &[], b).is_empty());
+    }
+
+    #[test]
+    fn single_target_stays_where_it_opened() {
+        let all = monitors(&["a", "b"]);
+        let b = || Some("b".to_string());
+        assert_eq!(
+            OverlayTarget::Cursor.pick(&all, &monitors(&["a"]), b),
+            monitors(&["a"])
+        );
+        // Once that monitor is gone, it moves.
+        assert_eq!(
+            OverlayTarget::Cursor.pick(&all, &monitors(&["x"]), b),
+            monitors(&["b"])
+        );
+    }
+
+    #[test]
+    fn banner_sits_in_the_top_right_corner() {
+        let monitor = Rect {
+            x: 1440.0,
+            y: 0.0,
+            width: 1920.0,
+            height: 1080.0,
+        };
+        assert_eq!(OverlayStyle::Fullscreen.frame(monitor), monitor);
+        assert_eq!(
+            OverlayStyle::Banner.frame(monitor),
+            Rect {
+                x: 1440.0 + 1920.0 - BANNER_WIDTH - BANNER_MARGIN,
+                y: BANNER_MARGIN,
+                width: BANNER_WIDTH,
+                height: BANNER_HEIGHT,
+            }
+        );
+    }
+
+    #[test]
+    fn settings_parse_target_and_style() {
+        let settings: OverlaySettings =
+            serde_json::from_str(r#"{"target":"main_window","style":"banner"}"#).unwrap();
+        assert_eq!(settings.target, OverlayTarget::MainWindow);
+        assert_eq!(settings.style, OverlayStyle::Banner);
+        assert_eq!(settings.opacity_percent, 92);
+    }
+
+    #[test]
+    fn validation() {
+        assert!(OverlaySettings::default().validate().is_ok());
+        let faint = OverlaySettings {
+            opacity_percent: MIN_OPACITY_PERCENT - 1,
+            ..OverlaySettings::default()
+        };
+        assert!(faint
+            .validate()
+            .unwrap_err()
+            .starts_with("opacity_percent:"));
+        let blank = OverlaySettings {
+            message: Some("  ".to_string()),
+            ..OverlaySettings::default()
+        };
+        assert!(blank.validate().is_err());
+        let long = OverlaySettings {
+            exercises: vec!["x".repeat(MAX_TEXT_CHARS + 1)],
+            ..OverlaySettings::default()
+        };
+        assert!(long.validate().unwrap_err().starts_with("exercises:"));
+    }
+}
@@ -0,0 +1,173 @@
+/// A rectangle in physical screen coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl ScreenRect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+}
+
+/// Screen edge the tray (menu bar, taskbar or panel) is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayEdge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Guess which edge the tray sits on from the icon's position on its monitor.
+pub fn tray_edge(tray: ScreenRect, screen: ScreenRect) -> TrayEdge {
+    let (cx, cy) = tray.center();
+    let distances = [
+        (TrayEdge::Top, cy - screen.y),
+        (TrayEdge::Bottom, screen.bottom() - cy),
+        (TrayEdge::Left, cx - screen.x),
+        (TrayEdge::Right, screen.right() - cx),
+    ];
+    distances
+        .iter()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(edge, _)| *edge)
+        .unwrap_or(TrayEdge::Top)
+}
+
+/// Top-left position for a window of `size` so it opens next to the tray icon,
+/// on the side facing away from the tray edge and kept inside `screen`.
+///
+/// Without a known screen the window is placed below the icon, which is the
+/// macOS menu bar layout.
+pub fn window_position(
+    tray: ScreenRect,
+    size: (f64, f64),
+    screen: Option<ScreenRect>,
+) -> (f64, f64) {
+    let (width, height) = size;
+    let (cx, cy) = tray.center();
+
+    let Some(screen) = screen else {
+        return (cx - width / 2.0, tray.bottom());
+    };
+
+    let (x, y) = match tray_edge(tray, screen) {
+        TrayEdge::Top => (cx - width / 2.0, tray.bottom()),
+        TrayEdge::Bottom => (cx - width / 2.0, tray.y - height),
+        TrayEdge::Left => (tray.right(), cy - height / 2.0),
+        TrayEdge::Right => (tray.x - width, cy - height / 2.0),
+    };
+
+    (
+        clamp_axis(x, width, screen.x, screen.right()),
+        clamp_axis(y, height, screen.y, screen.bottom()),
+    )
+}
+
+fn clamp_axis(start: f64, len: f64, min: f64, max: f64) -> f64 {
+    if len >= max - min {
+        return min;
+    }
+    start.clamp(min, max - len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: ScreenRect = ScreenRect {
+        x: 0.0,
+        y: 0.0,
+        width: 1920.0,
+        height: 1080.0,
+    };
+    const WINDOW: (f64, f64) = (320.0, 400.0);
+
+    #[test]
+    fn detects_top_menu_bar() {
+        let tray = ScreenRect::new(1500.0, 0.0, 24.0, 24.0);
+        assert_eq!(tray_edge(tray, SCREEN), TrayEdge::Top);
+    }
+
+    #[test]
+    fn detects_bottom_taskbar() {
+        let tray = ScreenRect::new(1700.0, 1040.0, 40.0, 40.0);
+        assert_eq!(tray_edge(tray, SCREEN), TrayEdge::Bottom);
+    }
+
+    #[test]
+    fn detects_side_panels() {
+        let left = ScreenRect::new(0.0, 600.0, 40.0, 40.0);
+        let right = ScreenRect::new(1880.0, 600.0, 40.0, 40.0);
+        assert_eq!(tray_edge(left, SCREEN), TrayEdge::Left);
+        assert_eq!(tray_edge(right, SCREEN), TrayEdge::Right);
+    }
+
+    #[test]
+    fn top_tray_places_window_below_icon() {
+        let tray = ScreenRect::new(1000.0, 0.0, 24.0, 24.0);
+        assert_eq!(window_position(tray, WINDOW, Some(SCREEN)), (852.0, 24.0));
+    }
+
+    #[test]
+    fn bottom_tray_places_window_above_icon() {
+        let tray = ScreenRect::new(1000.0, 1040.0, 40.0, 40.0);
+        assert_eq!(window_position(tray, WINDOW, Some(SCREEN)), (860.0, 640.0));
+    }
+
+    #[test]
+    fn left_tray_places_window_to_the_right() {
+        let tray = ScreenRect::new(0.0, 500.0, 40.0, 40.0);
+        assert_eq!(window_position(tray, WINDOW, Some(SCREEN)), (40.0, 320.0));
+    }
+
+    #[test]
+    fn right_tray_places_window_to_the_left() {
+        let tray = ScreenRect::new(1880.0, 500.0, 40.0, 40.0);
+        assert_eq!(window_position(tray, WINDOW, Some(SCREEN)), (1560.0, 320.0));
+    }
+
+    #[test]
+    fn window_is_kept_on_screen_near_corner() {
+        let tray = ScreenRect::new(1890.0, 1050.0, 30.0, 30.0);
+        let (x, y) = window_position(tray, WINDOW, Some(SCREEN));
+        assert_eq!(x, 1600.0);
+        assert_eq!(y, 650.0);
+    }
+
+    #[test]
+    fn clamps_to_secondary_monitor_origin() {
+        let screen = ScreenRect::new(-1280.0, 0.0, 1280.0, 800.0);
+        let tray = ScreenRect::new(-1270.0, 0.0, 24.0, 24.0);
+        let (x, y) = window_position(tray, WINDOW, Some(screen));
+        assert_eq!(x, -1280.0);
+        assert_eq!(y, 24.0);
+    }
+
+    #[test]
+    fn unknown_screen_falls_back_to_below_icon() {
+        let tray = ScreenRect::new(100.0, 0.0, 20.0, 22.0);
+        assert_eq!(window_position(tray, WINDOW, None), (-50.0, 22.0));
+    }
+}
@@ -1,10 +1,16 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::duration::{self, DurationStyle};
+use crate::program::{Program, Segment, SegmentKind};
+
+/// What a segment is, as seen by notifications, sounds and the tray.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum Phase {
     Work,
     ShortBreak,
     LongBreak,
+    /// A program segment that is neither focus nor rest.
+    Other,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
@@ -13,7 +19,8 @@ pub struct PhaseTransition {
     pub to: Phase,
 }
 
-#[derive(Debug, Clone, Copy, Serialize)]
+/// Durations for the built-in classic program (see [`Program::classic`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PomodoroConfig {
     pub work_secs: u32,
     pub short_break_secs: u32,
@@ -37,42 +44,131 @@ pub enum PomodoroStatus {
     Idle,
     Running,
     Paused,
+    /// A phase ended and the next one waits for an explicit start.
+    Waiting,
+}
+
+/// Whether the next phase starts by itself when the current one ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoStart {
+    pub breaks: bool,
+    pub work: bool,
 }
 
+impl Default for AutoStart {
+    fn default() -> Self {
+        Self {
+            breaks: true,
+            work: true,
+        }
+    }
+}
+
+impl AutoStart {
+    fn starts(self, phase: Phase) -> bool {
+        match phase {
+            Phase::Work | Phase::Other => self.work,
+            Phase::ShortBreak | Phase::LongBreak => self.breaks,
+        }
+    }
+}
+
+/// How long into the following work phase a just-ended break can still be snoozed.
+pub const SNOOZE_WINDOW_SECS: u32 = 5 * 60;
+
+/// Runs a [`Program`]: its segments in order, starting over after the last.
 #[derive(Debug, Clone, Serialize)]
 pub struct PomodoroTimer {
-    config: PomodoroConfig,
+    program: Program,
+    /// `program` with repeats expanded; never empty.
+    segments: Vec<Segment>,
+    /// The current segment, or the postponed break during extra work.
+    index: usize,
     phase: Phase,
+    /// Length of the current phase; differs from the segment for a snoozed
+    /// break or postponed work.
+    phase_secs: u32,
     remaining_secs: u32,
     completed_sessions: u32,
     status: PomodoroStatus,
+    /// Index of the break that ended to start the current work phase, while
+    /// it can be snoozed.
+    ended_break: Option<usize>,
+    snoozes: u32,
+    /// Seconds spent in the current [`PomodoroStatus::Waiting`].
+    waiting_secs: u32,
+    /// Seconds spent waiting since the timer was last reset.
+    total_waiting_secs: u32,
+    /// Extra work before the break at `index`, set by
+    /// [`PomodoroTimer::postpone_break`]; the break follows it without
+    /// counting another session.
+    postponed: bool,
 }
 
 impl PomodoroTimer {
+    /// The classic program with the given durations.
     pub fn new(config: PomodoroConfig) -> Self {
+        Self::with_program(Program::classic(config))
+    }
+
+    /// Run `program`, which must have at least one segment (see
+    /// [`Program::validate`]); an empty one falls back to the classic program.
+    pub fn with_program(program: Program) -> Self {
+        let mut segments = program.segments();
+        if segments.is_empty() {
+            segments = Program::classic(PomodoroConfig::default()).segments();
+        }
+        let first = &segments[0];
         Self {
-            remaining_secs: config.work_secs,
-            config,
-            phase: Phase::Work,
+            phase: first.phase(),
+            phase_secs: first.secs,
+            remaining_secs: first.secs,
+            program,
+            segments,
+            index: 0,
             completed_sessions: 0,
             status: PomodoroStatus::Idle,
+            ended_break: None,
+            snoozes: 0,
+            waiting_secs: 0,
+            total_waiting_secs: 0,
+            postponed: false,
         }
     }
 
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
     pub fn phase(&self) -> Phase {
         self.phase
     }
 
+    /// The segment running now; `None` during extra work from a postponed break.
+    pub fn segment(&self) -> Option<&Segment> {
+        (!self.postponed).then(|| &self.segments[self.index])
+    }
+
+    /// Position of the current segment in the expanded program.
+    pub fn segment_index(&self) -> usize {
+        self.index
+    }
+
     pub fn remaining_secs(&self) -> u32 {
         self.remaining_secs
     }
 
     pub fn phase_duration_secs(&self) -> u32 {
-        match self.phase {
-            Phase::Work => self.config.work_secs,
-            Phase::ShortBreak => self.config.short_break_secs,
-            Phase::LongBreak => self.config.long_break_secs,
-        }
+        self.phase_secs
+    }
+
+    /// Focus segments in one pass through the program.
+    pub fn sessions_per_cycle(&self) -> u32 {
+        self.segments
+            .iter()
+            .filter(|s| s.kind == SegmentKind::Focus)
+            .count() as u32
     }
 
     pub fn completed_sessions(&self) -> u32 {
@@ -85,6 +181,16 @@ impl PomodoroTimer {
 
     pub fn start(&mut self) {
         self.status = PomodoroStatus::Running;
+        self.waiting_secs = 0;
+    }
+
+    /// How long the next phase has been waiting to be started.
+    pub fn waiting_secs(&self) -> Option<u32> {
+        (self.status == PomodoroStatus::Waiting).then_some(self.waiting_secs)
+    }
+
+    pub fn total_waiting_secs(&self) -> u32 {
+        self.total_waiting_secs
     }
 
     pub fn pause(&mut self) {
@@ -94,54 +200,142 @@ impl PomodoroTimer {
     }
 
     pub fn reset(&mut self) {
-        self.phase = Phase::Work;
-        self.remaining_secs = self.config.work_secs;
+        self.enter(0);
         self.completed_sessions = 0;
         self.status = PomodoroStatus::Idle;
+        self.ended_break = None;
+        self.snoozes = 0;
+        self.waiting_secs = 0;
+        self.total_waiting_secs = 0;
+    }
+
+    /// Make segment `index` current, from its start.
+    fn enter(&mut self, index: usize) {
+        let segment = &self.segments[index];
+        self.index = index;
+        self.phase = segment.phase();
+        self.phase_secs = segment.secs;
+        self.remaining_secs = segment.secs;
+        self.postponed = false;
+    }
+
+    /// Snoozes since the timer was last reset.
+    pub fn snooze_count(&self) -> u32 {
+        self.snoozes
+    }
+
+    /// A break just ended and the work phase that followed is still young.
+    pub fn can_snooze_break(&self) -> bool {
+        self.ended_break.is_some()
+            && self.phase == Phase::Work
+            && self.phase_secs - self.remaining_secs <= SNOOZE_WINDOW_SECS
+    }
+
+    /// Roll back to the break that just ended and run it for `secs` more.
+    /// Returns false when there is no break to snooze.
+    pub fn snooze_break(&mut self, secs: u32) -> bool {
+        if secs == 0 || !self.can_snooze_break() {
+            return false;
+        }
+        let Some(index) = self.ended_break.take() else {
+            return false;
+        };
+        self.enter(index);
+        self.phase_secs = secs;
+        self.remaining_secs = secs;
+        self.status = PomodoroStatus::Running;
+        self.snoozes += 1;
+        true
+    }
+
+    /// Seconds of the current break that have passed, while one is running or
+    /// waiting to start. A break is a segment with a break overlay.
+    pub fn break_elapsed_secs(&self) -> Option<u32> {
+        let on_break = self.segment().is_some_and(Segment::shows_overlay)
+            && matches!(
+                self.status,
+                PomodoroStatus::Running | PomodoroStatus::Waiting
+            );
+        on_break.then(|| self.phase_secs - self.remaining_secs)
     }
 
+    /// Work for `secs` more and take the current break after that. Returns
+    /// false when there is no break to postpone.
+    pub fn postpone_break(&mut self, secs: u32) -> bool {
+        if secs == 0 || self.break_elapsed_secs().is_none() {
+            return false;
+        }
+        self.postponed = true;
+        self.ended_break = None;
+        self.phase = Phase::Work;
+        self.phase_secs = secs;
+        self.remaining_secs = secs;
+        self.status = PomodoroStatus::Running;
+        self.waiting_secs = 0;
+        true
+    }
+
+    /// Tick with every phase starting automatically.
     pub fn tick(&mut self) -> Option<PhaseTransition> {
-        if self.status != PomodoroStatus::Running {
-            return None;
+        self.tick_with(AutoStart::default())
+    }
+
+    /// Count down one second. At a phase boundary the next phase either starts
+    /// or, when `auto_start` says not to, waits for [`PomodoroTimer::start`].
+    pub fn tick_with(&mut self, auto_start: AutoStart) -> Option<PhaseTransition> {
+        match self.status {
+            PomodoroStatus::Waiting => {
+                self.waiting_secs += 1;
+                self.total_waiting_secs += 1;
+                return None;
+            }
+            PomodoroStatus::Running => {}
+            PomodoroStatus::Idle | PomodoroStatus::Paused => return None,
         }
         self.remaining_secs = self.remaining_secs.saturating_sub(1);
-        if self.remaining_secs == 0 {
-            let from = self.phase;
-            let to = match self.phase {
-                Phase::Work => {
-                    self.completed_sessions += 1;
-                    if self
-                        .completed_sessions
-                        .is_multiple_of(self.config.sessions_before_long_break)
-                    {
-                        Phase::LongBreak
-                    } else {
-                        Phase::ShortBreak
-                    }
-                }
-                Phase::ShortBreak | Phase::LongBreak => Phase::Work,
-            };
-            self.phase = to;
-            self.remaining_secs = match to {
-                Phase::Work => self.config.work_secs,
-                Phase::ShortBreak => self.config.short_break_secs,
-                Phase::LongBreak => self.config.long_break_secs,
-            };
-            Some(PhaseTransition { from, to })
+        if self.remaining_secs > 0 {
+            return None;
+        }
+        let transition = self.advance();
+        if !auto_start.starts(transition.to) {
+            self.status = PomodoroStatus::Waiting;
+            self.waiting_secs = 0;
+        }
+        Some(transition)
+    }
+
+    /// End the current phase early and move to the next one, keeping the
+    /// running/paused status. A skipped work phase counts as completed.
+    pub fn skip(&mut self) -> PhaseTransition {
+        self.advance()
+    }
+
+    fn advance(&mut self) -> PhaseTransition {
+        let from = self.phase;
+        let from_index = self.index;
+        let next = if self.postponed {
+            self.index
         } else {
-            None
+            if self.segments[self.index].kind == SegmentKind::Focus {
+                self.completed_sessions += 1;
+            }
+            (self.index + 1) % self.segments.len()
+        };
+        let was_break = !self.postponed && matches!(from, Phase::ShortBreak | Phase::LongBreak);
+        self.enter(next);
+        self.ended_break = (was_break && self.phase == Phase::Work).then_some(from_index);
+        PhaseTransition {
+            from,
+            to: self.phase,
         }
     }
 
     pub fn display(&self) -> String {
-        let total = self.remaining_secs;
-        let m = total / 60;
-        let s = total % 60;
-        format!("{m:02}:{s:02}")
+        duration::format(self.remaining_secs, DurationStyle::Clock)
     }
 
     pub fn session_display(&self) -> String {
-        let total = self.config.sessions_before_long_break;
+        let total = self.sessions_per_cycle();
         (0..total)
             .map(|i| {
                 if i < self.completed_sessions {
@@ -153,19 +347,12 @@ impl PomodoroTimer {
             .collect::<Vec<_>>()
             .join(" ")
     }
-
-    pub fn tray_title(&self) -> String {
-        let icon = match self.phase {
-            Phase::Work => "🍅",
-            Phase::ShortBreak | Phase::LongBreak => "☕",
-        };
-        format!("{icon} {}", self.display())
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::program::Step;
 
     fn default_timer() -> PomodoroTimer {
         PomodoroTimer::new(PomodoroConfig::default())
@@ -287,22 +474,6 @@ mod tests {
         assert_eq!(timer.session_display(), "● ○ ○ ○");
     }
 
-    #[test]
-    fn tray_title_combines_icon_and_time() {
-        let timer = default_timer();
-        assert_eq!(timer.tray_title(), "🍅 25:00");
-    }
-
-    #[test]
-    fn tray_title_during_break() {
-        let mut timer = fast_timer();
-        timer.start();
-        for _ in 0..3 {
-            timer.tick();
-        }
-        assert_eq!(timer.tray_title(), "☕ 00:01");
-    }
-
     #[test]
     fn custom_config_uses_custom_durations() {
         let config = PomodoroConfig {
@@ -333,6 +504,33 @@ mod tests {
         assert_eq!(timer.remaining_secs(), 3);
     }
 
+    #[test]
+    fn skip_moves_to_next_phase_and_counts_session() {
+        let mut timer = fast_timer();
+        let transition = timer.skip();
+        assert_eq!(
+            transition,
+            PhaseTransition {
+                from: Phase::Work,
+                to: Phase::ShortBreak
+            }
+        );
+        assert_eq!(timer.completed_sessions(), 1);
+        assert_eq!(timer.remaining_secs(), 1);
+        assert_eq!(timer.status(), PomodoroStatus::Idle);
+    }
+
+    #[test]
+    fn skip_break_returns_to_work() {
+        let mut timer = fast_timer();
+        timer.start();
+        timer.skip();
+        let transition = timer.skip();
+        assert_eq!(transition.to, Phase::Work);
+        assert_eq!(timer.remaining_secs(), 3);
+        assert_eq!(timer.status(), PomodoroStatus::Running);
+    }
+
     #[test]
     fn reset_restores_initial_state() {
         let mut timer = fast_timer();
@@ -346,4 +544,326 @@ mod tests {
         assert_eq!(timer.completed_sessions(), 0);
         assert_eq!(timer.status(), PomodoroStatus::Idle);
     }
+
+    fn finish_break(timer: &mut PomodoroTimer) {
+        timer.start();
+        for _ in 0..4 {
+            timer.tick();
+        }
+        assert_eq!(timer.phase(), Phase::Work);
+    }
+
+    #[test]
+    fn just_ended_break_can_be_snoozed() {
+        let mut timer = fast_timer();
+        assert!(!timer.can_snooze_break());
+        finish_break(&mut timer);
+        assert!(timer.can_snooze_break());
+        assert!(timer.snooze_break(300));
+        assert_eq!(timer.phase(), Phase::ShortBreak);
+        assert_eq!(timer.remaining_secs(), 300);
+        assert_eq!(timer.phase_duration_secs(), 300);
+        assert_eq!(timer.completed_sessions(), 1);
+        assert_eq!(timer.status(), PomodoroStatus::Running);
+        assert_eq!(timer.snooze_count(), 1);
+        // Only once per ended break.
+        assert!(!timer.snooze_break(300));
+    }
+
+    #[test]
+    fn snoozed_break_ends_into_work_again() {
+        let mut timer = fast_timer();
+        finish_break(&mut timer);
+        timer.snooze_break(2);
+        timer.tick();
+        let t = timer.tick().unwrap();
+        assert_eq!(t.from, Phase::ShortBreak);
+        assert_eq!(t.to, Phase::Work);
+        assert_eq!(timer.phase_duration_secs(), 3);
+        assert!(timer.can_snooze_break());
+    }
+
+    #[test]
+    fn break_cannot_be_snoozed_after_the_window() {
+        let mut timer = PomodoroTimer::new(PomodoroConfig {
+            work_secs: SNOOZE_WINDOW_SECS + 10,
+            short_break_secs: 1,
+            long_break_secs: 1,
+            sessions_before_long_break: 4,
+        });
+        timer.skip();
+        timer.start();
+        timer.tick();
+        for _ in 0..SNOOZE_WINDOW_SECS {
+            timer.tick();
+        }
+        assert!(timer.can_snooze_break());
+        timer.tick();
+        assert!(!timer.can_snooze_break());
+        assert!(!timer.snooze_break(60));
+    }
+
+    #[test]
+    fn work_end_is_not_a_break_to_snooze() {
+        let mut timer = fast_timer();
+        timer.skip();
+        assert_eq!(timer.phase(), Phase::ShortBreak);
+        assert!(!timer.snooze_break(60));
+    }
+
+    #[test]
+    fn reset_clears_snoozes() {
+        let mut timer = fast_timer();
+        finish_break(&mut timer);
+        timer.snooze_break(60);
+        timer.reset();
+        assert_eq!(timer.snooze_count(), 0);
+        assert!(!timer.can_snooze_break());
+    }
+
+    const MANUAL: AutoStart = AutoStart {
+        breaks: false,
+        work: false,
+    };
+
+    #[test]
+    fn manual_start_waits_at_phase_boundary() {
+        let mut timer = fast_timer();
+        timer.start();
+        timer.tick_with(MANUAL);
+        timer.tick_with(MANUAL);
+        let transition = timer.tick_with(MANUAL);
+        assert_eq!(
+            transition,
+            Some(PhaseTransition {
+                from: Phase::Work,
+                to: Phase::ShortBreak
+            })
+        );
+        assert_eq!(timer.status(), PomodoroStatus::Waiting);
+        assert_eq!(timer.phase(), Phase::ShortBreak);
+        assert_eq!(timer.remaining_secs(), 1);
+        assert_eq!(timer.waiting_secs(), Some(0));
+
+        // Waiting does not count down the next phase.
+        assert_eq!(timer.tick_with(MANUAL), None);
+        assert_eq!(timer.tick_with(MANUAL), None);
+        assert_eq!(timer.remaining_secs(), 1);
+        assert_eq!(timer.waiting_secs(), Some(2));
+
+        timer.start();
+        assert_eq!(timer.status(), PomodoroStatus::Running);
+        assert_eq!(timer.waiting_secs(), None);
+        assert_eq!(timer.total_waiting_secs(), 2);
+    }
+
+    #[test]
+    fn auto_start_is_per_phase_kind() {
+        let only_breaks = AutoStart {
+            breaks: true,
+            work: false,
+        };
+        let mut timer = fast_timer();
+        timer.start();
+        for _ in 0..3 {
+            timer.tick_with(only_breaks);
+        }
+        assert_eq!(timer.phase(), Phase::ShortBreak);
+        assert_eq!(timer.status(), PomodoroStatus::Running);
+        timer.tick_with(only_breaks);
+        assert_eq!(timer.phase(), Phase::Work);
+        assert_eq!(timer.status(), PomodoroStatus::Waiting);
+    }
+
+    #[test]
+    fn waiting_time_accumulates_until_reset() {
+        let mut timer = fast_timer();
+        timer.start();
+        for _ in 0..4 {
+            timer.tick_with(MANUAL);
+        }
+        timer.start();
+        timer.tick_with(MANUAL);
+        assert_eq!(timer.status(), PomodoroStatus::Waiting);
+        timer.tick_with(MANUAL);
+        assert_eq!(timer.total_waiting_secs(), 2);
+        timer.pause();
+        assert_eq!(timer.status(), PomodoroStatus::Waiting);
+        timer.reset();
+        assert_eq!(timer.total_waiting_secs(), 0);
+        assert_eq!(timer.waiting_secs(), None);
+    }
+
+    #[test]
+    fn auto_start_settings_default_to_on() {
+        let parsed: AutoStart = serde_json::from_str(r#"{"work": false}"#).unwrap();
+        assert!(parsed.breaks);
+        assert!(!parsed.work);
+    }
+
+    #[test]
+    fn breaks_are_segments_with_an_overlay() {
+        let program: Program = serde_json::from_str(
+            r#"{"name": "stretchy", "steps": [
+                {"segment": {"name": "Focus", "secs": 2, "kind": "focus"}},
+                {"segment": {"name": "Stretch", "secs": 2, "kind": "other", "overlay": true}},
+                {"segment": {"name": "Tea", "secs": 2, "kind": "rest", "overlay": false}}
+            ]}"#,
+        )
+        .unwrap();
+        let mut timer = PomodoroTimer::with_program(program);
+        timer.start();
+        assert_eq!(timer.break_elapsed_secs(), None);
+        timer.skip();
+        timer.tick();
+        assert_eq!(timer.phase(), Phase::Other);
+        assert_eq!(timer.break_elapsed_secs(), Some(1));
+        timer.skip();
+        assert_eq!(timer.phase(), Phase::ShortBreak);
+        assert_eq!(timer.break_elapsed_secs(), None);
+    }
+
+    #[test]
+    fn postponed_break_follows_extra_work() {
+        let mut timer = fast_timer();
+        timer.start();
+        timer.skip();
+        assert_eq!(timer.break_elapsed_secs(), Some(0));
+        assert!(timer.postpone_break(2));
+        assert_eq!(timer.phase(), Phase::Work);
+        assert_eq!(timer.remaining_secs(), 2);
+        assert_eq!(timer.break_elapsed_secs(), None);
+        timer.tick();
+        let transition = timer.tick().unwrap();
+        assert_eq!(transition.to, Phase::ShortBreak);
+        // The extra work does not count as another session.
+        assert_eq!(timer.completed_sessions(), 1);
+        assert_eq!(timer.remaining_secs(), 1);
+    }
+
+    #[test]
+    fn only_breaks_can_be_postponed() {
+        let mut timer = fast_timer();
+        timer.start();
+        assert!(!timer.postpone_break(60));
+        timer.skip();
+        timer.pause();
+        assert!(!timer.postpone_break(60));
+        timer.start();
+        assert!(!timer.postpone_break(0));
+        assert!(timer.postpone_break(60));
+    }
+
+    // --- Program tests ---
+
+    fn segment(name: &str, secs: u32, kind: SegmentKind) -> Step {
+        Step::Segment(Segment::new(name, secs, kind))
+    }
+
+    fn program(steps: Vec<Step>) -> PomodoroTimer {
+        PomodoroTimer::with_program(Program {
+            name: "test".to_string(),
+            steps,
+        })
+    }
+
+    /// Skip through `n` segments and collect `(phase, secs)` of each one entered.
+    fn run(timer: &mut PomodoroTimer, n: usize) -> Vec<(Phase, u32)> {
+        (0..n)
+            .map(|_| {
+                timer.skip();
+                (timer.phase(), timer.remaining_secs())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fifty_ten_alternates() {
+        let mut timer = program(vec![
+            segment("Work", 50 * 60, SegmentKind::Focus),
+            segment("Break", 10 * 60, SegmentKind::Rest),
+        ]);
+        assert_eq!(
+            run(&mut timer, 3),
+            vec![
+                (Phase::ShortBreak, 10 * 60),
+                (Phase::Work, 50 * 60),
+                (Phase::ShortBreak, 10 * 60)
+            ]
+        );
+        assert_eq!(timer.completed_sessions(), 2);
+        assert_eq!(timer.sessions_per_cycle(), 1);
+    }
+
+    #[test]
+    fn ultradian_cycle_with_other_segment() {
+        let mut timer = program(vec![
+            segment("Deep Work", 90 * 60, SegmentKind::Focus),
+            segment("Recover", 20 * 60, SegmentKind::LongRest),
+            segment("Plan", 5 * 60, SegmentKind::Other),
+        ]);
+        assert_eq!(
+            run(&mut timer, 3),
+            vec![
+                (Phase::LongBreak, 20 * 60),
+                (Phase::Other, 5 * 60),
+                (Phase::Work, 90 * 60)
+            ]
+        );
+        assert_eq!(timer.segment().unwrap().name, "Deep Work");
+        assert_eq!(timer.display(), "1:30:00");
+        // Only focus segments count as sessions.
+        assert_eq!(timer.completed_sessions(), 1);
+    }
+
+    #[test]
+    fn work_lengths_can_differ_per_session() {
+        let mut timer = program(vec![
+            Step::Repeat {
+                times: 2,
+                steps: vec![
+                    segment("Work", 25 * 60, SegmentKind::Focus),
+                    segment("Break", 5 * 60, SegmentKind::Rest),
+                ],
+            },
+            segment("Long Work", 50 * 60, SegmentKind::Focus),
+            segment("Long Break", 15 * 60, SegmentKind::LongRest),
+        ]);
+        let secs: Vec<u32> = run(&mut timer, 6)
+            .into_iter()
+            .map(|(_, s)| s / 60)
+            .collect();
+        assert_eq!(secs, vec![5, 25, 5, 50, 15, 25]);
+        assert_eq!(timer.sessions_per_cycle(), 3);
+        assert_eq!(timer.session_display(), "● ● ●");
+    }
+
+    #[test]
+    fn snooze_returns_to_the_same_segment() {
+        let mut timer = program(vec![
+            segment("Work", 3, SegmentKind::Focus),
+            segment("Stretch", 1, SegmentKind::Rest),
+            segment("Work", 3, SegmentKind::Focus),
+            segment("Walk", 2, SegmentKind::LongRest),
+        ]);
+        timer.start();
+        run(&mut timer, 4);
+        assert_eq!(timer.segment_index(), 0);
+        assert!(timer.snooze_break(60));
+        assert_eq!(timer.segment().unwrap().name, "Walk");
+        assert_eq!(timer.remaining_secs(), 60);
+        timer.skip();
+        assert_eq!(timer.segment_index(), 0);
+    }
+
+    #[test]
+    fn postponed_work_has_no_segment() {
+        let mut timer = fast_timer();
+        timer.start();
+        timer.skip();
+        timer.postpone_break(60);
+        assert_eq!(timer.segment(), None);
+        timer.skip();
+        assert_eq!(timer.segment().unwrap().name, "Short Break");
+    }
 }
@@ -0,0 +1,620 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{EventSound, SoundEvent};
+use crate::interval::IntervalConfig;
+use crate::pomodoro::PomodoroConfig;
+use crate::program::{Program, CLASSIC, MAX_SEGMENT_SECS};
+
+pub const MAX_PRESETS: usize = 64;
+pub const MAX_NAME_CHARS: usize = 40;
+/// Version written to export files; newer files are rejected on import.
+pub const FILE_VERSION: u32 = 1;
+
+/// The timer a preset starts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PresetKind {
+    Basic {
+        secs: u32,
+    },
+    Pomodoro(PomodoroConfig),
+    /// A pomodoro program by name; see `get_programs`.
+    Program {
+        name: String,
+    },
+    Interval(IntervalConfig),
+}
+
+impl PresetKind {
+    /// Whether this starts the user program `name`.
+    fn runs(&self, name: &str) -> bool {
+        matches!(self, PresetKind::Program { name: n } if n == name)
+    }
+
+    fn validate(&self, programs: &[Program]) -> Result<(), String> {
+        match self {
+            PresetKind::Basic { secs } => {
+                if !(1..=MAX_SEGMENT_SECS).contains(secs) {
+                    return Err(format!("secs: must be between 1 and {MAX_SEGMENT_SECS}"));
+                }
+            }
+            PresetKind::Pomodoro(config) => {
+                for (name, secs) in [
+                    ("work_secs", config.work_secs),
+                    ("short_break_secs", config.short_break_secs),
+                    ("long_break_secs", config.long_break_secs),
+                ] {
+                    if !(1..=MAX_SEGMENT_SECS).contains(&secs) {
+                        return Err(format!("{name}: must be between 1 and {MAX_SEGMENT_SECS}"));
+                    }
+                }
+                if config.sessions_before_long_break == 0 {
+                    return Err("sessions_before_long_break: must be at least 1".to_string());
+                }
+            }
+            PresetKind::Program { name } => {
+                if name != CLASSIC && !programs.iter().any(|p| &p.name == name) {
+                    return Err(format!("name: unknown program {name:?}"));
+                }
+            }
+            PresetKind::Interval(config) => config.validate()?,
+        }
+        Ok(())
+    }
+}
+
+/// A named timer setup such as "Tea 3 min" or "Deep work 50/10".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    /// Stable identifier; assigned from the name when a preset is created.
+    #[serde(default)]
+    pub id: String,
+    /// Shown in preset lists and the tray menu.
+    pub name: String,
+    pub kind: PresetKind,
+    /// Shown with the running timer instead of the name.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Tray icon while it runs, instead of the one for the phase.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Played when the timer finishes or a work phase ends, instead of the
+    /// sound from settings.
+    #[serde(default)]
+    pub sound: Option<EventSound>,
+    /// Listed first in the tray menu.
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+impl Preset {
+    fn new(id: &str, name: &str, kind: PresetKind) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            kind,
+            label: None,
+            icon: None,
+            sound: None,
+            favorite: false,
+        }
+    }
+
+    /// A preset that is never saved and only names a timer, such as the
+    /// "tea" in a quick command "tea 4m".
+    pub fn unsaved(name: &str, kind: PresetKind) -> Self {
+        Self::new("", name, kind)
+    }
+
+    /// What the running timer is called.
+    pub fn display_name(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+
+    /// This preset's sound for `event`, if it overrides the one from settings.
+    pub fn sound_for(&self, event: SoundEvent) -> Option<&EventSound> {
+        match event {
+            SoundEvent::TimerFinished | SoundEvent::WorkFinished => self.sound.as_ref(),
+            SoundEvent::BreakFinished => None,
+        }
+    }
+
+    fn validate(&self, programs: &[Program]) -> Result<(), String> {
+        if self.id.is_empty() || self.id != slug(&self.id) {
+            return Err(format!(
+                "id: {:?} must be lowercase letters, digits and dashes",
+                self.id
+            ));
+        }
+        validate_text(&self.name).map_err(|e| format!("name: {e}"))?;
+        if let Some(label) = &self.label {
+            validate_text(label).map_err(|e| format!("label: {e}"))?;
+        }
+        if self.icon.as_deref().is_some_and(|i| i.trim().is_empty()) {
+            return Err("icon: must not be empty".to_string());
+        }
+        self.kind
+            .validate(programs)
+            .map_err(|e| format!("kind.{e}"))?;
+        if let Some(sound) = &self.sound {
+            sound.validate().map_err(|e| format!("sound: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_text(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    if text.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("must be at most {MAX_NAME_CHARS} characters"));
+    }
+    Ok(())
+}
+
+/// The name `program` is imported under: its own, "Name 2", "Name 3"… up to
+/// the first that is free or already names the same steps.
+fn free_program_name(programs: &[Program], program: &Program) -> String {
+    let mut name = program.name.clone();
+    let mut n = 2;
+    loop {
+        let taken = match programs.iter().find(|p| p.name == name) {
+            Some(existing) => existing.steps != program.steps,
+            None => name == CLASSIC,
+        };
+        if !taken {
+            return name;
+        }
+        name = format!("{} {n}", program.name);
+        n += 1;
+    }
+}
+
+/// "Deep work 50/10" → "deep-work-50-10".
+fn slug(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+/// The preset library, in the order it is listed. Starts with the presets
+/// the tray menu always offered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Presets(Vec<Preset>);
+
+impl Default for Presets {
+    fn default() -> Self {
+        let basic = |id: &str, name: &str, mins: u32| {
+            Preset::new(id, name, PresetKind::Basic { secs: mins * 60 })
+        };
+        Self(vec![
+            basic("timer-5", "Timer 5 min", 5),
+            basic("timer-15", "Timer 15 min", 15),
+            basic("timer-30", "Timer 30 min", 30),
+            basic("timer-60", "Timer 60 min", 60),
+            Preset::new(
+                "pomodoro-25-5",
+                "Pomodoro 25/5",
+                PresetKind::Pomodoro(PomodoroConfig::default()),
+            ),
+            Preset::new(
+                "pomodoro-50-10",
+                "Pomodoro 50/10",
+                PresetKind::Pomodoro(PomodoroConfig {
+                    work_secs: 50 * 60,
+                    short_break_secs: 10 * 60,
+                    long_break_secs: 30 * 60,
+                    sessions_before_long_break: 3,
+                }),
+            ),
+            Preset::new(
+                "tabata-20-10",
+                "Tabata 20/10",
+                PresetKind::Interval(IntervalConfig::default()),
+            ),
+        ])
+    }
+}
+
+impl Presets {
+    /// `programs` are the ones from settings; the classic one always exists.
+    pub fn validate(&self, programs: &[Program]) -> Result<(), String> {
+        if self.0.len() > MAX_PRESETS {
+            return Err(format!(": at most {MAX_PRESETS} allowed"));
+        }
+        for (i, preset) in self.0.iter().enumerate() {
+            preset
+                .validate(programs)
+                .map_err(|e| format!("[{i}].{e}"))?;
+            if self.0[..i].iter().any(|p| p.id == preset.id) {
+                return Err(format!("[{i}].id: {:?} is already taken", preset.id));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn list(&self) -> &[Preset] {
+        &self.0
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Preset> {
+        self.0.iter().find(|p| p.id == id)
+    }
+
+    /// The preset with `text` as its id or name, ignoring case.
+    pub fn find(&self, text: &str) -> Option<&Preset> {
+        let text = text.trim();
+        self.0.iter().find(|p| {
+            p.id.eq_ignore_ascii_case(text) || p.name.to_lowercase() == text.to_lowercase()
+        })
+    }
+
+    fn position(&self, id: &str) -> Result<usize, String> {
+        self.0
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| format!("unknown preset {id:?}"))
+    }
+
+    /// An unused id based on `wanted`, or on `name` when that is empty.
+    fn free_id(&self, wanted: &str, name: &str) -> String {
+        let base = match slug(wanted) {
+            id if id.is_empty() => slug(name),
+            id => id,
+        };
+        let base = if base.is_empty() {
+            "preset".to_string()
+        } else {
+            base
+        };
+        let mut id = base.clone();
+        let mut n = 2;
+        while self.get(&id).is_some() {
+            id = format!("{base}-{n}");
+            n += 1;
+        }
+        id
+    }
+
+    /// Add `preset` at the end, under a fresh id. Returns it as stored.
+    pub fn create(&mut self, mut preset: Preset) -> Preset {
+        preset.id = self.free_id(&preset.id, &preset.name);
+        self.0.push(preset.clone());
+        preset
+    }
+
+    /// Replace the preset with the same id.
+    pub fn update(&mut self, preset: Preset) -> Result<Preset, String> {
+        let i = self.position(&preset.id)?;
+        self.0[i] = preset.clone();
+        Ok(preset)
+    }
+
+    pub fn delete(&mut self, id: &str) -> Result<Preset, String> {
+        let i = self.position(id)?;
+        Ok(self.0.remove(i))
+    }
+
+    /// Move a preset to `index`, or to the end when past it.
+    pub fn move_to(&mut self, id: &str, index: usize) -> Result<(), String> {
+        let preset = self.delete(id)?;
+        let index = index.min(self.0.len());
+        self.0.insert(index, preset);
+        Ok(())
+    }
+
+    pub fn set_favorite(&mut self, id: &str, favorite: bool) -> Result<Preset, String> {
+        let i = self.position(id)?;
+        self.0[i].favorite = favorite;
+        Ok(self.0[i].clone())
+    }
+
+    /// Favourites first, each group in library order, with their indices.
+    pub fn menu_order(&self) -> Vec<(usize, &Preset)> {
+        let (mut favorites, rest): (Vec<_>, Vec<_>) =
+            self.0.iter().enumerate().partition(|(_, p)| p.favorite);
+        favorites.extend(rest);
+        favorites
+    }
+
+    /// The presets with `ids`, or all of them, for sharing, along with the
+    /// `programs` they run.
+    pub fn export(
+        &self,
+        ids: Option<&[String]>,
+        programs: &[Program],
+    ) -> Result<PresetFile, String> {
+        let presets: Vec<Preset> = match ids {
+            None => self.0.clone(),
+            Some(ids) => ids
+                .iter()
+                .map(|id| {
+                    self.get(id)
+                        .cloned()
+                        .ok_or(format!("unknown preset {id:?}"))
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        let programs = programs
+            .iter()
+            .filter(|program| presets.iter().any(|p| p.kind.runs(&program.name)))
+            .cloned()
+            .collect();
+        Ok(PresetFile {
+            version: FILE_VERSION,
+            presets,
+            programs,
+        })
+    }
+
+    /// Add the presets from `file`; ids already in use get a suffix. The
+    /// programs they run are added to `programs`, unless the same one is
+    /// already there; one whose name is taken by a different program gets a
+    /// number after its name.
+    pub fn import(
+        &mut self,
+        file: PresetFile,
+        programs: &mut Vec<Program>,
+    ) -> Result<Vec<Preset>, String> {
+        if file.version > FILE_VERSION {
+            return Err(format!(
+                "preset file version {} is newer than this app supports",
+                file.version
+            ));
+        }
+        let mut presets = file.presets;
+        for program in file.programs {
+            let name = free_program_name(programs, &program);
+            for preset in &mut presets {
+                if preset.kind.runs(&program.name) {
+                    preset.kind = PresetKind::Program { name: name.clone() };
+                }
+            }
+            if !programs.iter().any(|p| p.name == name) {
+                programs.push(Program { name, ..program });
+            }
+        }
+        Ok(presets.into_iter().map(|p| self.create(p)).collect())
+    }
+}
+
+/// Presets exported to share, as written to disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetFile {
+    pub version: u32,
+    pub presets: Vec<Preset>,
+    /// The user programs the presets run, so they work where they are imported.
+    #[serde(default)]
+    pub programs: Vec<Program>,
+}
+
+impl PresetFile {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("presets serialize")
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|e| format!("not a preset file: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tea() -> Preset {
+        Preset::new("", "Tea 3 min", PresetKind::Basic { secs: 180 })
+    }
+
+    fn ids(presets: &Presets) -> Vec<&str> {
+        presets.list().iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        let presets = Presets::default();
+        assert!(presets.validate(&[]).is_ok());
+        assert_eq!(presets.list().len(), 7);
+        assert_eq!(presets.list()[0].name, "Timer 5 min");
+    }
+
+    #[test]
+    fn find_matches_id_or_name_ignoring_case() {
+        let presets = Presets::default();
+        assert_eq!(presets.find("TIMER-5").unwrap().name, "Timer 5 min");
+        assert_eq!(presets.find(" tabata 20/10 ").unwrap().id, "tabata-20-10");
+        assert!(presets.find("tabata").is_none());
+    }
+
+    #[test]
+    fn parses_settings_json() {
+        let json = r#"[
+            {"name": "Tea", "kind": {"kind": "basic", "secs": 180}, "icon": "🍵", "id": "tea"},
+            {"id": "deep", "name": "Deep work", "favorite": true,
+             "kind": {"kind": "pomodoro", "work_secs": 3000, "short_break_secs": 600,
+                      "long_break_secs": 1800, "sessions_before_long_break": 3}},
+            {"id": "classic", "name": "Classic", "kind": {"kind": "program", "name": "classic"}}
+        ]"#;
+        let presets: Presets = serde_json::from_str(json).unwrap();
+        assert!(presets.validate(&[]).is_ok());
+        assert_eq!(presets.get("tea").unwrap().icon.as_deref(), Some("🍵"));
+        assert!(presets.get("deep").unwrap().favorite);
+    }
+
+    #[test]
+    fn validation() {
+        let check = |preset: Preset| Presets(vec![preset]).validate(&[]).unwrap_err();
+        let mut bad = tea();
+        assert!(check(bad.clone()).starts_with("[0].id:"));
+        bad.id = "tea".to_string();
+        bad.kind = PresetKind::Basic { secs: 0 };
+        assert!(check(bad.clone()).starts_with("[0].kind.secs:"));
+        bad.kind = PresetKind::Program {
+            name: "deep".to_string(),
+        };
+        assert!(check(bad.clone()).contains("unknown program"));
+        bad.kind = PresetKind::Basic { secs: 60 };
+        bad.label = Some(" ".to_string());
+        assert!(check(bad).starts_with("[0].label:"));
+
+        let mut twice = Presets::default();
+        twice.0.push(twice.0[0].clone());
+        assert!(twice.validate(&[]).unwrap_err().contains("already taken"));
+    }
+
+    #[test]
+    fn create_assigns_unique_ids() {
+        let mut presets = Presets::default();
+        assert_eq!(presets.create(tea()).id, "tea-3-min");
+        assert_eq!(presets.create(tea()).id, "tea-3-min-2");
+        let mut named = tea();
+        named.id = "Tea".to_string();
+        assert_eq!(presets.create(named).id, "tea");
+        let mut unnamed = tea();
+        unnamed.name = "お茶".to_string();
+        assert_eq!(presets.create(unnamed).id, "preset");
+        assert!(presets.validate(&[]).is_ok());
+    }
+
+    #[test]
+    fn update_delete_and_favorite() {
+        let mut presets = Presets::default();
+        let mut tea = presets.create(tea());
+        tea.kind = PresetKind::Basic { secs: 240 };
+        presets.update(tea.clone()).unwrap();
+        assert_eq!(
+            presets.get(&tea.id).unwrap().kind,
+            PresetKind::Basic { secs: 240 }
+        );
+        assert!(presets.set_favorite(&tea.id, true).unwrap().favorite);
+        assert_eq!(presets.delete(&tea.id).unwrap().name, "Tea 3 min");
+        assert!(presets.delete(&tea.id).is_err());
+        assert!(presets.update(tea).is_err());
+    }
+
+    #[test]
+    fn move_reorders() {
+        let mut presets = Presets::default();
+        presets.move_to("tabata-20-10", 0).unwrap();
+        presets.move_to("timer-5", 99).unwrap();
+        assert_eq!(ids(&presets)[0], "tabata-20-10");
+        assert_eq!(ids(&presets)[6], "timer-5");
+        assert!(presets.move_to("nope", 0).is_err());
+    }
+
+    #[test]
+    fn menu_lists_favorites_first() {
+        let mut presets = Presets::default();
+        presets.set_favorite("pomodoro-50-10", true).unwrap();
+        presets.set_favorite("timer-15", true).unwrap();
+        let order: Vec<usize> = presets.menu_order().iter().map(|(i, _)| *i).collect();
+        assert_eq!(order, [1, 5, 0, 2, 3, 4, 6]);
+    }
+
+    #[test]
+    fn export_then_import_round_trips() {
+        let mut presets = Presets::default();
+        let mut tea = tea();
+        tea.icon = Some("🍵".to_string());
+        let tea = presets.create(tea);
+        let file = presets
+            .export(Some(std::slice::from_ref(&tea.id)), &[])
+            .unwrap();
+        assert_eq!(file.version, FILE_VERSION);
+        let file = PresetFile::from_json(&file.to_json()).unwrap();
+
+        let mut other = Presets::default();
+        let mut programs = Vec::new();
+        let imported = other.import(file.clone(), &mut programs).unwrap();
+        assert_eq!(imported[0].id, "tea-3-min");
+        assert_eq!(imported[0].icon.as_deref(), Some("🍵"));
+        // Importing again keeps both.
+        assert_eq!(
+            other.import(file, &mut programs).unwrap()[0].id,
+            "tea-3-min-2"
+        );
+        assert!(programs.is_empty());
+
+        assert!(presets.export(Some(&["nope".to_string()]), &[]).is_err());
+        assert_eq!(presets.export(None, &[]).unwrap().presets.len(), 8);
+        let newer = PresetFile {
+            version: FILE_VERSION + 1,
+            presets: Vec::new(),
+            programs: Vec::new(),
+        };
+        assert!(other.import(newer, &mut programs).is_err());
+        assert!(PresetFile::from_json("[]").is_err());
+    }
+
+    #[test]
+    fn exports_the_programs_presets_run() {
+        let deep = |work_secs| Program {
+            name: "Deep".to_string(),
+            ..Program::classic(PomodoroConfig {
+                work_secs,
+                ..PomodoroConfig::default()
+            })
+        };
+        let unused = Program {
+            name: "Unused".to_string(),
+            ..deep(60)
+        };
+        let mut presets = Presets::default();
+        presets.create(Preset::new(
+            "deep",
+            "Deep",
+            PresetKind::Program {
+                name: "Deep".to_string(),
+            },
+        ));
+        let file = presets
+            .export(Some(&["deep".to_string()]), &[deep(3000), unused])
+            .unwrap();
+        assert_eq!(file.programs, [deep(3000)]);
+        let file = PresetFile::from_json(&file.to_json()).unwrap();
+
+        // Added where it is missing
+        let mut other = Presets(Vec::new());
+        let mut programs = Vec::new();
+        other.import(file.clone(), &mut programs).unwrap();
+        assert_eq!(programs, [deep(3000)]);
+        assert!(other.validate(&programs).is_ok());
+
+        // Reused when the same program is there
+        other.import(file.clone(), &mut programs).unwrap();
+        assert_eq!(programs.len(), 1);
+
+        // Renamed when a different one has its name
+        let mut programs = vec![deep(1500)];
+        let imported = other.import(file, &mut programs).unwrap();
+        assert_eq!(programs[1].name, "Deep 2");
+        assert_eq!(programs[1].steps, deep(3000).steps);
+        assert!(imported[0].kind.runs("Deep 2"));
+        assert!(other.validate(&programs).is_ok());
+
+        // Files written before programs were included still read
+        let old = r#"{"version": 1, "presets": []}"#;
+        assert!(PresetFile::from_json(old).unwrap().programs.is_empty());
+    }
+
+    #[test]
+    fn sound_overrides_finish_sounds_only() {
+        let mut tea = tea();
+        assert!(tea.sound_for(SoundEvent::TimerFinished).is_none());
+        tea.sound = Some(EventSound::default());
+        assert!(tea.sound_for(SoundEvent::TimerFinished).is_some());
+        assert!(tea.sound_for(SoundEvent::WorkFinished).is_some());
+        assert!(tea.sound_for(SoundEvent::BreakFinished).is_none());
+        assert_eq!(tea.display_name(), "Tea 3 min");
+        tea.label = Some("Tea".to_string());
+        assert_eq!(tea.display_name(), "Tea");
+    }
+}
@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+
+use crate::pomodoro::{Phase, PomodoroConfig};
+
+pub const MAX_SEGMENT_SECS: u32 = 24 * 60 * 60;
+/// Upper bound on a program's length once repeats are expanded.
+pub const MAX_SEGMENTS: usize = 500;
+pub const MAX_NAME_CHARS: usize = 40;
+/// Name of the built-in program built from a [`PomodoroConfig`].
+pub const CLASSIC: &str = "classic";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentKind {
+    /// Counts as a completed session when it ends.
+    Focus,
+    Rest,
+    /// A rest announced and shown as a long break.
+    LongRest,
+    Other,
+}
+
+/// One timed step of a program.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Segment {
+    pub name: String,
+    pub secs: u32,
+    pub kind: SegmentKind,
+    /// Tray icon for this segment instead of the one for its kind.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Announce the start of this segment.
+    #[serde(default = "default_notify")]
+    pub notify: bool,
+    /// Show the break overlay during this segment. Defaults to on for rests.
+    #[serde(default)]
+    pub overlay: Option<bool>,
+}
+
+fn default_notify() -> bool {
+    true
+}
+
+impl Segment {
+    pub fn new(name: &str, secs: u32, kind: SegmentKind) -> Self {
+        Self {
+            name: name.to_string(),
+            secs,
+            kind,
+            icon: None,
+            notify: true,
+            overlay: None,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        match self.kind {
+            SegmentKind::Focus => Phase::Work,
+            SegmentKind::Rest => Phase::ShortBreak,
+            SegmentKind::LongRest => Phase::LongBreak,
+            SegmentKind::Other => Phase::Other,
+        }
+    }
+
+    pub fn shows_overlay(&self) -> bool {
+        self.overlay.unwrap_or(matches!(
+            self.kind,
+            SegmentKind::Rest | SegmentKind::LongRest
+        ))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name).map_err(|e| format!("name: {e}"))?;
+        if !(1..=MAX_SEGMENT_SECS).contains(&self.secs) {
+            return Err(format!("secs: must be between 1 and {MAX_SEGMENT_SECS}"));
+        }
+        if self
+            .icon
+            .as_deref()
+            .is_some_and(|icon| icon.trim().is_empty())
+        {
+            return Err("icon: must not be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("must be at most {MAX_NAME_CHARS} characters"));
+    }
+    Ok(())
+}
+
+/// A segment, or a block of steps run `times` times in a row.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    Segment(Segment),
+    Repeat { times: u32, steps: Vec<Step> },
+}
+
+/// An ordered list of segments the pomodoro timer cycles through.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Program {
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+impl Program {
+    /// Work and short breaks, with a long break after every
+    /// `sessions_before_long_break` work sessions.
+    pub fn classic(config: PomodoroConfig) -> Self {
+        let work = Step::Segment(Segment::new("Work", config.work_secs, SegmentKind::Focus));
+        let short_break = Step::Segment(Segment::new(
+            "Short Break",
+            config.short_break_secs,
+            SegmentKind::Rest,
+        ));
+        let long_break = Step::Segment(Segment::new(
+            "Long Break",
+            config.long_break_secs,
+            SegmentKind::LongRest,
+        ));
+        let mut steps = Vec::new();
+        if config.sessions_before_long_break > 1 {
+            steps.push(Step::Repeat {
+                times: config.sessions_before_long_break - 1,
+                steps: vec![work.clone(), short_break],
+            });
+        }
+        steps.extend([work, long_break]);
+        Self {
+            name: CLASSIC.to_string(),
+            steps,
+        }
+    }
+
+    /// The segments in the order they run, with repeats expanded. Stops
+    /// expanding past [`MAX_SEGMENTS`].
+    pub fn segments(&self) -> Vec<Segment> {
+        fn expand(steps: &[Step], out: &mut Vec<Segment>) {
+            for step in steps {
+                match step {
+                    Step::Segment(segment) => {
+                        if out.len() > MAX_SEGMENTS {
+                            return;
+                        }
+                        out.push(segment.clone());
+                    }
+                    Step::Repeat { times, steps } => {
+                        for _ in 0..*times {
+                            if out.len() > MAX_SEGMENTS {
+                                return;
+                            }
+                            expand(steps, out);
+                        }
+                    }
+                }
+            }
+        }
+        let mut out = Vec::new();
+        expand(&self.steps, &mut out);
+        out
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name).map_err(|e| format!("name: {e}"))?;
+        fn check(steps: &[Step], path: &str) -> Result<(), String> {
+            for (i, step) in steps.iter().enumerate() {
+                let here = format!("{path}[{i}]");
+                match step {
+                    Step::Segment(segment) => {
+                        segment.validate().map_err(|e| format!("{here}.{e}"))?
+                    }
+                    Step::Repeat { times, steps } => {
+                        if *times == 0 {
+                            return Err(format!("{here}.times: must be at least 1"));
+                        }
+                        if steps.is_empty() {
+                            return Err(format!("{here}.steps: must not be empty"));
+                        }
+                        check(steps, &format!("{here}.steps"))?;
+                    }
+                }
+            }
+            Ok(())
+        }
+        check(&self.steps, "steps")?;
+        let len = self.segments().len();
+        if len == 0 {
+            return Err("steps: must contain a segment".to_string());
+        }
+        if len > MAX_SEGMENTS {
+            return Err(format!("steps: at most {MAX_SEGMENTS} segments allowed"));
+        }
+        Ok(())
+    }
+}
+
+/// Check user programs: each valid, names unique and not the built-in one.
+pub fn validate_programs(programs: &[Program]) -> Result<(), String> {
+    for (i, program) in programs.iter().enumerate() {
+        program.validate().map_err(|e| format!("[{i}].{e}"))?;
+        if program.name == CLASSIC || programs[..i].iter().any(|p| p.name == program.name) {
+            return Err(format!("[{i}].name: {:?} is already taken", program.name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(program: &Program) -> Vec<(String, u32)> {
+        program
+            .segments()
+            .into_iter()
+            .map(|s| (s.name, s.secs / 60))
+            .collect()
+    }
+
+    fn named(list: &[(&str, u32)]) -> Vec<(String, u32)> {
+        list.iter().map(|(n, m)| (n.to_string(), *m)).collect()
+    }
+
+    #[test]
+    fn classic_expands_to_the_pomodoro_cycle() {
+        let program = Program::classic(PomodoroConfig::default());
+        assert_eq!(
+            kinds(&program),
+            named(&[
+                ("Work", 25),
+                ("Short Break", 5),
+                ("Work", 25),
+                ("Short Break", 5),
+                ("Work", 25),
+                ("Short Break", 5),
+                ("Work", 25),
+                ("Long Break", 15),
+            ])
+        );
+        assert!(program.validate().is_ok());
+    }
+
+    #[test]
+    fn classic_with_one_session_has_no_short_breaks() {
+        let program = Program::classic(PomodoroConfig {
+            sessions_before_long_break: 1,
+            ..PomodoroConfig::default()
+        });
+        assert_eq!(kinds(&program), named(&[("Work", 25), ("Long Break", 15)]));
+    }
+
+    #[test]
+    fn parses_nested_repeats() {
+        // 25-25-50 focus blocks with a long rest after each round.
+        let program: Program = serde_json::from_str(
+            r#"{
+                "name": "25-25-50",
+                "steps": [
+                    {"repeat": {"times": 2, "steps": [
+                        {"segment": {"name": "Focus", "secs": 1500, "kind": "focus"}},
+                        {"segment": {"name": "Rest", "secs": 300, "kind": "rest"}}
+                    ]}},
+                    {"segment": {"name": "Deep", "secs": 3000, "kind": "focus", "icon": "🧠"}},
+                    {"segment": {"name": "Walk", "secs": 900, "kind": "long_rest", "notify": false}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(program.validate().is_ok());
+        let segments = program.segments();
+        assert_eq!(segments.len(), 6);
+        assert_eq!(segments[4].icon.as_deref(), Some("🧠"));
+        assert!(!segments[5].notify);
+        assert_eq!(segments[5].phase(), Phase::LongBreak);
+    }
+
+    #[test]
+    fn overlay_defaults_to_rests() {
+        let rest = Segment::new("Rest", 60, SegmentKind::Rest);
+        let other = Segment::new("Stretch", 60, SegmentKind::Other);
+        assert!(rest.shows_overlay());
+        assert!(!other.shows_overlay());
+        let quiet_rest = Segment {
+            overlay: Some(false),
+            ..rest
+        };
+        assert!(!quiet_rest.shows_overlay());
+        assert_eq!(other.phase(), Phase::Other);
+    }
+
+    #[test]
+    fn validation() {
+        let empty = Program {
+            name: "empty".to_string(),
+            steps: vec![],
+        };
+        assert!(empty.validate().unwrap_err().starts_with("steps:"));
+        let zero = Program {
+            name: "zero".to_string(),
+            steps: vec![Step::Repeat {
+                times: 0,
+                steps: vec![Step::Segment(Segment::new("Work", 60, SegmentKind::Focus))],
+            }],
+        };
+        assert_eq!(
+            zero.validate().unwrap_err(),
+            "steps[0].times: must be at least 1"
+        );
+        let huge = Program {
+            name: "huge".to_string(),
+            steps: vec![Step::Repeat {
+                times: 1000,
+                steps: vec![Step::Segment(Segment::new("Work", 60, SegmentKind::Focus))],
+            }],
+        };
+        assert!(huge.validate().unwrap_err().contains("at most"));
+        let bad_secs = Program {
+            name: "bad".to_string(),
+            steps: vec![Step::Segment(Segment::new("Work", 0, SegmentKind::Focus))],
+        };
+        assert!(bad_secs
+            .validate()
+            .unwrap_err()
+            .starts_with("steps[0].secs:"));
+    }
+
+    #[test]
+    fn program_names_must_be_unique() {
+        let program = |name: &str| Program {
+            name: name.to_string(),
+            steps: vec![Step::Segment(Segment::new("Work", 60, SegmentKind::Focus))],
+        };
+        assert!(validate_programs(&[program("a"), program("b")]).is_ok());
+        assert!(validate_programs(&[program("a"), program("a")]).is_err());
+        assert!(validate_programs(&[program(CLASSIC)]).is_err());
+    }
+}
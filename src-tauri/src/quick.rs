@@ -0,0 +1,712 @@
+use chrono::NaiveTime;
+use serde::Serialize;
+
+use crate::duration::{self, DurationStyle};
+use crate::interval::IntervalConfig;
+use crate::pomodoro::PomodoroConfig;
+use crate::presets::{self, Preset};
+use crate::program::{Program, CLASSIC, MAX_SEGMENTS, MAX_SEGMENT_SECS};
+use crate::settings::Settings;
+
+/// Most suggestions offered for one input.
+pub const MAX_SUGGESTIONS: usize = 8;
+
+/// Keywords, whether they take arguments, and what they do.
+const COMMANDS: &[(&str, bool, &str)] = &[
+    ("start", false, "Start or resume the timer"),
+    ("pause", false, "Pause the timer"),
+    ("toggle", false, "Start or pause the timer"),
+    ("reset", false, "Reset the timer"),
+    ("skip", false, "Skip to the next phase"),
+    ("break", false, "Take the break earned in Flowtime"),
+    ("dismiss", false, "Acknowledge a finished timer"),
+    ("timer", true, "Count down, e.g. timer 10m"),
+    ("pomodoro", true, "Pomodoro, e.g. pomodoro 50/10 x3"),
+    ("program", true, "Run a pomodoro program by name"),
+    ("interval", true, "Interval workout, e.g. interval 20/10 x8"),
+    ("flowtime", false, "Count work up and earn breaks"),
+    ("stopwatch", false, "Count up, in Flowtime mode"),
+    ("alarm", true, "Ring at a time, e.g. alarm 15:30 standup"),
+];
+
+/// What a quick command such as "tea 4m" asks for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QuickAction {
+    Start,
+    Pause,
+    Toggle,
+    Reset,
+    Skip,
+    /// End Flowtime work and take the earned break.
+    TakeBreak,
+    /// Acknowledge a finished timer.
+    Dismiss,
+    /// A countdown, named after `label` when one is given.
+    Timer {
+        secs: u32,
+        label: Option<String>,
+    },
+    Pomodoro {
+        config: PomodoroConfig,
+    },
+    Program {
+        name: String,
+    },
+    Interval {
+        config: IntervalConfig,
+    },
+    /// Flowtime mode, which also serves as the stopwatch.
+    Flowtime,
+    /// Ring at `time` ("HH:MM"), named after `label` when one is given.
+    Alarm {
+        time: String,
+        label: Option<String>,
+    },
+    Preset {
+        id: String,
+    },
+}
+
+impl QuickAction {
+    /// Whether running this ends or leaves a break in progress; `running` is
+    /// whether the timer runs now, which decides what toggling does.
+    pub fn leaves_break(&self, running: bool) -> bool {
+        match self {
+            QuickAction::Start | QuickAction::TakeBreak | QuickAction::Dismiss => false,
+            QuickAction::Toggle => running,
+            _ => true,
+        }
+    }
+}
+
+/// Why a quick command could not be read or run. `start..end` are the part
+/// of the input it is about, in UTF-16 code units as the entry box's
+/// selection counts them, so it can mark that part.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuickError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl QuickError {
+    /// An error about the whole input.
+    pub fn new(message: impl Into<String>, input: &str) -> Self {
+        Self {
+            message: message.into(),
+            start: 0,
+            end: utf16_len(input),
+        }
+    }
+
+    fn at(message: impl Into<String>, input: &str, words: &[Word]) -> Self {
+        let (start, end) = span(input, words);
+        Self {
+            message: message.into(),
+            start,
+            end,
+        }
+    }
+}
+
+/// A completion for the entry box.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Suggestion {
+    /// The whole input once the suggestion is taken.
+    pub text: String,
+    pub hint: String,
+}
+
+/// A word of the input and its byte offset.
+#[derive(Debug, Clone, Copy)]
+struct Word<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+fn words(input: &str) -> Vec<Word<'_>> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (i, c) in input.char_indices().chain([(input.len(), ' ')]) {
+        match (c.is_whitespace(), start) {
+            (true, Some(at)) => {
+                out.push(Word {
+                    text: &input[at..i],
+                    at,
+                });
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// The input from the first of `words` to the end of the last.
+fn text<'a>(input: &'a str, words: &[Word]) -> &'a str {
+    match (words.first(), words.last()) {
+        (Some(first), Some(last)) => &input[first.at..last.at + last.text.len()],
+        _ => "",
+    }
+}
+
+/// UTF-16 offsets of `words` in the input.
+fn span(input: &str, words: &[Word]) -> (usize, usize) {
+    match (words.first(), words.last()) {
+        (Some(first), Some(last)) => (
+            utf16_len(&input[..first.at]),
+            utf16_len(&input[..last.at + last.text.len()]),
+        ),
+        _ => (0, utf16_len(input)),
+    }
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Read a quick command:
+///
+/// - a preset's name or id: `tabata 20/10`
+/// - a duration, optionally after a label or "timer": `25m`, `tea 4m`,
+///   `boil eggs for 7 minutes`
+/// - `pomodoro [work/break[/long break]] [xN]`, in minutes: `pomodoro 50/10 x3`
+/// - `interval [work/rest] [xN]`, in seconds: `interval 40/20 x10`
+/// - `program <name>`, `alarm <HH:MM> [label]`
+/// - `flowtime` or `stopwatch`, and `start`, `pause`, `toggle`, `reset`,
+///   `skip`, `break`, `dismiss`
+pub fn parse(input: &str, settings: &Settings) -> Result<QuickAction, QuickError> {
+    let words = words(input);
+    let Some(first) = words.first() else {
+        return Err(QuickError::new(
+            "type a command, e.g. \"tea 4m\" or \"pomodoro 50/10 x3\"",
+            input,
+        ));
+    };
+    let args = &words[1..];
+    let keyword = first.text.to_lowercase();
+    let preset = settings.presets.find(input);
+    match control(&keyword) {
+        // A control command means itself even when a preset has the same name
+        Some(action) if args.is_empty() => return Ok(action),
+        Some(_) if preset.is_none() => {
+            return Err(QuickError::at(
+                format!("unexpected {:?} after {:?}", args[0].text, first.text),
+                input,
+                args,
+            ))
+        }
+        _ => {}
+    }
+    if let Some(preset) = preset {
+        return Ok(QuickAction::Preset {
+            id: preset.id.clone(),
+        });
+    }
+    match keyword.as_str() {
+        "timer" if args.is_empty() => Err(QuickError::at(
+            "expected a duration after \"timer\", e.g. timer 10m",
+            input,
+            &words,
+        )),
+        "timer" => timer(input, args),
+        "pomodoro" | "pomo" => pomodoro(input, args),
+        "interval" | "hiit" => interval(input, args, settings),
+        "program" => program(input, &words, settings),
+        "alarm" => alarm(input, &words),
+        _ => timer(input, &words),
+    }
+}
+
+/// `words` as a duration, or a label followed by one.
+fn timer(input: &str, words: &[Word]) -> Result<QuickAction, QuickError> {
+    let found = (0..words.len()).find_map(|i| {
+        duration::parse(text(input, &words[i..]))
+            .ok()
+            .map(|secs| (i, secs))
+    });
+    let Some((i, secs)) = found else {
+        // Report the duration the input seems to end with, if any.
+        let Some(i) = words
+            .iter()
+            .position(|w| w.text.starts_with(|c: char| c.is_ascii_digit()))
+        else {
+            return Err(QuickError::at(
+                format!("unknown command {:?}", words[0].text),
+                input,
+                &words[..1],
+            ));
+        };
+        let err = duration::parse(text(input, &words[i..])).unwrap_err();
+        return Err(QuickError::at(err, input, &words[i..]));
+    };
+    if secs > MAX_SEGMENT_SECS {
+        return Err(QuickError::at(too_long(), input, &words[i..]));
+    }
+    let label = match &words[..i] {
+        [] => None,
+        label => Some(self::label(input, label)?),
+    };
+    Ok(QuickAction::Timer { secs, label })
+}
+
+fn label(input: &str, words: &[Word]) -> Result<String, QuickError> {
+    let label = text(input, words);
+    if label.chars().count() > presets::MAX_NAME_CHARS {
+        return Err(QuickError::at(
+            format!(
+                "labels must be at most {} characters",
+                presets::MAX_NAME_CHARS
+            ),
+            input,
+            words,
+        ));
+    }
+    Ok(label.to_string())
+}
+
+fn too_long() -> String {
+    format!(
+        "must be at most {}",
+        duration::format(MAX_SEGMENT_SECS, DurationStyle::Verbose)
+    )
+}
+
+/// "x3" or "3x".
+fn repeat_count(text: &str) -> Option<&str> {
+    let lower = text.strip_prefix(['x', 'X', '×']);
+    let upper = text.strip_suffix(['x', 'X', '×']);
+    lower
+        .or(upper)
+        .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Lengths such as "50/10" and a repeat count such as "x3", each optional.
+/// Bare numbers in the lengths count `bare_secs` each.
+fn lengths_and_count(
+    input: &str,
+    args: &[Word],
+    max_lengths: usize,
+    bare_secs: u32,
+) -> Result<(Vec<u32>, Option<u32>), QuickError> {
+    let mut lengths = None;
+    let mut count = None;
+    for word in args {
+        let one = std::slice::from_ref(word);
+        if let Some(n) = repeat_count(word.text) {
+            if count.is_some() {
+                return Err(QuickError::at("repeat count given twice", input, one));
+            }
+            let n = n
+                .parse()
+                .map_err(|_| QuickError::at("repeat count is too large", input, one))?;
+            count = Some(n);
+            continue;
+        }
+        if lengths.is_some() {
+            return Err(QuickError::at(
+                format!("unexpected {:?}", word.text),
+                input,
+                one,
+            ));
+        }
+        let parts: Vec<&str> = word.text.split('/').collect();
+        if parts.len() > max_lengths {
+            return Err(QuickError::at(
+                format!("expected at most {max_lengths} lengths separated by /"),
+                input,
+                one,
+            ));
+        }
+        let secs = parts
+            .iter()
+            .map(|part| match part.parse::<u32>() {
+                Ok(n) => n
+                    .checked_mul(bare_secs)
+                    .ok_or_else(|| format!("{part:?} {}", too_long())),
+                Err(_) => duration::parse(part),
+            })
+            .collect::<Result<Vec<u32>, String>>()
+            .map_err(|e| QuickError::at(e, input, one))?;
+        if secs.iter().any(|&s| s > MAX_SEGMENT_SECS) {
+            return Err(QuickError::at(
+                format!("lengths {}", too_long()),
+                input,
+                one,
+            ));
+        }
+        lengths = Some(secs);
+    }
+    Ok((lengths.unwrap_or_default(), count))
+}
+
+fn pomodoro(input: &str, args: &[Word]) -> Result<QuickAction, QuickError> {
+    let (lengths, sessions) = lengths_and_count(input, args, 3, 60)?;
+    let mut config = PomodoroConfig::default();
+    for (length, secs) in [
+        &mut config.work_secs,
+        &mut config.short_break_secs,
+        &mut config.long_break_secs,
+    ]
+    .into_iter()
+    .zip(lengths)
+    {
+        if secs == 0 {
+            return Err(QuickError::at(
+                "pomodoro lengths must be at least 1 second",
+                input,
+                args,
+            ));
+        }
+        *length = secs;
+    }
+    if let Some(sessions) = sessions {
+        // Each session is a work and a break segment of the program.
+        let max = (MAX_SEGMENTS / 2) as u32;
+        if !(1..=max).contains(&sessions) {
+            return Err(QuickError::at(
+                format!("sessions must be between 1 and {max}"),
+                input,
+                args,
+            ));
+        }
+        config.sessions_before_long_break = sessions;
+    }
+    Ok(QuickAction::Pomodoro { config })
+}
+
+fn interval(input: &str, args: &[Word], settings: &Settings) -> Result<QuickAction, QuickError> {
+    let (lengths, rounds) = lengths_and_count(input, args, 2, 1)?;
+    let mut config = settings.interval.workout;
+    if let Some(&work) = lengths.first() {
+        config.work_secs = work;
+    }
+    if let Some(&rest) = lengths.get(1) {
+        config.rest_secs = rest;
+    }
+    if let Some(rounds) = rounds {
+        config.rounds = rounds;
+    }
+    config
+        .validate()
+        .map_err(|e| QuickError::at(e, input, args))?;
+    Ok(QuickAction::Interval { config })
+}
+
+/// `words` starts with "program".
+fn program(input: &str, words: &[Word], settings: &Settings) -> Result<QuickAction, QuickError> {
+    let wanted = text(input, &words[1..]);
+    if wanted.is_empty() {
+        return Err(QuickError::at(
+            "expected a program name after \"program\"",
+            input,
+            words,
+        ));
+    }
+    program_names(settings)
+        .find(|name| name.to_lowercase() == wanted.to_lowercase())
+        .map(|name| QuickAction::Program {
+            name: name.to_string(),
+        })
+        .ok_or_else(|| QuickError::at(format!("unknown program {wanted:?}"), input, &words[1..]))
+}
+
+fn program_names(settings: &Settings) -> impl Iterator<Item = &str> {
+    std::iter::once(CLASSIC).chain(settings.programs.iter().map(|p: &Program| p.name.as_str()))
+}
+
+/// The action for a keyword that takes no arguments, such as "pause".
+fn control(keyword: &str) -> Option<QuickAction> {
+    Some(match keyword {
+        "start" | "resume" => QuickAction::Start,
+        "pause" | "stop" => QuickAction::Pause,
+        "toggle" => QuickAction::Toggle,
+        "reset" => QuickAction::Reset,
+        "skip" => QuickAction::Skip,
+        "break" => QuickAction::TakeBreak,
+        "dismiss" | "ok" => QuickAction::Dismiss,
+        "flowtime" | "flow" | "stopwatch" => QuickAction::Flowtime,
+        _ => return None,
+    })
+}
+
+/// `words` starts with "alarm".
+fn alarm(input: &str, words: &[Word]) -> Result<QuickAction, QuickError> {
+    let Some(time) = words.get(1) else {
+        return Err(QuickError::at(
+            "expected a time after \"alarm\", e.g. alarm 15:30",
+            input,
+            words,
+        ));
+    };
+    if NaiveTime::parse_from_str(time.text, "%H:%M").is_err() {
+        return Err(QuickError::at(
+            format!("invalid time {:?}, expected HH:MM", time.text),
+            input,
+            &words[1..2],
+        ));
+    }
+    let label = match &words[2..] {
+        [] => None,
+        rest => Some(label(input, rest)?),
+    };
+    Ok(QuickAction::Alarm {
+        time: time.text.to_string(),
+        label,
+    })
+}
+
+/// Completions for `input`: commands, program names and presets that start
+/// with what has been typed, ignoring case.
+pub fn suggest(input: &str, settings: &Settings) -> Vec<Suggestion> {
+    let typed = input.trim_start().to_lowercase();
+    let commands = COMMANDS.iter().map(|(keyword, args, hint)| Suggestion {
+        text: if *args {
+            format!("{keyword} ")
+        } else {
+            keyword.to_string()
+        },
+        hint: hint.to_string(),
+    });
+    let programs = program_names(settings).map(|name| Suggestion {
+        text: format!("program {name}"),
+        hint: "Program".to_string(),
+    });
+    let presets = settings.presets.list().iter().map(|p: &Preset| Suggestion {
+        text: p.name.clone(),
+        hint: "Preset".to_string(),
+    });
+    let mut out: Vec<Suggestion> = Vec::new();
+    for suggestion in commands.chain(programs).chain(presets) {
+        let candidate = suggestion.text.to_lowercase();
+        if candidate.starts_with(&typed)
+            && candidate.trim_end() != typed.trim_end()
+            && !out.iter().any(|s| s.text == suggestion.text)
+        {
+            out.push(suggestion);
+            if out.len() == MAX_SUGGESTIONS {
+                break;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presets::PresetKind;
+    use crate::program::{Segment, SegmentKind, Step};
+
+    fn settings() -> Settings {
+        let mut settings = Settings::default();
+        settings.programs.push(Program {
+            name: "Deep Work".to_string(),
+            steps: vec![Step::Segment(Segment::new(
+                "Focus",
+                90 * 60,
+                SegmentKind::Focus,
+            ))],
+        });
+        settings
+    }
+
+    fn ok(input: &str) -> QuickAction {
+        parse(input, &settings()).unwrap()
+    }
+
+    fn err(input: &str) -> QuickError {
+        parse(input, &settings()).unwrap_err()
+    }
+
+    fn timer(secs: u32, label: Option<&str>) -> QuickAction {
+        QuickAction::Timer {
+            secs,
+            label: label.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parses_controls() {
+        assert_eq!(ok("pause"), QuickAction::Pause);
+        assert_eq!(ok("  Start "), QuickAction::Start);
+        assert_eq!(ok("resume"), QuickAction::Start);
+        assert_eq!(ok("reset"), QuickAction::Reset);
+        assert_eq!(ok("skip"), QuickAction::Skip);
+        assert_eq!(ok("break"), QuickAction::TakeBreak);
+        assert_eq!(ok("dismiss"), QuickAction::Dismiss);
+        assert_eq!(ok("stopwatch"), QuickAction::Flowtime);
+        assert_eq!(ok("flowtime"), QuickAction::Flowtime);
+    }
+
+    #[test]
+    fn parses_timers_with_and_without_labels() {
+        assert_eq!(ok("25m"), timer(1500, None));
+        assert_eq!(ok("timer 10m"), timer(600, None));
+        assert_eq!(ok("in 20 minutes"), timer(1200, None));
+        assert_eq!(ok("tea 4m"), timer(240, Some("tea")));
+        assert_eq!(ok("boil eggs for 7 minutes"), timer(420, Some("boil eggs")));
+        assert_eq!(ok("Laundry 1:30:00"), timer(5400, Some("Laundry")));
+    }
+
+    #[test]
+    fn parses_pomodoro() {
+        let config = |work: u32, short: u32, long: u32, sessions| QuickAction::Pomodoro {
+            config: PomodoroConfig {
+                work_secs: work * 60,
+                short_break_secs: short * 60,
+                long_break_secs: long * 60,
+                sessions_before_long_break: sessions,
+            },
+        };
+        assert_eq!(ok("pomodoro"), config(25, 5, 15, 4));
+        assert_eq!(ok("pomodoro 50/10 x3"), config(50, 10, 15, 3));
+        assert_eq!(ok("pomo 3x 45/15/30"), config(45, 15, 30, 3));
+        assert_eq!(ok("pomodoro 1h/10m"), config(60, 10, 15, 4));
+    }
+
+    #[test]
+    fn parses_interval_lengths_in_seconds() {
+        let QuickAction::Interval { config } = ok("interval 40/20 x10") else {
+            panic!("expected an interval");
+        };
+        assert_eq!(
+            (config.work_secs, config.rest_secs, config.rounds),
+            (40, 20, 10)
+        );
+        assert_eq!(config.warmup_secs, IntervalConfig::default().warmup_secs);
+        let QuickAction::Interval { config } = ok("hiit 1m") else {
+            panic!("expected an interval");
+        };
+        assert_eq!((config.work_secs, config.rest_secs), (60, 10));
+    }
+
+    #[test]
+    fn parses_programs_alarms_and_presets() {
+        assert_eq!(
+            ok("program deep work"),
+            QuickAction::Program {
+                name: "Deep Work".to_string()
+            }
+        );
+        assert_eq!(
+            ok("alarm 15:30 standup"),
+            QuickAction::Alarm {
+                time: "15:30".to_string(),
+                label: Some("standup".to_string())
+            }
+        );
+        assert_eq!(
+            ok("alarm 07:00"),
+            QuickAction::Alarm {
+                time: "07:00".to_string(),
+                label: None
+            }
+        );
+        assert_eq!(
+            ok("Tabata 20/10"),
+            QuickAction::Preset {
+                id: "tabata-20-10".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn controls_win_over_presets_of_the_same_name() {
+        let mut settings = settings();
+        let basic = PresetKind::Basic { secs: 60 };
+        settings
+            .presets
+            .create(Preset::unsaved("Pause", basic.clone()));
+        let room = settings
+            .presets
+            .create(Preset::unsaved("Break room", basic));
+        assert_eq!(parse("pause", &settings), Ok(QuickAction::Pause));
+        assert_eq!(
+            parse("break room", &settings),
+            Ok(QuickAction::Preset { id: room.id })
+        );
+        assert_eq!(
+            parse("pause now", &settings).unwrap_err().message,
+            "unexpected \"now\" after \"pause\""
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let at = |input: &str| {
+            let e = err(input);
+            (e.message, e.start, e.end)
+        };
+        let span = |input: &str| {
+            let e = err(input);
+            (e.start, e.end)
+        };
+        assert_eq!(at("explode").0, "unknown command \"explode\"");
+        assert_eq!(span("explode now"), (0, 7));
+        assert_eq!(
+            at("pause now"),
+            ("unexpected \"now\" after \"pause\"".to_string(), 6, 9)
+        );
+        let (message, start, end) = at("tea 4 parsecs");
+        assert!(message.contains("unknown unit \"parsecs\""));
+        assert_eq!((start, end), (4, 13));
+        assert_eq!(span("pomodoro 50/abc x3"), (9, 15));
+        assert!(at("pomodoro x0").0.contains("between 1 and 250"));
+        assert!(at("pomodoro x3 x4").0.contains("given twice"));
+        assert!(at("pomodoro 1/2/3/4").0.contains("at most 3 lengths"));
+        assert!(at("interval 20/10 x0").0.starts_with("rounds:"));
+        assert!(at("timer 25h").0.contains("at most 24 hours"));
+        assert!(at("timer").0.starts_with("expected a duration"));
+        assert_eq!(
+            at("program nope"),
+            ("unknown program \"nope\"".to_string(), 8, 12)
+        );
+        assert_eq!(span("alarm 25:00 x"), (6, 11));
+        assert!(at("").0.starts_with("type a command"));
+        // Offsets count UTF-16 code units, as the entry box does, not bytes.
+        assert_eq!(span("té 4 parsecs"), (3, 12));
+        assert_eq!(span("🍵 tea 4x"), (7, 9));
+        assert_eq!(span("🍵"), (0, 2));
+    }
+
+    #[test]
+    fn suggests_what_starts_with_the_input() {
+        let texts = |input: &str| -> Vec<String> {
+            suggest(input, &settings())
+                .into_iter()
+                .map(|s| s.text)
+                .collect()
+        };
+        assert_eq!(texts("pa"), ["pause"]);
+        assert_eq!(
+            texts("PRO"),
+            ["program ", "program classic", "program Deep Work"]
+        );
+        assert_eq!(texts("program d"), ["program Deep Work"]);
+        assert_eq!(
+            texts("po"),
+            ["pomodoro ", "Pomodoro 25/5", "Pomodoro 50/10"]
+        );
+        assert_eq!(texts("tab"), ["Tabata 20/10"]);
+        assert!(texts("pause").is_empty());
+        assert!(texts("zzz").is_empty());
+        assert_eq!(texts("").len(), MAX_SUGGESTIONS);
+    }
+
+    #[test]
+    fn serializes_for_the_frontend() {
+        let json = serde_json::to_value(ok("tea 4m")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"kind": "timer", "secs": 240, "label": "tea"})
+        );
+        let json = serde_json::to_value(err("explode")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"message": "unknown command \"explode\"", "start": 0, "end": 7})
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_WINDOWS: usize = 16;
+
+/// A recurring quiet period, e.g. weekday evenings from "18:00" to "09:00".
+/// An `end` at or before `start` runs past midnight into the next day, so
+/// "00:00" to "00:00" is a whole day; the period belongs to the day it
+/// starts on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietWindow {
+    /// Days the window starts on. Empty means every day.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// Wall-clock "HH:MM".
+    pub start: String,
+    pub end: String,
+}
+
+impl QuietWindow {
+    fn validate(&self) -> Result<(), String> {
+        parse_time(&self.start).map_err(|e| format!("start: {e}"))?;
+        parse_time(&self.end).map_err(|e| format!("end: {e}"))?;
+        Ok(())
+    }
+
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    /// Whether the wall-clock time `at` falls in this window.
+    fn contains(&self, at: NaiveDateTime) -> bool {
+        let (Ok(start), Ok(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        let time = at.time();
+        let today = at.weekday();
+        if start < end {
+            self.starts_on(today) && start <= time && time < end
+        } else {
+            let yesterday = (at - Duration::days(1)).weekday();
+            (self.starts_on(today) && time >= start) || (self.starts_on(yesterday) && time < end)
+        }
+    }
+}
+
+fn parse_time(text: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(text, "%H:%M")
+        .map_err(|_| format!("invalid time {text:?}, expected HH:MM"))
+}
+
+/// Scheduled quiet hours. Windows are evaluated on the wall clock of
+/// `time_zone` (an IANA name such as "Europe/Berlin"), or of the system time
+/// zone when unset, so they follow daylight saving changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuietHoursSettings {
+    pub enabled: bool,
+    pub time_zone: Option<String>,
+    pub windows: Vec<QuietWindow>,
+}
+
+impl QuietHoursSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(name) = &self.time_zone {
+            name.parse::<Tz>()
+                .map_err(|_| format!("time_zone: unknown time zone {name:?}"))?;
+        }
+        if self.windows.len() > MAX_WINDOWS {
+            return Err(format!("windows: at most {MAX_WINDOWS} allowed"));
+        }
+        for (i, window) in self.windows.iter().enumerate() {
+            window.validate().map_err(|e| format!("windows[{i}].{e}"))?;
+        }
+        Ok(())
+    }
+
+    /// Whether the instant `now` falls in a quiet window.
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let local = match self.time_zone.as_deref().map(str::parse::<Tz>) {
+            Some(Ok(tz)) => now.with_timezone(&tz).naive_local(),
+            Some(Err(_)) => return false,
+            None => now.with_timezone(&chrono::Local).naive_local(),
+        };
+        self.windows.iter().any(|w| w.contains(local))
+    }
+}
+
+/// Alerts held back while quiet, reported in one summary when it ends.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SuppressedAlerts {
+    pub phase_changes: u32,
+    pub reminders: u32,
+    pub warnings: u32,
+    /// The last phase the timer moved to, e.g. "ShortBreak" or "finished".
+    pub last_phase: Option<String>,
+}
+
+impl SuppressedAlerts {
+    pub fn is_empty(&self) -> bool {
+        self.phase_changes == 0 && self.reminders == 0 && self.warnings == 0
+    }
+}
+
+/// Tracks whether alerts are being suppressed and what was held back.
+#[derive(Debug, Default)]
+pub struct QuietTracker {
+    quiet: bool,
+    suppressed: SuppressedAlerts,
+}
+
+impl QuietTracker {
+    /// Record whether this tick is quiet. Returns what was suppressed when a
+    /// quiet period with held-back alerts has just ended.
+    pub fn update(&mut self, quiet: bool) -> Option<SuppressedAlerts> {
+        let ended = self.quiet && !quiet;
+        self.quiet = quiet;
+        if !ended {
+            return None;
+        }
+        let summary = std::mem::take(&mut self.suppressed);
+        (!summary.is_empty()).then_some(summary)
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        self.quiet
+    }
+
+    pub fn suppress_phase_change(&mut self, to: &str) {
+        self.suppressed.phase_changes += 1;
+        self.suppressed.last_phase = Some(to.to_string());
+    }
+
+    pub fn suppress_reminder(&mut self) {
+        self.suppressed.reminders += 1;
+    }
+
+    pub fn suppress_warnings(&mut self, count: u32) {
+        self.suppressed.warnings += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn window(days: &[Weekday], start: &str, end: &str) -> QuietWindow {
+        QuietWindow {
+            days: days.to_vec(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    fn settings(tz: &str, windows: Vec<QuietWindow>) -> QuietHoursSettings {
+        QuietHoursSettings {
+            enabled: true,
+            time_zone: Some(tz.to_string()),
+            windows,
+        }
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn daytime_window_on_chosen_days() {
+        // 2024-03-04 is a Monday.
+        let s = settings("UTC", vec![window(&[Weekday::Mon], "12:00", "13:00")]);
+        assert!(!s.is_quiet(utc(2024, 3, 4, 11, 59)));
+        assert!(s.is_quiet(utc(2024, 3, 4, 12, 0)));
+        assert!(!s.is_quiet(utc(2024, 3, 4, 13, 0)));
+        assert!(!s.is_quiet(utc(2024, 3, 5, 12, 30)));
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_its_start_day() {
+        let s = settings("UTC", vec![window(&[Weekday::Fri], "22:00", "07:00")]);
+        // Friday 2024-03-08 evening through Saturday morning.
+        assert!(s.is_quiet(utc(2024, 3, 8, 23, 0)));
+        assert!(s.is_quiet(utc(2024, 3, 9, 6, 59)));
+        assert!(!s.is_quiet(utc(2024, 3, 9, 7, 0)));
+        // Thursday night is not covered.
+        assert!(!s.is_quiet(utc(2024, 3, 8, 2, 0)));
+        assert!(!s.is_quiet(utc(2024, 3, 9, 23, 0)));
+    }
+
+    #[test]
+    fn weekends_all_day() {
+        let s = settings(
+            "UTC",
+            vec![window(&[Weekday::Sat, Weekday::Sun], "00:00", "00:00")],
+        );
+        assert!(!s.is_quiet(utc(2024, 3, 8, 23, 59)));
+        assert!(s.is_quiet(utc(2024, 3, 9, 0, 0)));
+        assert!(s.is_quiet(utc(2024, 3, 10, 23, 59)));
+        assert!(!s.is_quiet(utc(2024, 3, 11, 0, 0)));
+    }
+
+    #[test]
+    fn follows_the_time_zone() {
+        let s = settings("Asia/Tokyo", vec![window(&[], "18:00", "09:00")]);
+        // 09:30 UTC is 18:30 in Tokyo.
+        assert!(s.is_quiet(utc(2024, 3, 4, 9, 30)));
+        assert!(!s.is_quiet(utc(2024, 3, 4, 3, 0)));
+    }
+
+    #[test]
+    fn spring_forward_keeps_wall_clock_end() {
+        // New York skips 02:00-03:00 on 2024-03-10; the window still ends at
+        // 07:00 local, which is now 11:00 UTC instead of 12:00.
+        let s = settings("America/New_York", vec![window(&[], "22:00", "07:00")]);
+        assert!(s.is_quiet(utc(2024, 3, 10, 10, 59)));
+        assert!(!s.is_quiet(utc(2024, 3, 10, 11, 0)));
+        // The day before, 07:00 EST was 12:00 UTC.
+        assert!(s.is_quiet(utc(2024, 3, 9, 11, 30)));
+        assert!(!s.is_quiet(utc(2024, 3, 9, 12, 0)));
+    }
+
+    #[test]
+    fn window_starting_in_the_skipped_hour() {
+        // 02:30 never happens on 2024-03-10; the window is quiet from 03:00 EDT.
+        let s = settings("America/New_York", vec![window(&[], "02:30", "04:00")]);
+        // 01:59 EST = 06:59 UTC, 03:00 EDT = 07:00 UTC.
+        assert!(!s.is_quiet(utc(2024, 3, 10, 6, 59)));
+        assert!(s.is_quiet(utc(2024, 3, 10, 7, 0)));
+        assert!(!s.is_quiet(utc(2024, 3, 10, 8, 0)));
+    }
+
+    #[test]
+    fn fall_back_repeats_the_hour() {
+        // 01:00-02:00 happens twice on 2024-11-03 in New York; both are quiet.
+        let s = settings("America/New_York", vec![window(&[], "01:00", "02:00")]);
+        // 01:30 EDT = 05:30 UTC, 01:30 EST = 06:30 UTC.
+        assert!(s.is_quiet(utc(2024, 11, 3, 5, 30)));
+        assert!(s.is_quiet(utc(2024, 11, 3, 6, 30)));
+        // 02:00 EST = 07:00 UTC.
+        assert!(!s.is_quiet(utc(2024, 11, 3, 7, 0)));
+    }
+
+    #[test]
+    fn disabled_is_never_quiet() {
+        let mut s = settings("UTC", vec![window(&[], "00:00", "00:00")]);
+        assert!(s.is_quiet(utc(2024, 3, 4, 12, 0)));
+        s.enabled = false;
+        assert!(!s.is_quiet(utc(2024, 3, 4, 12, 0)));
+    }
+
+    #[test]
+    fn tracker_summarizes_when_quiet_ends() {
+        let mut tracker = QuietTracker::default();
+        assert_eq!(tracker.update(true), None);
+        tracker.suppress_phase_change("ShortBreak");
+        tracker.suppress_phase_change("Work");
+        tracker.suppress_reminder();
+        tracker.suppress_warnings(2);
+        assert_eq!(tracker.update(true), None);
+        let summary = tracker.update(false).unwrap();
+        assert_eq!(summary.phase_changes, 2);
+        assert_eq!(summary.reminders, 1);
+        assert_eq!(summary.warnings, 2);
+        assert_eq!(summary.last_phase.as_deref(), Some("Work"));
+        // Nothing suppressed: no summary.
+        tracker.update(true);
+        assert_eq!(tracker.update(false), None);
+    }
+
+    #[test]
+    fn validation() {
+        assert!(QuietHoursSettings::default().validate().is_ok());
+        let bad_zone = settings("Mars/Olympus", vec![]);
+        assert!(bad_zone.validate().unwrap_err().starts_with("time_zone:"));
+        let bad_time = settings("UTC", vec![window(&[], "25:00", "07:00")]);
+        assert!(bad_time
+            .validate()
+            .unwrap_err()
+            .starts_with("windows[0].start:"));
+        let parsed: QuietHoursSettings = serde_json::from_str(
+            r#"{"enabled":true,"windows":[{"days":["Sat","Sun"],"start":"00:00","end":"00:00"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.windows[0].days, vec![Weekday::Sat, Weekday::Sun]);
+        assert_eq!(parsed.time_zone, None);
+    }
+}
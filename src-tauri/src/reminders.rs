@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::EventSound;
+
+pub const MIN_INTERVAL_SECS: u32 = 10;
+pub const MAX_INTERVAL_SECS: u32 = 60 * 60;
+
+/// Re-alerts for a finished basic timer until it is acknowledged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReminderSettings {
+    pub enabled: bool,
+    pub interval_secs: u32,
+    /// Stop after this many reminders; 0 keeps reminding until dismissed.
+    pub max_reminders: u32,
+    /// Added to the finish sound's volume on every reminder.
+    pub volume_step: f32,
+}
+
+impl Default for ReminderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 2 * 60,
+            max_reminders: 5,
+            volume_step: 0.1,
+        }
+    }
+}
+
+impl ReminderSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&self.interval_secs) {
+            return Err(format!(
+                "interval_secs: must be between {MIN_INTERVAL_SECS} and {MAX_INTERVAL_SECS}"
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.volume_step) {
+            return Err("volume_step: must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// The reminder number (starting at 1) due exactly `finished_secs` after
+    /// the finish, if any. Called once per tick, so each reminder fires once.
+    pub fn due(&self, finished_secs: u32) -> Option<u32> {
+        if !self.enabled || finished_secs == 0 || !finished_secs.is_multiple_of(self.interval_secs)
+        {
+            return None;
+        }
+        let n = finished_secs / self.interval_secs;
+        (self.max_reminders == 0 || n <= self.max_reminders).then_some(n)
+    }
+
+    /// The finish sound made louder for reminder `n`.
+    pub fn escalate(&self, sound: &EventSound, n: u32) -> EventSound {
+        EventSound {
+            volume: (sound.volume + self.volume_step * n as f32).min(1.0),
+            ..sound.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn due_on_each_interval_up_to_the_limit() {
+        let settings = ReminderSettings {
+            interval_secs: 120,
+            max_reminders: 2,
+            ..ReminderSettings::default()
+        };
+        let due: Vec<(u32, u32)> = (0..=600)
+            .filter_map(|s| settings.due(s).map(|n| (s, n)))
+            .collect();
+        assert_eq!(due, vec![(120, 1), (240, 2)]);
+    }
+
+    #[test]
+    fn unlimited_and_disabled() {
+        let mut settings = ReminderSettings {
+            interval_secs: 60,
+            max_reminders: 0,
+            ..ReminderSettings::default()
+        };
+        assert_eq!(settings.due(60 * 100), Some(100));
+        settings.enabled = false;
+        assert_eq!(settings.due(60), None);
+    }
+
+    #[test]
+    fn escalation_gets_louder_and_caps() {
+        let settings = ReminderSettings::default();
+        let sound = EventSound {
+            volume: 0.5,
+            ..EventSound::default()
+        };
+        assert!((settings.escalate(&sound, 1).volume - 0.6).abs() < 1e-6);
+        assert!((settings.escalate(&sound, 3).volume - 0.8).abs() < 1e-6);
+        assert_eq!(settings.escalate(&sound, 10).volume, 1.0);
+        assert_eq!(settings.escalate(&sound, 1).sound, sound.sound);
+    }
+
+    #[test]
+    fn validation() {
+        assert!(ReminderSettings::default().validate().is_ok());
+        let short = ReminderSettings {
+            interval_secs: 5,
+            ..ReminderSettings::default()
+        };
+        assert!(short.validate().is_err());
+        let loud = ReminderSettings {
+            volume_step: 2.0,
+            ..ReminderSettings::default()
+        };
+        assert!(loud.validate().is_err());
+    }
+}
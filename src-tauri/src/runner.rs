@@ -1,15 +1,34 @@
+use chrono::{Local, Utc};
 use std::sync::Mutex;
 use tauri::webview::WebviewWindowBuilder;
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Emitter, Manager, Monitor};
+use tauri_plugin_notification::NotificationExt;
 use tokio::time::{interval, Duration};
 
-use crate::commands::{ActiveTimer, AppState, TimerSnapshot};
-use crate::tray::update_tray_title;
+use crate::audio::{AudioPlayer, EventSound, SoundEvent};
+use crate::commands::{
+    ambient_playing, begin_notification, do_run_schedule, emit_and_update_tray, info_notification,
+    save_schedule_state, ActiveTimer, AppState, NotificationEvent, TimerSnapshot,
+};
+use crate::history::{BreakEventKind, BreakHistory};
+use crate::notification::{
+    command_error_message, encode_query_value, missed_schedules_message, notification_message,
+    quiet_summary_message, reminder_message, segment_message, warning_message, ActionButton,
+    NotificationMessage, NotificationStyle,
+};
+use crate::overlay::{OverlayPayload, OverlayStyle, OverlayTarget, OverlayTracker, Rect};
+use crate::program::Segment;
+use crate::quiet_hours::QuietTracker;
+use crate::schedule::ScheduleState;
+use crate::tray::refresh_tray;
+use crate::warnings::{Countdown, Warning, WarningPayload, WarningSettings, WarningTracker};
 
 #[derive(Clone, serde::Serialize)]
 struct PhaseChangePayload {
     from: String,
     to: String,
+    /// Pass to `notification_action` to act on this phase change.
+    event_id: u64,
 }
 
 pub fn start_tick_loop(app: AppHandle) {
@@ -22,67 +41,476 @@ pub fn start_tick_loop(app: AppHandle) {
     });
 }
 
+/// Run schedules as they come due, checked once a second next to the tick
+/// loop. The first check reports what was missed since the app last ran.
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut check_interval = interval(Duration::from_secs(1));
+        loop {
+            check_interval.tick().await;
+            check_schedules(&app);
+        }
+    });
+}
+
+fn check_schedules(app: &AppHandle) {
+    let now = Local::now();
+    let schedules = {
+        let state = app.state::<Mutex<AppState>>();
+        let s = state.lock().unwrap();
+        s.settings.schedules.clone()
+    };
+    let due = {
+        let tracker = app.state::<Mutex<ScheduleState>>();
+        let mut tracker = tracker.lock().unwrap();
+        let last_minute = tracker.checked_at.map(|at| at.timestamp() / 60);
+        let due = tracker.check(&schedules, &now);
+        // Saved once a minute, and whenever something came due, so a restart
+        // knows how far it got
+        if last_minute != Some(now.timestamp() / 60) || due != Default::default() {
+            save_schedule_state(app, &tracker);
+        }
+        due
+    };
+    let mut missed = due.missed;
+    for occurrence in due.run {
+        let result = {
+            let state = app.state::<Mutex<AppState>>();
+            let mut s = state.lock().unwrap();
+            do_run_schedule(&mut s, &occurrence, &now)
+        };
+        match result {
+            Ok(Some(snapshot)) => emit_and_update_tray(app, snapshot),
+            // A running timer or waiting break is not replaced; the occurrence
+            // counts as missed
+            Ok(None) => missed.push(occurrence),
+            Err(e) => eprintln!("Failed to run schedule {}: {}", occurrence.schedule, e),
+        }
+    }
+    if !missed.is_empty() {
+        let _ = app.emit("schedule:missed", &missed);
+        let alert_with = {
+            let state = app.state::<Mutex<AppState>>();
+            let mut s = state.lock().unwrap();
+            // Quiet hours and do-not-disturb hold this alert back like any other
+            (!is_quiet(&s)).then(|| {
+                let event = info_notification(&mut s, "schedule", "missed");
+                (s.settings.notifications.style, event)
+            })
+        };
+        if let Some((style, event)) = alert_with {
+            alert(app, style, &event, &missed_schedules_message(&missed));
+        }
+    }
+}
+
 fn tick_once(app: &AppHandle) {
     let state = app.state::<Mutex<AppState>>();
-    let mut state = state.lock().unwrap();
+    let mut guard = state.lock().unwrap();
+    let state = &mut *guard;
+    let style = state.settings.notifications.style;
+    let mut auto_start = state.settings.auto_start;
+    // While presenting, a break that comes due waits (see `set_presenting`)
+    if state.presenting {
+        auto_start.breaks = false;
+    }
 
-    match &mut state.active {
+    let ticked = match &mut state.active {
         ActiveTimer::Basic(timer) => {
             let was_finished = timer.is_finished();
-            timer.tick();
-            let snapshot = TimerSnapshot::from_basic(timer);
-            let _ = app.emit("timer:tick", &snapshot);
-            if timer.is_finished() && !was_finished {
-                let _ = app.emit(
-                    "timer:phase-change",
-                    PhaseChangePayload {
-                        from: "timer".to_string(),
-                        to: "finished".to_string(),
-                    },
-                );
-                open_notification_window(app, "timer", "finished");
-            }
+            timer.tick_at(&Local::now());
+            (timer.is_finished() && !was_finished)
+                .then(|| ("timer".to_string(), "finished".to_string()))
         }
         ActiveTimer::Pomodoro(timer) => {
+            let transition = timer.tick_with(auto_start);
+            transition.map(|t| (format!("{:?}", t.from), format!("{:?}", t.to)))
+        }
+        ActiveTimer::Flowtime(timer) => {
+            let transition = timer.tick_with(auto_start);
+            transition.map(|t| (format!("{:?}", t.from), format!("{:?}", t.to)))
+        }
+        ActiveTimer::Interval(timer) => {
             let transition = timer.tick();
-            let snapshot = TimerSnapshot::from_pomodoro(timer);
-            let _ = app.emit("timer:tick", &snapshot);
-            if let Some(t) = transition {
-                let from = format!("{:?}", t.from);
-                let to = format!("{:?}", t.to);
-                let _ = app.emit(
-                    "timer:phase-change",
-                    PhaseChangePayload {
-                        from: from.clone(),
-                        to: to.clone(),
-                    },
-                );
-                open_notification_window(app, &from, &to);
+            transition.map(|t| (format!("{:?}", t.from), format!("{:?}", t.to)))
+        }
+    };
+    // A skip not announced yet (see `announce_pending_change`) goes out now
+    let pending = state.pending_change.take();
+    let phase_change = ticked.or(pending);
+    let _ = app.emit("timer:tick", &TimerSnapshot::from_state(state));
+
+    // Quiet hours and do-not-disturb hold back alerts, not the timer itself
+    let quiet = is_quiet(state);
+    let quiet_tracker = app.state::<Mutex<QuietTracker>>();
+    let mut quiet_tracker = quiet_tracker.lock().unwrap();
+    let quiet_summary = quiet_tracker
+        .update(quiet)
+        .map(|summary| (info_notification(state, "quiet", "ended"), summary));
+
+    let announcement = phase_change
+        .as_ref()
+        .map(|(from, to)| prepare_announcement(app, state, from, to, quiet));
+    // Workouts cue every segment change and beep over each segment's last seconds
+    let cue = match &state.active {
+        ActiveTimer::Interval(t) if state.settings.sounds.enabled && !quiet => {
+            let cues = &state.settings.interval;
+            if phase_change.is_some() {
+                Some(cues.cue.clone())
+            } else if t.in_countdown() {
+                Some(cues.countdown.clone())
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+    .filter(|cue| cue.sound.is_some());
+    let reminder = match &state.active {
+        ActiveTimer::Basic(t) if t.is_unacknowledged() => t
+            .finished_secs_ago()
+            .and_then(|secs| state.settings.reminders.due(secs).map(|n| (n, secs))),
+        _ => None,
+    };
+    let reminder_sound = reminder.filter(|_| !quiet).and_then(|(n, _)| {
+        state
+            .settings
+            .sounds
+            .for_event(SoundEvent::TimerFinished)
+            .map(|sound| state.settings.reminders.escalate(sound, n))
+    });
+    let reminder_event = reminder.map(|_| begin_notification(state, "timer", "finished"));
+    let target = state.settings.overlay.target;
+    let overlay_style = state.settings.overlay.style;
+    let ambient = state.settings.ambient.clone();
+    let ambient_playing = ambient_playing(state);
+    let overlay = reconcile_overlays(app, state);
+
+    let countdown = Countdown::from_state(state);
+    let mut warnings = app
+        .state::<Mutex<WarningTracker>>()
+        .lock()
+        .unwrap()
+        .check(&countdown, &state.settings.warnings);
+    if quiet {
+        if let Some((_, to)) = &phase_change {
+            quiet_tracker.suppress_phase_change(to);
+        }
+        if reminder.is_some() {
+            quiet_tracker.suppress_reminder();
+        }
+        quiet_tracker.suppress_warnings(warnings.len() as u32);
+        warnings.clear();
+    }
+    drop(quiet_tracker);
+    let warning_alerts = (!warnings.is_empty()).then(|| WarningAlerts {
+        settings: state.settings.warnings.clone(),
+        native: style.shows_native(),
+        sounds: state.settings.sounds.enabled,
+    });
+
+    drop(guard);
+
+    let audio = app.state::<AudioPlayer>();
+    audio.update_ambient(&ambient, ambient_playing);
+    if let Some(cue) = cue {
+        audio.play(&cue);
+    }
+    if let Some(alerts) = warning_alerts {
+        for warning in warnings {
+            alerts.fire(app, &audio, &countdown, warning);
+        }
+    }
+    if let (Some((_, finished_secs_ago)), Some(event)) = (reminder, reminder_event) {
+        if !quiet {
+            if let Some(sound) = reminder_sound {
+                audio.play(&sound);
             }
+            remind(app, finished_secs_ago, style, &event);
+        }
+    }
+    if let Some((event, summary)) = quiet_summary {
+        alert(app, style, &event, &quiet_summary_message(&summary));
+    }
+    if let Some(announcement) = announcement {
+        announcement.send(app, style);
+    }
+    sync_overlays(app, overlay, target, overlay_style);
+    refresh_tray(app);
+}
+
+/// Announce a phase change made outside the tick loop, e.g. by skipping, the
+/// same way the tick loop announces its own. Called from
+/// `emit_and_update_tray`, so AFTER dropping the AppState lock.
+pub fn announce_pending_change(app: &AppHandle) {
+    let state = app.state::<Mutex<AppState>>();
+    let mut guard = state.lock().unwrap();
+    let state = &mut *guard;
+    let Some((from, to)) = state.pending_change.take() else {
+        return;
+    };
+    let style = state.settings.notifications.style;
+    let quiet = is_quiet(state);
+    if quiet {
+        let quiet_tracker = app.state::<Mutex<QuietTracker>>();
+        quiet_tracker.lock().unwrap().suppress_phase_change(&to);
+    }
+    let announcement = prepare_announcement(app, state, &from, &to, quiet);
+    let target = state.settings.overlay.target;
+    let overlay_style = state.settings.overlay.style;
+    let overlay = reconcile_overlays(app, state);
+    drop(guard);
+
+    announcement.send(app, style);
+    sync_overlays(app, overlay, target, overlay_style);
+}
+
+/// Show `error` from a command that has nowhere else to report it, e.g. one a
+/// second launch forwarded from a terminal that never sees the running app's
+/// output.
+pub fn alert_command_error(app: &AppHandle, error: &str) {
+    let (style, event) = {
+        let state = app.state::<Mutex<AppState>>();
+        let mut s = state.lock().unwrap();
+        let event = info_notification(&mut s, "command", "failed");
+        (s.settings.notifications.style, event)
+    };
+    alert(app, style, &event, &command_error_message(error));
+}
+
+/// Quiet hours and do-not-disturb hold back alerts.
+fn is_quiet(state: &AppState) -> bool {
+    state.do_not_disturb || state.settings.quiet_hours.is_quiet(Utc::now())
+}
+
+/// How a phase change is announced, worked out while the state is locked.
+struct Announcement {
+    event: NotificationEvent,
+    /// The program segment just entered.
+    entered: Option<Segment>,
+    shows_overlay: bool,
+    /// False while presenting or quiet, and for segments that stay silent.
+    notify: bool,
+    sound: Option<EventSound>,
+}
+
+impl Announcement {
+    fn send(self, app: &AppHandle, style: NotificationStyle) {
+        if let Some(sound) = &self.sound {
+            app.state::<AudioPlayer>().play(sound);
         }
+        let _ = app.emit(
+            "timer:phase-change",
+            PhaseChangePayload {
+                from: self.event.from.clone(),
+                to: self.event.to.clone(),
+                event_id: self.event.id,
+            },
+        );
+        if self.notify {
+            notify_phase_change(
+                app,
+                &self.event,
+                style,
+                self.entered.as_ref(),
+                self.shows_overlay,
+            );
+        }
+    }
+}
+
+/// Record the notification for `from` → `to`, hold back a break that starts
+/// while presenting, and open the break overlay the entered segment asks for.
+fn prepare_announcement(
+    app: &AppHandle,
+    state: &mut AppState,
+    from: &str,
+    to: &str,
+    quiet: bool,
+) -> Announcement {
+    // The program segment just entered decides how it is announced
+    let entered = match &state.active {
+        ActiveTimer::Pomodoro(t) => t.segment().cloned(),
+        _ => None,
+    };
+    let shows_overlay = match &state.active {
+        // Flowtime breaks have no segment but are covered all the same
+        ActiveTimer::Flowtime(t) => t.break_elapsed_secs().is_some(),
+        _ => entered.as_ref().is_some_and(Segment::shows_overlay),
+    };
+    let announce = entered.as_ref().is_none_or(|s| s.notify);
+
+    let deferring = state.presenting && shows_overlay;
+    if deferring {
+        state.deferred_break = Some(Countdown::from_state(state).key);
     }
+    let notify = !deferring && !quiet && announce;
 
-    drop(state);
-    update_tray_title(app);
+    let sound = SoundEvent::from_transition(from, to)
+        .filter(|_| notify)
+        .and_then(|event| {
+            // A preset's own sound replaces the one from settings
+            let sounds = &state.settings.sounds;
+            state
+                .preset
+                .as_ref()
+                .and_then(|p| p.sound_for(event))
+                .filter(|_| sounds.enabled)
+                .or_else(|| sounds.for_event(event))
+                .cloned()
+        });
+    let event = begin_notification(state, from, to);
+    if state.settings.notifications.style.shows_window() && !deferring && !quiet && shows_overlay {
+        app.state::<Mutex<OverlayTracker>>()
+            .lock()
+            .unwrap()
+            .open(state);
+    }
+    Announcement {
+        event,
+        entered,
+        shows_overlay,
+        notify,
+        sound,
+    }
 }
 
-fn open_notification_window(app: &AppHandle, from: &str, to: &str) {
-    // Work → Break transitions get a fullscreen overlay instead of a small notification
-    if from == "Work" && (to == "ShortBreak" || to == "LongBreak") {
-        open_overlay_windows(app, to);
+/// The overlay payload for the current state, closing the tracker's overlay
+/// once its break is over.
+fn reconcile_overlays(app: &AppHandle, state: &AppState) -> Option<OverlayPayload> {
+    let postpones_used = app
+        .state::<Mutex<BreakHistory>>()
+        .lock()
+        .unwrap()
+        .count_on(Local::now().date_naive(), BreakEventKind::Postponed);
+    app.state::<Mutex<OverlayTracker>>()
+        .lock()
+        .unwrap()
+        .reconcile(state, postpones_used)
+}
+
+/// What a pre-end warning may do, captured while the state is locked.
+struct WarningAlerts {
+    settings: WarningSettings,
+    native: bool,
+    sounds: bool,
+}
+
+impl WarningAlerts {
+    fn fire(&self, app: &AppHandle, audio: &AudioPlayer, countdown: &Countdown, warning: Warning) {
+        let _ = app.emit(
+            "timer:warning",
+            WarningPayload {
+                warning,
+                phase: countdown.label.clone(),
+                remaining_secs: countdown.remaining_secs,
+            },
+        );
+        match warning {
+            Warning::BeforeEnd { .. } => {
+                if self.settings.notify && self.native {
+                    let message = warning_message(&countdown.label, countdown.remaining_secs);
+                    send_native_notification(app, &message);
+                }
+                if self.sounds {
+                    audio.play(&self.settings.sound);
+                }
+            }
+            Warning::Countdown { .. } => {
+                if self.sounds {
+                    audio.play(&self.settings.countdown_sound);
+                }
+            }
+        }
+    }
+}
+
+/// Announce a phase change. A segment with a break overlay gets that instead
+/// of a notification window (see `sync_overlays`).
+fn notify_phase_change(
+    app: &AppHandle,
+    event: &NotificationEvent,
+    style: NotificationStyle,
+    segment: Option<&Segment>,
+    shows_overlay: bool,
+) {
+    let Some(message) = notification_message(&event.from, &event.to)
+        .or_else(|| segment.map(|s| segment_message(&s.name)))
+    else {
         return;
+    };
+    if style.shows_native() {
+        send_native_notification(app, &message);
+    }
+    if style.shows_window() && !shows_overlay {
+        open_notification_window(app, event, &message);
+    }
+}
+
+/// Re-alert for a finished timer that has not been acknowledged.
+fn remind(
+    app: &AppHandle,
+    finished_secs_ago: u32,
+    style: NotificationStyle,
+    event: &NotificationEvent,
+) {
+    alert(app, style, event, &reminder_message(finished_secs_ago));
+}
+
+/// Show `message` for `event` in the configured notification style.
+fn alert(
+    app: &AppHandle,
+    style: NotificationStyle,
+    event: &NotificationEvent,
+    message: &NotificationMessage,
+) {
+    if style.shows_native() {
+        send_native_notification(app, message);
+    }
+    if style.shows_window() {
+        open_notification_window(app, event, message);
+    }
+}
+
+fn send_native_notification(app: &AppHandle, message: &NotificationMessage) {
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(&message.title)
+        .body(&message.body)
+        .show()
+    {
+        eprintln!("Failed to send notification: {}", e);
     }
+}
 
+fn open_notification_window(
+    app: &AppHandle,
+    event: &NotificationEvent,
+    message: &NotificationMessage,
+) {
     // Close existing notification window so a fresh one opens with the new params
     if let Some(win) = app.get_webview_window("notification") {
         let _ = win.close();
     }
 
-    let path = format!("notification.html?from={}&to={}", from, to);
+    let buttons: Vec<ActionButton> = event.actions.iter().copied().map(Into::into).collect();
+    let actions = serde_json::to_string(&buttons).unwrap_or_else(|_| "[]".to_string());
+    let path = format!(
+        "notification.html?id={}&from={}&to={}&title={}&body={}&actions={}",
+        event.id,
+        encode_query_value(&event.from),
+        encode_query_value(&event.to),
+        encode_query_value(&message.title),
+        encode_query_value(&message.body),
+        encode_query_value(&actions),
+    );
+    // Action buttons need an extra row in the window.
+    let height = if buttons.is_empty() { 120.0 } else { 156.0 };
     let url = tauri::WebviewUrl::App(path.into());
     let builder = WebviewWindowBuilder::new(app, "notification", url)
         .title("Notification")
-        .inner_size(300.0, 120.0)
+        .inner_size(300.0, height)
         .decorations(false)
         .transparent(true)
         .always_on_top(true)
@@ -96,46 +524,113 @@ fn open_notification_window(app: &AppHandle, from: &str, to: &str) {
     }
 }
 
-fn open_overlay_windows(app: &AppHandle, to: &str) {
-    // Close any existing overlay windows first
-    close_overlay_windows(app);
-
-    let monitors = match app.available_monitors() {
-        Ok(m) => m,
-        Err(e) => {
-            eprintln!("Failed to enumerate monitors: {}", e);
-            return;
+/// Open or close overlay windows to match the tracker and the monitors
+/// `target` selects, then push the live payload to them.
+fn sync_overlays(
+    app: &AppHandle,
+    payload: Option<OverlayPayload>,
+    target: OverlayTarget,
+    style: OverlayStyle,
+) {
+    let tracker = app.state::<Mutex<OverlayTracker>>();
+    let monitors = if payload.is_some() {
+        match app.available_monitors() {
+            Ok(m) => m,
+            Err(e) => {
+                eprintln!("Failed to enumerate monitors: {}", e);
+                return;
+            }
         }
+    } else {
+        Vec::new()
     };
+    let ids: Vec<String> = monitors.iter().map(monitor_id).collect();
+    let current = tracker.lock().unwrap().monitors();
+    let wanted = target.pick(&ids, &current, || {
+        let monitor = match target {
+            OverlayTarget::All => None,
+            OverlayTarget::Primary => app.primary_monitor().ok().flatten(),
+            OverlayTarget::MainWindow => app
+                .get_webview_window("main")
+                .and_then(|w| w.current_monitor().ok().flatten()),
+            OverlayTarget::Cursor => app
+                .cursor_position()
+                .ok()
+                .and_then(|pos| app.monitor_from_point(pos.x, pos.y).ok().flatten()),
+        };
+        monitor.as_ref().map(monitor_id)
+    });
+    let plan = tracker.lock().unwrap().plan(&wanted);
 
-    for (i, monitor) in monitors.iter().enumerate() {
-        let label = format!("overlay-{}", i);
-        let path = format!("overlay.html?to={}", to);
-        let url = tauri::WebviewUrl::App(path.into());
+    for label in &plan.close {
+        if let Some(win) = app.get_webview_window(label) {
+            let _ = win.close();
+        }
+    }
+    if let Some(payload) = payload {
+        for (id, label) in &plan.open {
+            let Some(monitor) = monitors.iter().find(|m| monitor_id(m) == *id) else {
+                continue;
+            };
+            if let Err(e) = open_overlay_window(app, monitor, label, &payload.phase, style) {
+                eprintln!("Failed to create overlay window {}: {}", label, e);
+                tracker.lock().unwrap().forget(label);
+            }
+        }
+        let _ = app.emit("overlay:update", &payload);
+    }
+}
 
-        let pos = monitor.position();
-        let size = monitor.size();
-        let scale = monitor.scale_factor();
+/// Identifies a monitor across enumerations.
+fn monitor_id(monitor: &Monitor) -> String {
+    let pos = monitor.position();
+    format!(
+        "{}@{},{}",
+        monitor.name().map(String::as_str).unwrap_or("monitor"),
+        pos.x,
+        pos.y
+    )
+}
 
-        let logical_width = size.width as f64 / scale;
-        let logical_height = size.height as f64 / scale;
-        let logical_x = pos.x as f64 / scale;
-        let logical_y = pos.y as f64 / scale;
+fn open_overlay_window(
+    app: &AppHandle,
+    monitor: &Monitor,
+    label: &str,
+    to: &str,
+    style: OverlayStyle,
+) -> tauri::Result<()> {
+    let banner = style == OverlayStyle::Banner;
+    let path = format!(
+        "overlay.html?to={}&banner={}",
+        encode_query_value(to),
+        banner
+    );
+    let url = tauri::WebviewUrl::App(path.into());
 
-        let builder = WebviewWindowBuilder::new(app, &label, url)
-            .title("Break Overlay")
-            .position(logical_x, logical_y)
-            .inner_size(logical_width, logical_height)
-            .decorations(false)
-            .always_on_top(true)
-            .skip_taskbar(true)
-            .resizable(false);
+    let pos = monitor.position();
+    let size = monitor.size();
+    let scale = monitor.scale_factor();
 
-        match builder.build() {
-            Ok(_) => {}
-            Err(e) => eprintln!("Failed to create overlay window {}: {}", label, e),
-        }
-    }
+    let frame = style.frame(Rect {
+        x: pos.x as f64 / scale,
+        y: pos.y as f64 / scale,
+        width: size.width as f64 / scale,
+        height: size.height as f64 / scale,
+    });
+
+    WebviewWindowBuilder::new(app, label, url)
+        .title("Break Overlay")
+        .position(frame.x, frame.y)
+        .inner_size(frame.width, frame.height)
+        // A banner should not pull focus from what the user is doing
+        .focused(!banner)
+        .transparent(banner)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .build()?;
+    Ok(())
 }
 
 pub fn close_overlay_windows(app: &AppHandle) {
@@ -0,0 +1,473 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::alarm::Alarm;
+use crate::program::{Program, CLASSIC};
+
+pub const MAX_SCHEDULES: usize = 32;
+pub const MAX_NAME_CHARS: usize = 40;
+/// An occurrence found this long after it was due is reported as missed
+/// instead of run, e.g. after the app was closed or the machine slept.
+pub const GRACE_SECS: i64 = 60;
+/// How far back missed occurrences are looked for.
+pub const MISSED_LOOKBACK_DAYS: i64 = 7;
+pub const MAX_UPCOMING: usize = 100;
+
+/// What a schedule does when it comes due.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScheduleAction {
+    /// Ring like an alarm set for the occurrence.
+    Alarm,
+    /// Start the named pomodoro program.
+    Program {
+        name: String,
+    },
+    /// Start the interval workout from settings.
+    Interval,
+    Flowtime,
+}
+
+/// A recurring rule such as weekdays at "09:00" or every day at "12:30".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Days it runs on. Empty means every day.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// Wall-clock "HH:MM".
+    pub time: String,
+    pub action: ScheduleAction,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Schedule {
+    fn validate(&self, programs: &[Program]) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name: must not be empty".to_string());
+        }
+        if self.name.chars().count() > MAX_NAME_CHARS {
+            return Err(format!("name: must be at most {MAX_NAME_CHARS} characters"));
+        }
+        parse_time(&self.time).map_err(|e| format!("time: {e}"))?;
+        if let ScheduleAction::Program { name } = &self.action {
+            if name != CLASSIC && !programs.iter().any(|p| &p.name == name) {
+                return Err(format!("action.name: unknown program {name:?}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn runs_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    /// Occurrences strictly after `after`, earliest first, on its wall clock.
+    pub fn occurrences_after<'a, Tz: TimeZone>(
+        &'a self,
+        after: &'a DateTime<Tz>,
+    ) -> impl Iterator<Item = Occurrence> + 'a {
+        let time = parse_time(&self.time).ok();
+        let start = after.naive_local().date();
+        (0..)
+            .map_while(move |day| Some((start + Duration::days(day), time?)))
+            .filter(|(date, _)| self.runs_on(date.weekday()))
+            .map(|(date, time)| Occurrence {
+                schedule: self.name.clone(),
+                date,
+                time,
+            })
+            .filter(|o| o.instant(&after.timezone()) > *after)
+    }
+}
+
+fn parse_time(text: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(text, "%H:%M")
+        .map_err(|_| format!("invalid time {text:?}, expected HH:MM"))
+}
+
+/// `programs` are the ones from settings; the classic one always exists.
+pub fn validate_schedules(schedules: &[Schedule], programs: &[Program]) -> Result<(), String> {
+    if schedules.len() > MAX_SCHEDULES {
+        return Err(format!(": at most {MAX_SCHEDULES} allowed"));
+    }
+    for (i, schedule) in schedules.iter().enumerate() {
+        schedule
+            .validate(programs)
+            .map_err(|e| format!("[{i}].{e}"))?;
+        if schedules[..i].iter().any(|s| s.name == schedule.name) {
+            return Err(format!("[{i}].name: {:?} is already taken", schedule.name));
+        }
+    }
+    Ok(())
+}
+
+/// One run of a schedule, on the local wall clock.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Occurrence {
+    pub schedule: String,
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+}
+
+impl Occurrence {
+    pub fn alarm(&self) -> Alarm {
+        Alarm {
+            date: self.date,
+            time: self.time,
+            name: None,
+        }
+    }
+
+    pub fn instant<Tz: TimeZone>(&self, tz: &Tz) -> DateTime<Tz> {
+        self.alarm().instant(tz)
+    }
+}
+
+/// Payload entry of the `get_upcoming_occurrences` command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Upcoming {
+    #[serde(flatten)]
+    pub occurrence: Occurrence,
+    /// "15:30", or "Mar 10 15:30" when not today.
+    pub label: String,
+    pub skipped: bool,
+}
+
+/// Occurrences that came due since the last check.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Due {
+    /// On time; to be run, earliest first.
+    pub run: Vec<Occurrence>,
+    /// Too late to run, e.g. while the app was closed.
+    pub missed: Vec<Occurrence>,
+}
+
+/// Scheduler progress, persisted as JSON next to the settings (see
+/// [`crate::json_file`]) so occurrences missed while the app was closed can be
+/// reported on the next start. A missing or unreadable file starts fresh,
+/// which reports nothing as missed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleState {
+    /// When occurrences were last looked for; later ones are still to come.
+    pub checked_at: Option<DateTime<Utc>>,
+    /// Future occurrences the user chose to skip.
+    pub skipped: Vec<Occurrence>,
+}
+
+impl ScheduleState {
+    /// Collect the occurrences of enabled schedules due since the last check.
+    /// The first check only records `now`.
+    pub fn check<Tz: TimeZone>(&mut self, schedules: &[Schedule], now: &DateTime<Tz>) -> Due {
+        let tz = now.timezone();
+        let mut due = Due::default();
+        let since = self.checked_at.replace(now.with_timezone(&Utc));
+        // A clock set backwards just restarts from now.
+        if let Some(since) = since.filter(|since| *since < now.with_timezone(&Utc)) {
+            let lookback = now.clone() - Duration::days(MISSED_LOOKBACK_DAYS);
+            let since = since.with_timezone(&tz).max(lookback);
+            let mut found: Vec<Occurrence> = schedules
+                .iter()
+                .filter(|s| s.enabled)
+                .flat_map(|s| {
+                    s.occurrences_after(&since)
+                        .take_while(|o| o.instant(&tz) <= *now)
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            found.sort_by_key(|o| o.instant(&tz));
+            for occurrence in found {
+                if let Some(i) = self.skipped.iter().position(|s| *s == occurrence) {
+                    self.skipped.remove(i);
+                } else if (now.clone() - occurrence.instant(&tz)).num_seconds() > GRACE_SECS {
+                    due.missed.push(occurrence);
+                } else {
+                    due.run.push(occurrence);
+                }
+            }
+        }
+        self.skipped.retain(|o| o.instant(&tz) > *now);
+        due
+    }
+
+    /// The next `count` occurrences across enabled schedules, earliest first.
+    pub fn upcoming<Tz: TimeZone>(
+        &self,
+        schedules: &[Schedule],
+        now: &DateTime<Tz>,
+        count: usize,
+    ) -> Vec<Upcoming> {
+        let tz = now.timezone();
+        let today = now.naive_local().date();
+        let count = count.min(MAX_UPCOMING);
+        let mut next: Vec<Occurrence> = schedules
+            .iter()
+            .filter(|s| s.enabled)
+            .flat_map(|s| s.occurrences_after(now).take(count).collect::<Vec<_>>())
+            .collect();
+        next.sort_by_key(|o| o.instant(&tz));
+        next.truncate(count);
+        next.into_iter()
+            .map(|occurrence| Upcoming {
+                label: occurrence.alarm().label(today),
+                skipped: self.skipped.contains(&occurrence),
+                occurrence,
+            })
+            .collect()
+    }
+
+    /// Skip the next occurrence of schedule `name` that is not already
+    /// skipped, and return it.
+    pub fn skip_next<Tz: TimeZone>(
+        &mut self,
+        schedules: &[Schedule],
+        name: &str,
+        now: &DateTime<Tz>,
+    ) -> Result<Occurrence, String> {
+        let schedule = schedules
+            .iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| format!("unknown schedule {name:?}"))?;
+        if !schedule.enabled {
+            return Err(format!("schedule {name:?} is disabled"));
+        }
+        let occurrence = schedule
+            .occurrences_after(now)
+            .find(|o| !self.skipped.contains(o))
+            .ok_or_else(|| format!("schedule {name:?} has no upcoming occurrence"))?;
+        self.skipped.push(occurrence.clone());
+        Ok(occurrence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_file;
+    use chrono_tz::America::New_York;
+    use chrono_tz::Tz;
+
+    // 2024-03-04 is a Monday.
+    fn ny(d: u32, h: u32, min: u32) -> DateTime<Tz> {
+        New_York.with_ymd_and_hms(2024, 3, d, h, min, 0).unwrap()
+    }
+
+    fn schedule(name: &str, days: &[Weekday], time: &str) -> Schedule {
+        Schedule {
+            name: name.to_string(),
+            enabled: true,
+            days: days.to_vec(),
+            time: time.to_string(),
+            action: ScheduleAction::Alarm,
+        }
+    }
+
+    fn weekdays() -> Vec<Weekday> {
+        use Weekday::*;
+        vec![Mon, Tue, Wed, Thu, Fri]
+    }
+
+    fn names(occurrences: &[Occurrence]) -> Vec<(String, u32)> {
+        occurrences
+            .iter()
+            .map(|o| (o.schedule.clone(), o.date.day()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_settings_json() {
+        let json = r#"[
+            {"name": "Focus", "days": ["Mon", "Tue"], "time": "09:00",
+             "action": {"kind": "program", "name": "classic"}},
+            {"name": "Lunch", "time": "12:30", "action": {"kind": "alarm"}}
+        ]"#;
+        let schedules: Vec<Schedule> = serde_json::from_str(json).unwrap();
+        assert_eq!(schedules[0].days, vec![Weekday::Mon, Weekday::Tue]);
+        assert!(schedules[1].enabled && schedules[1].days.is_empty());
+        assert!(validate_schedules(&schedules, &[]).is_ok());
+    }
+
+    #[test]
+    fn validation() {
+        let mut bad = schedule("Lunch", &[], "12:60");
+        assert!(validate_schedules(&[bad.clone()], &[])
+            .unwrap_err()
+            .starts_with("[0].time:"));
+        bad.time = "12:30".to_string();
+        bad.action = ScheduleAction::Program {
+            name: "deep".to_string(),
+        };
+        assert!(validate_schedules(&[bad], &[])
+            .unwrap_err()
+            .contains("unknown program"));
+        let twice = [schedule("A", &[], "09:00"), schedule("A", &[], "10:00")];
+        assert!(validate_schedules(&twice, &[])
+            .unwrap_err()
+            .contains("already taken"));
+        assert!(validate_schedules(&[schedule(" ", &[], "09:00")], &[]).is_err());
+    }
+
+    #[test]
+    fn weekday_rule_skips_the_weekend() {
+        let focus = schedule("Focus", &weekdays(), "09:00");
+        // Friday after 09:00: next is Monday.
+        let next: Vec<_> = focus.occurrences_after(&ny(8, 10, 0)).take(2).collect();
+        assert_eq!(names(&next), [("Focus".into(), 11), ("Focus".into(), 12)]);
+        // Exactly at the time counts as past.
+        let next = focus.occurrences_after(&ny(4, 9, 0)).next().unwrap();
+        assert_eq!(next.date.day(), 5);
+    }
+
+    #[test]
+    fn first_check_only_records_the_time() {
+        let schedules = [schedule("Lunch", &[], "12:30")];
+        let mut state = ScheduleState::default();
+        assert_eq!(state.check(&schedules, &ny(4, 12, 30)), Due::default());
+        assert!(state.checked_at.is_some());
+    }
+
+    #[test]
+    fn runs_on_time_and_reports_missed() {
+        let schedules = [
+            schedule("Focus", &weekdays(), "09:00"),
+            schedule("Lunch", &[], "12:30"),
+        ];
+        let mut state = ScheduleState::default();
+        state.check(&schedules, &ny(4, 12, 29));
+        let due = state.check(&schedules, &ny(4, 12, 30));
+        assert_eq!(names(&due.run), [("Lunch".into(), 4)]);
+        assert!(due.missed.is_empty());
+        // Nothing twice.
+        assert_eq!(state.check(&schedules, &ny(4, 12, 31)), Due::default());
+
+        // Closed from Monday afternoon until Wednesday 10:00.
+        let due = state.check(&schedules, &ny(6, 10, 0));
+        assert!(due.run.is_empty());
+        assert_eq!(
+            names(&due.missed),
+            [
+                ("Focus".into(), 5),
+                ("Lunch".into(), 5),
+                ("Focus".into(), 6)
+            ]
+        );
+    }
+
+    #[test]
+    fn missed_lookback_is_bounded() {
+        let schedules = [schedule("Lunch", &[], "12:30")];
+        let mut state = ScheduleState {
+            checked_at: Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+            skipped: Vec::new(),
+        };
+        let due = state.check(&schedules, &ny(20, 13, 0));
+        assert_eq!(due.missed.len(), MISSED_LOOKBACK_DAYS as usize);
+    }
+
+    #[test]
+    fn disabled_schedules_do_not_run() {
+        let mut lunch = schedule("Lunch", &[], "12:30");
+        lunch.enabled = false;
+        let schedules = [lunch];
+        let mut state = ScheduleState::default();
+        state.check(&schedules, &ny(4, 12, 0));
+        assert_eq!(state.check(&schedules, &ny(4, 12, 30)), Due::default());
+        assert!(state.upcoming(&schedules, &ny(4, 12, 0), 5).is_empty());
+        assert!(state.skip_next(&schedules, "Lunch", &ny(4, 12, 0)).is_err());
+    }
+
+    #[test]
+    fn upcoming_merges_schedules() {
+        let schedules = [
+            schedule("Focus", &weekdays(), "09:00"),
+            schedule("Lunch", &[], "12:30"),
+        ];
+        let state = ScheduleState::default();
+        // Saturday morning.
+        let upcoming = state.upcoming(&schedules, &ny(9, 8, 0), 4);
+        let labels: Vec<_> = upcoming.iter().map(|u| u.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            ["12:30", "Mar 10 12:30", "Mar 11 09:00", "Mar 11 12:30"]
+        );
+        assert_eq!(upcoming[2].occurrence.schedule, "Focus");
+    }
+
+    #[test]
+    fn skipping_the_next_occurrence() {
+        let schedules = [schedule("Lunch", &[], "12:30")];
+        let mut state = ScheduleState::default();
+        let now = ny(4, 12, 0);
+        state.check(&schedules, &now);
+        assert_eq!(
+            state
+                .skip_next(&schedules, "Lunch", &now)
+                .unwrap()
+                .date
+                .day(),
+            4
+        );
+        // Skipping again skips the one after.
+        assert_eq!(
+            state
+                .skip_next(&schedules, "Lunch", &now)
+                .unwrap()
+                .date
+                .day(),
+            5
+        );
+        assert!(state.skip_next(&schedules, "Dinner", &now).is_err());
+        let upcoming = state.upcoming(&schedules, &now, 3);
+        let skipped: Vec<_> = upcoming.iter().map(|u| u.skipped).collect();
+        assert_eq!(skipped, [true, true, false]);
+
+        assert_eq!(state.check(&schedules, &ny(4, 12, 30)), Due::default());
+        assert_eq!(state.skipped.len(), 1);
+        // A skipped occurrence is not reported as missed either.
+        let due = state.check(&schedules, &ny(6, 13, 0));
+        assert_eq!(names(&due.missed), [("Lunch".into(), 6)]);
+        assert!(state.skipped.is_empty());
+    }
+
+    #[test]
+    fn clock_set_backwards_runs_nothing() {
+        let schedules = [schedule("Lunch", &[], "12:30")];
+        let mut state = ScheduleState::default();
+        state.check(&schedules, &ny(5, 13, 0));
+        assert_eq!(state.check(&schedules, &ny(4, 12, 0)), Due::default());
+        // Restarted from the earlier time.
+        let due = state.check(&schedules, &ny(4, 12, 30));
+        assert_eq!(names(&due.run), [("Lunch".into(), 4)]);
+    }
+
+    #[test]
+    fn round_trips_through_a_file() {
+        let path = std::env::temp_dir()
+            .join(format!("mac-timer-schedule-{}", std::process::id()))
+            .join("schedule_state.json");
+        let state = ScheduleState {
+            checked_at: Some(Utc.with_ymd_and_hms(2024, 3, 4, 12, 0, 0).unwrap()),
+            skipped: vec![Occurrence {
+                schedule: "Lunch".to_string(),
+                date: NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+                time: NaiveTime::from_hms_opt(12, 30, 0).unwrap(),
+            }],
+        };
+        json_file::save(&path, &state).unwrap();
+        assert_eq!(
+            json_file::load::<ScheduleState>(&path, "schedule state"),
+            state
+        );
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        assert_eq!(
+            json_file::load::<ScheduleState>(&path, "schedule state"),
+            ScheduleState::default()
+        );
+    }
+}
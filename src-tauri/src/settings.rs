@@ -0,0 +1,229 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ambient::AmbientSettings;
+use crate::audio::SoundSettings;
+use crate::flowtime::FlowtimeSettings;
+use crate::interval::IntervalSettings;
+use crate::json_file;
+use crate::notification::NotificationSettings;
+use crate::overlay::OverlaySettings;
+use crate::pomodoro::AutoStart;
+use crate::presets::Presets;
+use crate::program::{self, Program};
+use crate::quiet_hours::QuietHoursSettings;
+use crate::reminders::ReminderSettings;
+use crate::schedule::{self, Schedule};
+use crate::shortcuts::ShortcutSettings;
+use crate::snooze::SnoozeSettings;
+use crate::strict::StrictSettings;
+use crate::tray_format::TrayTitleSettings;
+use crate::warnings::WarningSettings;
+
+/// User preferences persisted as JSON in the app config directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub tray_title: TrayTitleSettings,
+    pub notifications: NotificationSettings,
+    pub sounds: SoundSettings,
+    pub ambient: AmbientSettings,
+    pub warnings: WarningSettings,
+    pub reminders: ReminderSettings,
+    pub snooze: SnoozeSettings,
+    pub auto_start: AutoStart,
+    pub overlay: OverlaySettings,
+    pub strict: StrictSettings,
+    pub quiet_hours: QuietHoursSettings,
+    /// Pomodoro programs besides the built-in classic one.
+    pub programs: Vec<Program>,
+    pub interval: IntervalSettings,
+    pub flowtime: FlowtimeSettings,
+    /// Recurring alarms and timer starts.
+    pub schedules: Vec<Schedule>,
+    pub presets: Presets,
+    pub shortcuts: ShortcutSettings,
+}
+
+impl Settings {
+    /// Check every field that can be invalid; the message is shown to the user.
+    pub fn validate(&self) -> Result<(), String> {
+        self.tray_title
+            .validate()
+            .map_err(|e| format!("tray_title.{e}"))?;
+        self.sounds.validate().map_err(|e| format!("sounds.{e}"))?;
+        self.ambient
+            .validate()
+            .map_err(|e| format!("ambient.{e}"))?;
+        self.warnings
+            .validate()
+            .map_err(|e| format!("warnings.{e}"))?;
+        self.reminders
+            .validate()
+            .map_err(|e| format!("reminders.{e}"))?;
+        self.snooze.validate().map_err(|e| format!("snooze.{e}"))?;
+        self.overlay
+            .validate()
+            .map_err(|e| format!("overlay.{e}"))?;
+        self.strict.validate().map_err(|e| format!("strict.{e}"))?;
+        self.quiet_hours
+            .validate()
+            .map_err(|e| format!("quiet_hours.{e}"))?;
+        program::validate_programs(&self.programs).map_err(|e| format!("programs{e}"))?;
+        self.interval
+            .validate()
+            .map_err(|e| format!("interval.{e}"))?;
+        self.flowtime
+            .validate()
+            .map_err(|e| format!("flowtime.{e}"))?;
+        schedule::validate_schedules(&self.schedules, &self.programs)
+            .map_err(|e| format!("schedules{e}"))?;
+        self.presets
+            .validate(&self.programs)
+            .map_err(|e| format!("presets{e}"))?;
+        self.shortcuts
+            .validate()
+            .map_err(|e| format!("shortcuts.{e}"))?;
+        Ok(())
+    }
+}
+
+/// Read settings from `path`. A missing file yields the defaults; an unreadable
+/// or invalid one is reported and also falls back to the defaults.
+pub fn load(path: &Path) -> Settings {
+    let settings: Settings = json_file::load(path, "settings");
+    settings.validate().map(|()| settings).unwrap_or_else(|e| {
+        eprintln!("Ignoring invalid settings {}: {}", path.display(), e);
+        Settings::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir()
+            .join(format!("mac-timer-settings-{}-{name}", std::process::id()))
+            .join("settings.json")
+    }
+
+    #[test]
+    fn default_settings_are_valid() {
+        assert!(Settings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_template_fails_validation() {
+        let mut settings = Settings::default();
+        settings.tray_title.template = "{icon} {bogus}".to_string();
+        let err = settings.validate().unwrap_err();
+        assert!(err.starts_with("tray_title.template: unknown placeholder {bogus}"));
+    }
+
+    #[test]
+    fn invalid_sound_fails_validation() {
+        let mut settings = Settings::default();
+        settings.sounds.work_finished.repeat = 99;
+        let err = settings.validate().unwrap_err();
+        assert!(err.starts_with("sounds.work_finished: repeat"));
+    }
+
+    #[test]
+    fn invalid_ambient_volume_fails_validation() {
+        let mut settings = Settings::default();
+        settings.ambient.volume = 1.5;
+        let err = settings.validate().unwrap_err();
+        assert!(err.starts_with("ambient.volume"));
+    }
+
+    #[test]
+    fn invalid_warning_offset_fails_validation() {
+        let mut settings = Settings::default();
+        settings.warnings.offsets_secs = vec![60, 0];
+        let err = settings.validate().unwrap_err();
+        assert!(err.starts_with("warnings.offsets_secs: 0 must be"));
+    }
+
+    #[test]
+    fn invalid_reminders_fail_validation() {
+        let mut settings = Settings::default();
+        settings.reminders.interval_secs = 1;
+        let err = settings.validate().unwrap_err();
+        assert!(err.starts_with("reminders.interval_secs"));
+    }
+
+    #[test]
+    fn unknown_quiet_hours_time_zone_fails_validation() {
+        let mut settings = Settings::default();
+        settings.quiet_hours.time_zone = Some("Nowhere/City".to_string());
+        let err = settings.validate().unwrap_err();
+        assert!(err.starts_with("quiet_hours.time_zone"));
+    }
+
+    #[test]
+    fn invalid_interval_workout_fails_validation() {
+        let mut settings = Settings::default();
+        settings.interval.workout.sets = 0;
+        let err = settings.validate().unwrap_err();
+        assert!(err.starts_with("interval.workout.sets"));
+    }
+
+    #[test]
+    fn schedule_for_unknown_program_fails_validation() {
+        let settings: Settings = serde_json::from_str(
+            r#"{"schedules": [{"name": "Focus", "time": "09:00", "action": {"kind": "program", "name": "deep"}}]}"#,
+        )
+        .unwrap();
+        let err = settings.validate().unwrap_err();
+        assert!(err.starts_with("schedules[0].action.name"));
+    }
+
+    #[test]
+    fn conflicting_shortcuts_fail_validation() {
+        let mut settings = Settings::default();
+        settings.shortcuts.toggle_timer = Some("Ctrl+Alt+T".to_string());
+        settings.shortcuts.skip_phase = Some("ctrl+alt+t".to_string());
+        let err = settings.validate().unwrap_err();
+        assert!(err.starts_with("shortcuts.skip_phase"));
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"tray_title": {"hide_seconds": true}}"#).unwrap();
+        assert!(settings.tray_title.hide_seconds);
+        assert_eq!(settings.tray_title.template, "{icon} {time}");
+        assert_eq!(settings.tray_title.icons.work, "🍅");
+        assert_eq!(settings.notifications, NotificationSettings::default());
+        assert_eq!(settings.sounds, SoundSettings::default());
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let path = temp_path("round-trip");
+        let mut settings = Settings::default();
+        settings.tray_title.template = "{icon} {mm}".to_string();
+        json_file::save(&path, &settings).unwrap();
+        assert_eq!(load(&path), settings);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn load_missing_file_returns_defaults() {
+        assert_eq!(load(&temp_path("missing")), Settings::default());
+    }
+
+    #[test]
+    fn load_invalid_file_returns_defaults() {
+        let path = temp_path("invalid");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, r#"{"tray_title": {"template": "{"}}"#).unwrap();
+        assert_eq!(load(&path), Settings::default());
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(load(&path), Settings::default());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,350 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tray_menu::TrayAction;
+
+/// Modifiers in the order they are written, with the spellings accepted for
+/// each.
+const MODIFIERS: &[(&str, &[&str])] = &[
+    (
+        "CmdOrCtrl",
+        &[
+            "cmdorctrl",
+            "commandorcontrol",
+            "cmdorcontrol",
+            "commandorctrl",
+        ],
+    ),
+    ("Cmd", &["cmd", "command", "super", "meta"]),
+    ("Ctrl", &["ctrl", "control"]),
+    ("Alt", &["alt", "option"]),
+    ("Shift", &["shift"]),
+];
+
+/// Named keys besides letters, digits and F1–F24, with their aliases.
+const KEYS: &[(&str, &[&str])] = &[
+    ("Space", &["space"]),
+    ("Enter", &["enter", "return"]),
+    ("Tab", &["tab"]),
+    ("Escape", &["escape", "esc"]),
+    ("Backspace", &["backspace"]),
+    ("Delete", &["delete", "del"]),
+    ("Up", &["up", "arrowup"]),
+    ("Down", &["down", "arrowdown"]),
+    ("Left", &["left", "arrowleft"]),
+    ("Right", &["right", "arrowright"]),
+    ("Home", &["home"]),
+    ("End", &["end"]),
+    ("PageUp", &["pageup"]),
+    ("PageDown", &["pagedown"]),
+    ("Minus", &["minus", "-"]),
+    ("Equal", &["equal", "="]),
+    ("Comma", &["comma", ","]),
+    ("Period", &["period", "."]),
+    ("Slash", &["slash", "/"]),
+    ("Semicolon", &["semicolon", ";"]),
+    ("Quote", &["quote", "'"]),
+    ("Backquote", &["backquote", "`"]),
+    ("BracketLeft", &["bracketleft", "["]),
+    ("BracketRight", &["bracketright", "]"]),
+    ("Backslash", &["backslash", "\\"]),
+];
+
+/// What a global shortcut does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    /// Start or pause the timer.
+    ToggleTimer,
+    Reset,
+    SkipPhase,
+    /// Show or hide the main window.
+    ToggleWindow,
+    /// Show the main window with the quick command box focused.
+    QuickEntry,
+}
+
+impl ShortcutAction {
+    /// The tray menu item doing the same, so both run the same code.
+    /// `None` for quick entry, which has no menu item.
+    pub fn tray_action(self) -> Option<TrayAction> {
+        match self {
+            ShortcutAction::ToggleTimer => Some(TrayAction::Toggle),
+            ShortcutAction::Reset => Some(TrayAction::Reset),
+            ShortcutAction::SkipPhase => Some(TrayAction::Skip),
+            ShortcutAction::ToggleWindow => Some(TrayAction::Show),
+            ShortcutAction::QuickEntry => None,
+        }
+    }
+}
+
+/// Rewrite an accelerator such as "ctrl+shift+k" as "Ctrl+Shift+K", the
+/// spelling registered with the system. It needs a modifier other than Shift,
+/// except for F keys, so it cannot take over ordinary typing.
+pub fn normalize(accelerator: &str) -> Result<String, String> {
+    let invalid = |why: String| format!("invalid shortcut {accelerator:?}: {why}");
+    let parts: Vec<&str> = accelerator.split('+').map(str::trim).collect();
+    let Some((key, modifiers)) = parts
+        .split_last()
+        .filter(|_| !accelerator.trim().is_empty())
+    else {
+        return Err(invalid("empty".to_string()));
+    };
+    let mut held = [false; MODIFIERS.len()];
+    for part in modifiers {
+        if part.is_empty() {
+            return Err(invalid("empty part between +".to_string()));
+        }
+        let lower = part.to_lowercase();
+        let Some(i) = MODIFIERS
+            .iter()
+            .position(|(_, names)| names.contains(&lower.as_str()))
+        else {
+            return Err(invalid(match key_name(part) {
+                Some(_) => "only the last part can be a key".to_string(),
+                None => format!("unknown modifier {part:?}"),
+            }));
+        };
+        if held[i] {
+            return Err(invalid(format!("{} given twice", MODIFIERS[i].0)));
+        }
+        held[i] = true;
+    }
+    if key.is_empty() {
+        return Err(invalid("missing key after the last +".to_string()));
+    }
+    let key = match key_name(key) {
+        Some(key) => key,
+        None if MODIFIERS
+            .iter()
+            .any(|(_, names)| names.contains(&key.to_lowercase().as_str())) =>
+        {
+            return Err(invalid("missing key after the modifiers".to_string()))
+        }
+        None => return Err(invalid(format!("unknown key {key:?}"))),
+    };
+    if held[0] && (held[1] || held[2]) {
+        return Err(invalid(
+            "CmdOrCtrl cannot be combined with Cmd or Ctrl".to_string(),
+        ));
+    }
+    let is_function_key = key.len() > 1 && key.starts_with('F') && key[1..].parse::<u8>().is_ok();
+    if !is_function_key && !held[..4].contains(&true) {
+        return Err(invalid(
+            "needs Cmd, Ctrl or Alt, unless it is an F key".to_string(),
+        ));
+    }
+    let mut out: Vec<&str> = MODIFIERS
+        .iter()
+        .zip(held)
+        .filter(|(_, held)| *held)
+        .map(|((name, _), _)| *name)
+        .collect();
+    out.push(&key);
+    Ok(out.join("+"))
+}
+
+/// The canonical name of `key`, or `None` if it is not one.
+fn key_name(key: &str) -> Option<String> {
+    let lower = key.to_lowercase();
+    if let Some((name, _)) = KEYS
+        .iter()
+        .find(|(_, names)| names.contains(&lower.as_str()))
+    {
+        return Some(name.to_string());
+    }
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphanumeric() {
+            return Some(c.to_ascii_uppercase().to_string());
+        }
+    }
+    let n: u8 = lower.strip_prefix('f')?.parse().ok()?;
+    (1..=24).contains(&n).then(|| format!("F{n}"))
+}
+
+/// `accelerator` with CmdOrCtrl replaced by what it means on this platform,
+/// so shortcuts that would press the same keys compare equal.
+fn resolved(accelerator: &str) -> String {
+    let platform = if cfg!(target_os = "macos") {
+        "Cmd"
+    } else {
+        "Ctrl"
+    };
+    match accelerator.strip_prefix("CmdOrCtrl+") {
+        Some(rest) => {
+            let mut parts: Vec<&str> = rest.split('+').collect();
+            parts.push(platform);
+            let order = |part: &&str| {
+                MODIFIERS
+                    .iter()
+                    .position(|(name, _)| name == part)
+                    .unwrap_or(MODIFIERS.len())
+            };
+            parts.sort_by_key(order);
+            parts.join("+")
+        }
+        None => accelerator.to_string(),
+    }
+}
+
+/// System-wide keyboard shortcuts, e.g. "CmdOrCtrl+Alt+Space". Each is off
+/// while unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShortcutSettings {
+    pub toggle_timer: Option<String>,
+    pub reset: Option<String>,
+    pub skip_phase: Option<String>,
+    pub toggle_window: Option<String>,
+    pub quick_entry: Option<String>,
+}
+
+impl ShortcutSettings {
+    fn entries(&self) -> [(&'static str, ShortcutAction, Option<&str>); 5] {
+        [
+            (
+                "toggle_timer",
+                ShortcutAction::ToggleTimer,
+                self.toggle_timer.as_deref(),
+            ),
+            ("reset", ShortcutAction::Reset, self.reset.as_deref()),
+            (
+                "skip_phase",
+                ShortcutAction::SkipPhase,
+                self.skip_phase.as_deref(),
+            ),
+            (
+                "toggle_window",
+                ShortcutAction::ToggleWindow,
+                self.toggle_window.as_deref(),
+            ),
+            (
+                "quick_entry",
+                ShortcutAction::QuickEntry,
+                self.quick_entry.as_deref(),
+            ),
+        ]
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut seen: Vec<(&str, String)> = Vec::new();
+        for (field, _, accelerator) in self.entries() {
+            let Some(accelerator) = accelerator else {
+                continue;
+            };
+            let keys = resolved(&normalize(accelerator).map_err(|e| format!("{field}: {e}"))?);
+            if let Some((other, _)) = seen.iter().find(|(_, k)| *k == keys) {
+                return Err(format!(
+                    "{field}: {accelerator:?} is already used by {other}"
+                ));
+            }
+            seen.push((field, keys));
+        }
+        Ok(())
+    }
+
+    /// The shortcuts that are set, spelled as they are registered.
+    pub fn bindings(&self) -> Vec<(ShortcutAction, String)> {
+        self.entries()
+            .into_iter()
+            .filter_map(|(_, action, accelerator)| {
+                normalize(accelerator?).ok().map(|a| (action, a))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_spelling_and_order() {
+        assert_eq!(normalize("ctrl+shift+k"), Ok("Ctrl+Shift+K".to_string()));
+        assert_eq!(
+            normalize("Shift + Option + Command + space"),
+            Ok("Cmd+Alt+Shift+Space".to_string())
+        );
+        assert_eq!(
+            normalize("CommandOrControl+Alt+ArrowUp"),
+            Ok("CmdOrCtrl+Alt+Up".to_string())
+        );
+        assert_eq!(normalize("Alt+/"), Ok("Alt+Slash".to_string()));
+        assert_eq!(normalize("f13"), Ok("F13".to_string()));
+        assert_eq!(normalize("Shift+F5"), Ok("Shift+F5".to_string()));
+        assert_eq!(normalize("Ctrl+1"), Ok("Ctrl+1".to_string()));
+    }
+
+    #[test]
+    fn rejects_what_cannot_be_registered() {
+        let err = |accelerator: &str| normalize(accelerator).unwrap_err();
+        assert_eq!(err(" "), "invalid shortcut \" \": empty");
+        assert!(err("Ctrl+").contains("missing key after the last +"));
+        assert!(err("Ctrl++K").contains("empty part between +"));
+        assert!(err("Ctrl+Shift").contains("missing key after the modifiers"));
+        assert!(err("Hyper+K").contains("unknown modifier \"Hyper\""));
+        assert!(err("K+Ctrl").contains("only the last part can be a key"));
+        assert!(err("Ctrl+Banana").contains("unknown key \"Banana\""));
+        assert!(err("Ctrl+F25").contains("unknown key"));
+        assert!(err("Ctrl+control+K").contains("Ctrl given twice"));
+        assert!(err("CmdOrCtrl+Cmd+K").contains("cannot be combined"));
+        assert!(err("K").contains("needs Cmd, Ctrl or Alt"));
+        assert!(err("Shift+K").contains("needs Cmd, Ctrl or Alt"));
+    }
+
+    #[test]
+    fn settings_default_to_no_shortcuts() {
+        let settings = ShortcutSettings::default();
+        assert!(settings.validate().is_ok());
+        assert!(settings.bindings().is_empty());
+        let parsed: ShortcutSettings =
+            serde_json::from_str(r#"{"toggle_timer": "ctrl+alt+t"}"#).unwrap();
+        assert_eq!(
+            parsed.bindings(),
+            [(ShortcutAction::ToggleTimer, "Ctrl+Alt+T".to_string())]
+        );
+    }
+
+    #[test]
+    fn validation_names_the_field_and_the_conflict() {
+        let mut settings = ShortcutSettings {
+            toggle_timer: Some("Ctrl+Alt+T".to_string()),
+            reset: Some("ctrl+bogus".to_string()),
+            ..ShortcutSettings::default()
+        };
+        assert!(settings.validate().unwrap_err().starts_with("reset: "));
+        settings.reset = Some("alt+CONTROL+t".to_string());
+        assert_eq!(
+            settings.validate().unwrap_err(),
+            "reset: \"alt+CONTROL+t\" is already used by toggle_timer"
+        );
+        // CmdOrCtrl presses the same keys as this platform's own modifier.
+        let own = if cfg!(target_os = "macos") {
+            "Cmd"
+        } else {
+            "Ctrl"
+        };
+        settings.reset = Some(format!("{own}+Shift+R"));
+        settings.quick_entry = Some("CmdOrCtrl+Shift+R".to_string());
+        assert!(settings
+            .validate()
+            .unwrap_err()
+            .starts_with("quick_entry: "));
+        settings.quick_entry = Some("CmdOrCtrl+Shift+Q".to_string());
+        assert!(settings.validate().is_ok());
+        assert_eq!(settings.bindings().len(), 3);
+    }
+
+    #[test]
+    fn actions_share_the_tray_menu_paths() {
+        assert_eq!(
+            ShortcutAction::ToggleTimer.tray_action(),
+            Some(TrayAction::Toggle)
+        );
+        assert_eq!(
+            ShortcutAction::ToggleWindow.tray_action(),
+            Some(TrayAction::Show)
+        );
+        assert_eq!(ShortcutAction::QuickEntry.tray_action(), None);
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+pub const MAX_SNOOZE_MINUTES: u32 = 120;
+
+/// Snooze lengths offered on the notification window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnoozeSettings {
+    pub options_minutes: Vec<u32>,
+}
+
+impl Default for SnoozeSettings {
+    fn default() -> Self {
+        Self {
+            options_minutes: vec![5, 10],
+        }
+    }
+}
+
+impl SnoozeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.options_minutes.len() > 4 {
+            return Err("options_minutes: at most 4 options".to_string());
+        }
+        for &minutes in &self.options_minutes {
+            validate_minutes(minutes).map_err(|e| format!("options_minutes: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn validate_minutes(minutes: u32) -> Result<(), String> {
+    if !(1..=MAX_SNOOZE_MINUTES).contains(&minutes) {
+        return Err(format!(
+            "snooze must be between 1 and {MAX_SNOOZE_MINUTES} minutes"
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the phase change reported as `from` → `to` can be snoozed: a
+/// finished basic timer, or a break that just ended.
+pub fn is_snoozable(from: &str, to: &str) -> bool {
+    matches!(
+        (from, to),
+        ("timer", "finished") | ("ShortBreak" | "LongBreak", "Work")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snoozable_transitions() {
+        assert!(is_snoozable("timer", "finished"));
+        assert!(is_snoozable("ShortBreak", "Work"));
+        assert!(is_snoozable("LongBreak", "Work"));
+        assert!(!is_snoozable("Work", "ShortBreak"));
+    }
+
+    #[test]
+    fn validation() {
+        assert!(SnoozeSettings::default().validate().is_ok());
+        let zero = SnoozeSettings {
+            options_minutes: vec![0],
+        };
+        assert!(zero.validate().is_err());
+        let many = SnoozeSettings {
+            options_minutes: vec![1, 2, 3, 4, 5],
+        };
+        assert!(many.validate().is_err());
+        assert!(validate_minutes(MAX_SNOOZE_MINUTES + 1).is_err());
+    }
+}
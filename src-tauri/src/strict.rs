@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+pub const MAX_MIN_BREAK_SECS: u32 = 60 * 60;
+pub const MAX_POSTPONE_MINUTES: u32 = 60;
+
+/// Opt-in rules that make break overlays harder to skip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrictSettings {
+    pub enabled: bool,
+    /// In strict mode, the overlay cannot be dismissed before this much of the
+    /// break has passed.
+    pub min_break_secs: u32,
+    /// Postpones allowed per day, in any mode.
+    pub postpones_per_day: u32,
+    pub postpone_minutes: u32,
+}
+
+impl Default for StrictSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_break_secs: 2 * 60,
+            postpones_per_day: 2,
+            postpone_minutes: 5,
+        }
+    }
+}
+
+impl StrictSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_break_secs > MAX_MIN_BREAK_SECS {
+            return Err(format!(
+                "min_break_secs: must be at most {MAX_MIN_BREAK_SECS}"
+            ));
+        }
+        if !(1..=MAX_POSTPONE_MINUTES).contains(&self.postpone_minutes) {
+            return Err(format!(
+                "postpone_minutes: must be between 1 and {MAX_POSTPONE_MINUTES}"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Seconds until the overlay may be dismissed, `elapsed_secs` into the
+    /// break. 0 when it may be dismissed now.
+    pub fn dismiss_wait_secs(&self, elapsed_secs: u32) -> u32 {
+        if self.enabled {
+            self.min_break_secs.saturating_sub(elapsed_secs)
+        } else {
+            0
+        }
+    }
+
+    pub fn postpones_left(&self, used_today: u32) -> u32 {
+        self.postpones_per_day.saturating_sub(used_today)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dismissal_waits_only_in_strict_mode() {
+        let mut settings = StrictSettings::default();
+        assert_eq!(settings.dismiss_wait_secs(0), 0);
+        settings.enabled = true;
+        assert_eq!(settings.dismiss_wait_secs(0), 120);
+        assert_eq!(settings.dismiss_wait_secs(100), 20);
+        assert_eq!(settings.dismiss_wait_secs(500), 0);
+    }
+
+    #[test]
+    fn postpones_run_out() {
+        let settings = StrictSettings::default();
+        assert_eq!(settings.postpones_left(0), 2);
+        assert_eq!(settings.postpones_left(2), 0);
+        assert_eq!(settings.postpones_left(5), 0);
+    }
+
+    #[test]
+    fn validation() {
+        assert!(StrictSettings::default().validate().is_ok());
+        let long = StrictSettings {
+            min_break_secs: MAX_MIN_BREAK_SECS + 1,
+            ..StrictSettings::default()
+        };
+        assert!(long.validate().is_err());
+        let zero = StrictSettings {
+            postpone_minutes: 0,
+            ..StrictSettings::default()
+        };
+        assert!(zero.validate().is_err());
+    }
+}
@@ -1,5 +1,9 @@
+use chrono::{DateTime, TimeZone};
 use serde::Serialize;
 
+use crate::alarm::Alarm;
+use crate::duration::{self, DurationStyle};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum TimerStatus {
     Idle,
@@ -11,19 +15,45 @@ pub enum TimerStatus {
 #[derive(Debug, Clone, Serialize)]
 pub struct BasicTimer {
     duration_secs: u32,
+    /// Length of the current run: the duration, or the snooze length after a snooze.
+    run_secs: u32,
     remaining_secs: u32,
     status: TimerStatus,
+    snoozes: u32,
+    /// Seconds since the timer finished; counted by `tick` while finished.
+    finished_secs: u32,
+    acknowledged: bool,
+    /// Counts down to this wall-clock time instead; see [`BasicTimer::tick_at`].
+    alarm: Option<Alarm>,
 }
 
 impl BasicTimer {
     pub fn new(duration_secs: u32) -> Self {
         Self {
             duration_secs,
+            run_secs: duration_secs,
             remaining_secs: duration_secs,
             status: TimerStatus::Idle,
+            snoozes: 0,
+            finished_secs: 0,
+            acknowledged: false,
+            alarm: None,
         }
     }
 
+    /// A timer that finishes when `alarm` rings, as seen from `now`.
+    pub fn with_alarm<Tz: TimeZone>(alarm: Alarm, now: &DateTime<Tz>) -> Self {
+        let timer = Self::new(alarm.secs_until(now));
+        Self {
+            alarm: Some(alarm),
+            ..timer
+        }
+    }
+
+    pub fn alarm(&self) -> Option<&Alarm> {
+        self.alarm.as_ref()
+    }
+
     pub fn remaining_secs(&self) -> u32 {
         self.remaining_secs
     }
@@ -32,6 +62,30 @@ impl BasicTimer {
         self.duration_secs
     }
 
+    pub fn run_secs(&self) -> u32 {
+        self.run_secs
+    }
+
+    /// Snoozes since the timer was last reset or given a new duration.
+    pub fn snooze_count(&self) -> u32 {
+        self.snoozes
+    }
+
+    /// Restart a finished timer for `secs`. Returns false if it isn't finished.
+    pub fn snooze(&mut self, secs: u32) -> bool {
+        if !self.is_finished() || secs == 0 {
+            return false;
+        }
+        self.run_secs = secs;
+        self.remaining_secs = secs;
+        self.status = TimerStatus::Running;
+        self.finished_secs = 0;
+        self.snoozes += 1;
+        // A snoozed alarm counts down like any other timer
+        self.alarm = None;
+        true
+    }
+
     pub fn status(&self) -> TimerStatus {
         self.status
     }
@@ -40,16 +94,60 @@ impl BasicTimer {
         self.status == TimerStatus::Finished
     }
 
+    /// A finished timer that nobody has dismissed yet.
+    pub fn is_unacknowledged(&self) -> bool {
+        self.is_finished() && !self.acknowledged
+    }
+
+    /// How long ago the timer finished, while it is finished.
+    pub fn finished_secs_ago(&self) -> Option<u32> {
+        self.is_finished().then_some(self.finished_secs)
+    }
+
+    /// Dismiss the finish alert. Returns false if there was nothing to dismiss.
+    pub fn acknowledge(&mut self) -> bool {
+        if !self.is_unacknowledged() {
+            return false;
+        }
+        self.acknowledged = true;
+        true
+    }
+
     pub fn tick(&mut self) {
+        if self.status == TimerStatus::Finished {
+            self.finished_secs = self.finished_secs.saturating_add(1);
+            return;
+        }
         if self.status != TimerStatus::Running {
             return;
         }
         self.remaining_secs = self.remaining_secs.saturating_sub(1);
         if self.remaining_secs == 0 {
-            self.status = TimerStatus::Finished;
+            self.finish();
+        }
+    }
+
+    /// Tick an alarm against the clock: the remaining time is re-read from
+    /// `now` every second, so it tracks clock, time zone and daylight saving
+    /// changes. Without an alarm this is [`BasicTimer::tick`].
+    pub fn tick_at<Tz: TimeZone>(&mut self, now: &DateTime<Tz>) {
+        let Some(alarm) = self.alarm.as_ref().filter(|_| !self.is_finished()) else {
+            self.tick();
+            return;
+        };
+        self.remaining_secs = alarm.secs_until(now);
+        self.run_secs = self.run_secs.max(self.remaining_secs);
+        if self.status == TimerStatus::Running && self.remaining_secs == 0 {
+            self.finish();
         }
     }
 
+    fn finish(&mut self) {
+        self.status = TimerStatus::Finished;
+        self.finished_secs = 0;
+        self.acknowledged = false;
+    }
+
     pub fn start(&mut self) {
         if self.status != TimerStatus::Finished {
             self.status = TimerStatus::Running;
@@ -62,27 +160,31 @@ impl BasicTimer {
         }
     }
 
+    /// Back to the full duration. An alarm that already rang becomes a plain
+    /// timer of its original length.
     pub fn reset(&mut self) {
+        if self.is_finished() {
+            self.alarm = None;
+        }
+        self.run_secs = self.duration_secs;
         self.remaining_secs = self.duration_secs;
         self.status = TimerStatus::Idle;
+        self.finished_secs = 0;
+        self.snoozes = 0;
     }
 
     pub fn set_duration(&mut self, secs: u32) {
+        self.alarm = None;
         self.duration_secs = secs;
+        self.run_secs = secs;
         self.remaining_secs = secs;
         self.status = TimerStatus::Idle;
+        self.finished_secs = 0;
+        self.snoozes = 0;
     }
 
     pub fn display(&self) -> String {
-        let total = self.remaining_secs;
-        let h = total / 3600;
-        let m = (total % 3600) / 60;
-        let s = total % 60;
-        if h > 0 {
-            format!("{h}:{m:02}:{s:02}")
-        } else {
-            format!("{m:02}:{s:02}")
-        }
+        duration::format(self.remaining_secs, DurationStyle::Clock)
     }
 }
 
@@ -184,4 +286,137 @@ mod tests {
         assert_eq!(timer.remaining_secs(), 600);
         assert_eq!(timer.status(), TimerStatus::Idle);
     }
+
+    #[test]
+    fn finished_timer_is_unacknowledged_and_counts_time() {
+        let mut timer = BasicTimer::new(1);
+        assert!(!timer.is_unacknowledged());
+        assert_eq!(timer.finished_secs_ago(), None);
+        timer.start();
+        timer.tick();
+        assert!(timer.is_unacknowledged());
+        assert_eq!(timer.finished_secs_ago(), Some(0));
+        timer.tick();
+        timer.tick();
+        assert_eq!(timer.finished_secs_ago(), Some(2));
+        assert_eq!(timer.remaining_secs(), 0);
+    }
+
+    #[test]
+    fn acknowledge_only_once() {
+        let mut timer = BasicTimer::new(1);
+        assert!(!timer.acknowledge());
+        timer.start();
+        timer.tick();
+        assert!(timer.acknowledge());
+        assert!(!timer.is_unacknowledged());
+        assert!(!timer.acknowledge());
+        assert!(timer.is_finished());
+    }
+
+    #[test]
+    fn finishing_again_needs_a_new_acknowledgement() {
+        let mut timer = BasicTimer::new(1);
+        timer.start();
+        timer.tick();
+        timer.acknowledge();
+        timer.reset();
+        assert!(!timer.is_unacknowledged());
+        timer.start();
+        timer.tick();
+        assert!(timer.is_unacknowledged());
+        assert_eq!(timer.finished_secs_ago(), Some(0));
+    }
+
+    #[test]
+    fn snooze_restarts_a_finished_timer() {
+        let mut timer = BasicTimer::new(1);
+        assert!(!timer.snooze(300));
+        timer.start();
+        timer.tick();
+        assert!(timer.snooze(300));
+        assert_eq!(timer.status(), TimerStatus::Running);
+        assert_eq!(timer.remaining_secs(), 300);
+        assert_eq!(timer.run_secs(), 300);
+        assert_eq!(timer.duration_secs(), 1);
+        assert_eq!(timer.snooze_count(), 1);
+        assert!(!timer.is_unacknowledged());
+    }
+
+    #[test]
+    fn snoozed_timer_finishes_and_alerts_again() {
+        let mut timer = BasicTimer::new(1);
+        timer.start();
+        timer.tick();
+        timer.acknowledge();
+        timer.snooze(2);
+        timer.tick();
+        timer.tick();
+        assert!(timer.is_unacknowledged());
+        assert!(timer.snooze(2));
+        assert_eq!(timer.snooze_count(), 2);
+    }
+
+    #[test]
+    fn reset_returns_to_the_original_duration() {
+        let mut timer = BasicTimer::new(1);
+        timer.start();
+        timer.tick();
+        timer.snooze(300);
+        timer.reset();
+        assert_eq!(timer.remaining_secs(), 1);
+        assert_eq!(timer.run_secs(), 1);
+        assert_eq!(timer.snooze_count(), 0);
+    }
+
+    fn alarm_at(h: u32, m: u32) -> (BasicTimer, chrono::DateTime<chrono::Utc>) {
+        use chrono::TimeZone;
+        let now = chrono::Utc.with_ymd_and_hms(2024, 3, 4, 15, 0, 0).unwrap();
+        let alarm = Alarm::parse(&format!("{h:02}:{m:02}"), None, &now).unwrap();
+        (BasicTimer::with_alarm(alarm, &now), now)
+    }
+
+    #[test]
+    fn alarm_follows_the_clock() {
+        let (mut timer, now) = alarm_at(15, 30);
+        assert_eq!(timer.remaining_secs(), 30 * 60);
+        assert_eq!(timer.run_secs(), 30 * 60);
+        timer.start();
+        // The machine slept for ten minutes between ticks.
+        timer.tick_at(&(now + chrono::Duration::minutes(10)));
+        assert_eq!(timer.remaining_secs(), 20 * 60);
+        timer.tick_at(&(now + chrono::Duration::minutes(30)));
+        assert!(timer.is_unacknowledged());
+        timer.tick_at(&(now + chrono::Duration::minutes(31)));
+        assert_eq!(timer.finished_secs_ago(), Some(1));
+    }
+
+    #[test]
+    fn paused_alarm_does_not_ring() {
+        let (mut timer, now) = alarm_at(15, 1);
+        timer.tick_at(&(now + chrono::Duration::minutes(2)));
+        assert_eq!(timer.remaining_secs(), 0);
+        assert!(!timer.is_finished());
+        timer.start();
+        timer.tick_at(&(now + chrono::Duration::minutes(2)));
+        assert!(timer.is_finished());
+    }
+
+    #[test]
+    fn snoozed_or_reset_alarm_becomes_a_timer() {
+        let (mut timer, now) = alarm_at(15, 1);
+        timer.start();
+        timer.tick_at(&(now + chrono::Duration::minutes(1)));
+        assert!(timer.snooze(300));
+        assert_eq!(timer.alarm(), None);
+        timer.tick_at(&(now + chrono::Duration::hours(1)));
+        assert_eq!(timer.remaining_secs(), 299);
+
+        let (mut timer, now) = alarm_at(15, 1);
+        timer.start();
+        timer.tick_at(&(now + chrono::Duration::minutes(1)));
+        timer.reset();
+        assert_eq!(timer.alarm(), None);
+        assert_eq!(timer.remaining_secs(), 60);
+    }
 }
@@ -1,26 +1,24 @@
 use std::sync::Mutex;
 use tauri::{
-    menu::{Menu, MenuItem},
+    menu::{IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{TrayIconBuilder, TrayIconEvent},
-    AppHandle, Manager, PhysicalPosition, Rect,
+    AppHandle, Manager, PhysicalPosition, Rect, Wry,
 };
 
-use crate::commands::AppState;
+use crate::commands::{self, emit_and_update_tray, AppState, TimerSnapshot};
+use crate::placement::{self, ScreenRect};
+use crate::tray_icon::{self, TrayIconCache, TrayIconSpec};
+use crate::tray_menu::{self, MenuEntry, TrayAction, TrayMenuCache};
 
-pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
-    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-    let menu = Menu::with_items(app, &[&quit])?;
+/// Linux panels never deliver tray click events, so the window has to be
+/// reachable from the menu instead.
+const MENU_SHOW_ITEM: bool = cfg!(target_os = "linux");
 
+pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
     TrayIconBuilder::with_id("main")
-        .title("⏱ 25:00")
         .tooltip("Timer")
-        .menu(&menu)
         .show_menu_on_left_click(false)
-        .on_menu_event(|app, event| {
-            if event.id() == "quit" {
-                app.exit(0);
-            }
-        })
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()))
         .on_tray_icon_event(|tray, event| {
             if let TrayIconEvent::Click {
                 button: tauri::tray::MouseButton::Left,
@@ -29,27 +27,62 @@ pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
                 ..
             } = event
             {
-                toggle_window(tray.app_handle(), rect);
+                toggle_window(tray.app_handle(), Some(rect));
             }
         })
         .build(app)?;
 
+    refresh_tray(app);
     Ok(())
 }
 
-pub fn toggle_window(app: &AppHandle, tray_rect: Rect) {
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    if let Some(action) = TrayAction::from_id(id) {
+        run_action(app, action);
+    }
+}
+
+/// Do what the menu item for `action` does. Global shortcuts share this path.
+pub fn run_action(app: &AppHandle, action: TrayAction) {
+    match action {
+        TrayAction::Quit => app.exit(0),
+        TrayAction::Show => toggle_window(app, None),
+        TrayAction::Dismiss => commands::acknowledge(app),
+        _ => {
+            let result = commands::with_break_rules(
+                app,
+                |s| tray_menu::leaves_break(action, s),
+                |s| tray_menu::apply(action, s),
+            );
+            match result {
+                Ok(Some(snapshot)) => emit_and_update_tray(app, snapshot),
+                Ok(None) => {}
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+    }
+}
+
+/// Show or hide the main window. With a tray rect the window opens next to the
+/// icon; without one (menu or keyboard triggered) it is centered on screen.
+pub fn toggle_window(app: &AppHandle, tray_rect: Option<Rect>) {
     if let Some(window) = app.get_webview_window("main") {
         if window.is_visible().unwrap_or(false) {
             let _ = window.hide();
         } else {
-            position_window_below_tray(&window, tray_rect);
+            match tray_rect {
+                Some(rect) => position_window_near_tray(&window, rect),
+                None => {
+                    let _ = window.center();
+                }
+            }
             let _ = window.show();
             let _ = window.set_focus();
         }
     }
 }
 
-fn position_window_below_tray(window: &tauri::WebviewWindow, tray_rect: Rect) {
+fn position_window_near_tray(window: &tauri::WebviewWindow, tray_rect: Rect) {
     let scale = window.scale_factor().unwrap_or(1.0);
     let win_size = match window.outer_size() {
         Ok(s) => s,
@@ -58,27 +91,125 @@ fn position_window_below_tray(window: &tauri::WebviewWindow, tray_rect: Rect) {
 
     let tray_pos = tray_rect.position.to_physical::<f64>(scale);
     let tray_size = tray_rect.size.to_physical::<f64>(scale);
+    let tray = ScreenRect::new(tray_pos.x, tray_pos.y, tray_size.width, tray_size.height);
 
-    // Center the window horizontally under the tray icon
-    let tray_center_x = tray_pos.x + tray_size.width / 2.0;
-    let x = tray_center_x - win_size.width as f64 / 2.0;
-    // Place window just below the tray icon
-    let y = tray_pos.y + tray_size.height;
+    // The tray may sit on any edge (bottom taskbar on Windows, side panels on Linux),
+    // so work out the side to open on from the monitor that holds the icon.
+    let screen = window
+        .monitor_from_point(
+            tray_pos.x + tray_size.width / 2.0,
+            tray_pos.y + tray_size.height / 2.0,
+        )
+        .ok()
+        .flatten()
+        .map(|m| {
+            let pos = m.position();
+            let size = m.size();
+            ScreenRect::new(
+                pos.x as f64,
+                pos.y as f64,
+                size.width as f64,
+                size.height as f64,
+            )
+        });
+
+    let (x, y) = placement::window_position(
+        tray,
+        (win_size.width as f64, win_size.height as f64),
+        screen,
+    );
 
     let _ = window.set_position(PhysicalPosition::new(x as i32, y as i32));
 }
 
+/// Bring the tray title, icon and menu in line with the current `AppState`.
+/// Must be called AFTER dropping the AppState lock.
+pub fn refresh_tray(app: &AppHandle) {
+    update_tray_title(app);
+    update_tray_menu(app);
+}
+
 pub fn update_tray_title(app: &AppHandle) {
     let state = app.state::<Mutex<AppState>>();
-    let title = {
+    let snapshot = {
         let state = state.lock().unwrap();
-        match &state.active {
-            crate::commands::ActiveTimer::Basic(t) => format!("⏱ {}", t.display()),
-            crate::commands::ActiveTimer::Pomodoro(t) => t.tray_title(),
-        }
+        TimerSnapshot::from_state(&state)
     };
 
     if let Some(tray) = app.tray_by_id("main") {
-        let _ = tray.set_title(Some(&title));
+        let _ = tray.set_tooltip(Some(&snapshot.tray_title));
+
+        #[cfg(target_os = "macos")]
+        let _ = tray.set_title(Some(&snapshot.tray_title));
+
+        update_tray_icon(app, &tray, &snapshot);
     }
 }
+
+fn update_tray_icon(app: &AppHandle, tray: &tauri::tray::TrayIcon, snapshot: &TimerSnapshot) {
+    // Windows and most Linux panels ignore tray titles, so the ring carries
+    // the remaining minutes there. On macOS the title already shows the time.
+    let show_minutes = cfg!(not(target_os = "macos"));
+    let spec = TrayIconSpec::from_snapshot(snapshot, show_minutes);
+
+    let cache = app.state::<Mutex<TrayIconCache>>();
+    if !cache.lock().unwrap().needs_update(&spec) {
+        return;
+    }
+    let icon = tauri::image::Image::new_owned(
+        tray_icon::render(&spec),
+        tray_icon::ICON_SIZE,
+        tray_icon::ICON_SIZE,
+    );
+    let _ = tray.set_icon(Some(icon));
+}
+
+fn update_tray_menu(app: &AppHandle) {
+    let model = {
+        let state = app.state::<Mutex<AppState>>();
+        let s = state.lock().unwrap();
+        tray_menu::menu_model(&s, MENU_SHOW_ITEM)
+    };
+
+    let cache = app.state::<Mutex<TrayMenuCache>>();
+    if !cache.lock().unwrap().needs_update(&model) {
+        return;
+    }
+    let Some(tray) = app.tray_by_id("main") else {
+        return;
+    };
+    match build_menu(app, &model) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => eprintln!("Failed to build tray menu: {}", e),
+    }
+}
+
+fn build_menu(app: &AppHandle, entries: &[MenuEntry]) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+    for entry in entries {
+        menu.append(build_entry(app, entry)?.as_ref())?;
+    }
+    Ok(menu)
+}
+
+fn build_entry(app: &AppHandle, entry: &MenuEntry) -> tauri::Result<Box<dyn IsMenuItem<Wry>>> {
+    Ok(match entry {
+        MenuEntry::Item { id, label, enabled } => Box::new(MenuItem::with_id(
+            app,
+            id.as_str(),
+            label,
+            *enabled,
+            None::<&str>,
+        )?),
+        MenuEntry::Separator => Box::new(PredefinedMenuItem::separator(app)?),
+        MenuEntry::Submenu { label, items } => {
+            let submenu = Submenu::new(app, label, true)?;
+            for item in items {
+                submenu.append(build_entry(app, item)?.as_ref())?;
+            }
+            Box::new(submenu)
+        }
+    })
+}
@@ -0,0 +1,754 @@
+use std::fmt;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::duration::{self, DurationStyle};
+use crate::flowtime::FlowtimeTimer;
+use crate::interval::IntervalTimer;
+use crate::pomodoro::{Phase, PomodoroTimer};
+use crate::timer::BasicTimer;
+
+/// Number of cells in the `{bar}` progress glyph bar.
+const BAR_CELLS: u32 = 5;
+
+/// Placeholder names accepted in a tray title template.
+pub const PLACEHOLDERS: [&str; 10] = [
+    "icon", "time", "hh", "mm", "ss", "sessions", "dots", "bar", "phase", "at",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Icon,
+    Time,
+    Hours,
+    Minutes,
+    Seconds,
+    Sessions,
+    Dots,
+    Bar,
+    Phase,
+    At,
+}
+
+impl Placeholder {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "icon" => Placeholder::Icon,
+            "time" => Placeholder::Time,
+            "hh" => Placeholder::Hours,
+            "mm" => Placeholder::Minutes,
+            "ss" => Placeholder::Seconds,
+            "sessions" => Placeholder::Sessions,
+            "dots" => Placeholder::Dots,
+            "bar" => Placeholder::Bar,
+            "phase" => Placeholder::Phase,
+            "at" => Placeholder::At,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    Empty,
+    UnknownPlaceholder(String),
+    UnclosedBrace(usize),
+    UnmatchedCloseBrace(usize),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Empty => write!(f, "tray title template is empty"),
+            TemplateError::UnknownPlaceholder(name) => write!(
+                f,
+                "unknown placeholder {{{name}}}; expected one of {}",
+                PLACEHOLDERS
+                    .iter()
+                    .map(|p| format!("{{{p}}}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TemplateError::UnclosedBrace(pos) => {
+                write!(f, "unclosed '{{' at position {pos}")
+            }
+            TemplateError::UnmatchedCloseBrace(pos) => {
+                write!(
+                    f,
+                    "unmatched '}}' at position {pos}; use '}}}}' for a literal brace"
+                )
+            }
+        }
+    }
+}
+
+/// A parsed tray title template such as `{icon} {mm}:{ss} {sessions}`.
+/// `{{` and `}}` produce literal braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayTemplate {
+    tokens: Vec<Token>,
+}
+
+impl TrayTemplate {
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        if template.trim().is_empty() {
+            return Err(TemplateError::Empty);
+        }
+
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().map(|(_, c)| *c) == Some('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().map(|(_, c)| *c) == Some('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, c)) => name.push(c),
+                            None => return Err(TemplateError::UnclosedBrace(pos)),
+                        }
+                    }
+                    let placeholder = Placeholder::from_name(name.trim())
+                        .ok_or(TemplateError::UnknownPlaceholder(name))?;
+                    if !literal.is_empty() {
+                        tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                    }
+                    tokens.push(Token::Placeholder(placeholder));
+                }
+                '}' => return Err(TemplateError::UnmatchedCloseBrace(pos)),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+        Ok(Self { tokens })
+    }
+
+    fn has(&self, placeholder: Placeholder) -> bool {
+        self.tokens.contains(&Token::Placeholder(placeholder))
+    }
+}
+
+/// Icons shown by `{icon}`, one per phase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhaseIcons {
+    pub work: String,
+    pub short_break: String,
+    pub long_break: String,
+    pub timer: String,
+    /// Program segments that are neither focus nor rest.
+    pub other: String,
+    pub interval: String,
+    pub alarm: String,
+}
+
+impl Default for PhaseIcons {
+    fn default() -> Self {
+        Self {
+            work: "🍅".to_string(),
+            short_break: "☕".to_string(),
+            long_break: "☕".to_string(),
+            timer: "⏱".to_string(),
+            other: "⏳".to_string(),
+            interval: "💪".to_string(),
+            alarm: "⏰".to_string(),
+        }
+    }
+}
+
+/// User settings for the menu bar title.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrayTitleSettings {
+    pub template: String,
+    /// Used instead of `template` in interval mode, where `{sessions}` is the
+    /// round counter.
+    pub interval_template: String,
+    /// Used for alarms, where `{at}` is the time the alarm rings.
+    pub alarm_template: String,
+    /// Show whole minutes only, so the title changes once a minute.
+    pub hide_seconds: bool,
+    /// How `{time}` is written when seconds are shown.
+    pub time_style: DurationStyle,
+    pub icons: PhaseIcons,
+}
+
+impl Default for TrayTitleSettings {
+    fn default() -> Self {
+        Self {
+            template: "{icon} {time}".to_string(),
+            interval_template: "{icon} {sessions} {time}".to_string(),
+            alarm_template: "{icon} {at} · {time}".to_string(),
+            hide_seconds: false,
+            time_style: DurationStyle::Clock,
+            icons: PhaseIcons::default(),
+        }
+    }
+}
+
+impl TrayTitleSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        TrayTemplate::parse(&self.template).map_err(|e| format!("template: {e}"))?;
+        TrayTemplate::parse(&self.interval_template)
+            .map_err(|e| format!("interval_template: {e}"))?;
+        TrayTemplate::parse(&self.alarm_template).map_err(|e| format!("alarm_template: {e}"))?;
+        Ok(())
+    }
+}
+
+/// Timer values a tray title can be rendered from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TitleContext {
+    pub phase: Option<Phase>,
+    /// What `{time}` shows: the time left, or worked in a Flowtime work phase.
+    pub display_secs: u32,
+    pub remaining_secs: u32,
+    pub total_secs: u32,
+    /// `(completed, per_cycle)` for pomodoro timers.
+    pub sessions: Option<(u32, u32)>,
+    pub session_display: Option<String>,
+    /// Name of the program segment, shown by `{phase}`.
+    pub segment: Option<String>,
+    /// The segment's own icon, used instead of the one for its phase.
+    pub icon: Option<String>,
+    /// `(round, rounds)` for interval workouts.
+    pub round: Option<(u32, u32)>,
+    /// When an alarm rings, e.g. "15:30", shown by `{at}`.
+    pub alarm: Option<String>,
+}
+
+impl TitleContext {
+    /// `now` decides whether the alarm label needs its date.
+    pub fn from_basic(t: &BasicTimer, now: &DateTime<Local>) -> Self {
+        Self {
+            phase: None,
+            display_secs: t.remaining_secs(),
+            remaining_secs: t.remaining_secs(),
+            total_secs: t.run_secs(),
+            sessions: None,
+            session_display: None,
+            segment: None,
+            icon: None,
+            round: None,
+            alarm: t.alarm().map(|a| a.label(now.date_naive())),
+        }
+    }
+
+    pub fn from_pomodoro(t: &PomodoroTimer) -> Self {
+        let per_cycle = t.sessions_per_cycle();
+        let segment = t.segment();
+        Self {
+            phase: Some(t.phase()),
+            display_secs: t.remaining_secs(),
+            remaining_secs: t.remaining_secs(),
+            total_secs: t.phase_duration_secs(),
+            sessions: Some((t.completed_sessions(), per_cycle)),
+            session_display: Some(t.session_display()),
+            segment: segment.map(|s| s.name.clone()),
+            icon: segment.and_then(|s| s.icon.clone()),
+            round: None,
+            alarm: None,
+        }
+    }
+
+    /// `{time}` counts up while working.
+    pub fn from_flowtime(t: &FlowtimeTimer) -> Self {
+        Self {
+            phase: Some(t.phase()),
+            display_secs: t.display_secs(),
+            remaining_secs: t.remaining_secs(),
+            total_secs: t.phase_duration_secs(),
+            sessions: None,
+            session_display: None,
+            segment: None,
+            icon: None,
+            round: None,
+            alarm: None,
+        }
+    }
+
+    pub fn from_interval(t: &IntervalTimer) -> Self {
+        let progress = t.progress();
+        Self {
+            phase: None,
+            display_secs: t.remaining_secs(),
+            remaining_secs: t.remaining_secs(),
+            total_secs: t.phase_duration_secs(),
+            sessions: None,
+            session_display: None,
+            segment: Some(t.phase().label().to_string()),
+            icon: None,
+            round: Some((progress.round, progress.rounds)),
+            alarm: None,
+        }
+    }
+}
+
+/// Render a tray title. Falls back to the default template if the configured
+/// one is invalid, so a bad settings file never blanks the menu bar.
+pub fn format_tray_title(settings: &TrayTitleSettings, ctx: &TitleContext) -> String {
+    let defaults = TrayTitleSettings::default();
+    let (source, fallback) = if ctx.round.is_some() {
+        (&settings.interval_template, &defaults.interval_template)
+    } else if ctx.alarm.is_some() {
+        (&settings.alarm_template, &defaults.alarm_template)
+    } else {
+        (&settings.template, &defaults.template)
+    };
+    let template = TrayTemplate::parse(source)
+        .unwrap_or_else(|_| TrayTemplate::parse(fallback).expect("default template is valid"));
+    render(&template, settings, ctx)
+}
+
+pub fn render(template: &TrayTemplate, settings: &TrayTitleSettings, ctx: &TitleContext) -> String {
+    let hours_shown = template.has(Placeholder::Hours);
+    let remaining = ctx.remaining_secs;
+    // With seconds hidden, round up so "0 minutes" only shows once time is up.
+    let whole_minutes = if settings.hide_seconds {
+        remaining.div_ceil(60)
+    } else {
+        remaining / 60
+    };
+
+    let mut out = String::new();
+    // Set after a placeholder that rendered nothing, so one space next to it
+    // can go instead of leaving doubled or trailing spaces behind.
+    let mut was_empty = false;
+    let mut after_colon = false;
+    for token in &template.tokens {
+        let p = match token {
+            Token::Placeholder(p) => p,
+            Token::Literal(s) => {
+                let s = match s.strip_prefix(' ') {
+                    Some(rest) if was_empty && (out.is_empty() || out.ends_with(' ')) => rest,
+                    _ => s,
+                };
+                out.push_str(s);
+                was_empty = false;
+                after_colon = s.ends_with(':');
+                continue;
+            }
+        };
+        let before = out.len();
+        match p {
+            Placeholder::Icon => out.push_str(
+                ctx.icon
+                    .as_deref()
+                    .unwrap_or_else(|| icon_for(&settings.icons, ctx)),
+            ),
+            Placeholder::Time => {
+                let style = if settings.hide_seconds {
+                    DurationStyle::Minutes
+                } else {
+                    settings.time_style
+                };
+                out.push_str(&duration::format(ctx.display_secs, style));
+            }
+            Placeholder::Hours => out.push_str(&(whole_minutes / 60).to_string()),
+            Placeholder::Minutes => {
+                if hours_shown {
+                    out.push_str(&format!("{:02}", whole_minutes % 60));
+                } else {
+                    out.push_str(&format!("{whole_minutes:02}"));
+                }
+            }
+            Placeholder::Seconds => {
+                if settings.hide_seconds {
+                    // Drop the separator typed before the seconds.
+                    if after_colon {
+                        out.pop();
+                    }
+                } else {
+                    out.push_str(&format!("{:02}", remaining % 60));
+                }
+            }
+            Placeholder::Sessions => {
+                if let Some((round, rounds)) = ctx.round {
+                    out.push_str(&format!("{round}/{rounds}"));
+                } else if let Some((done, per_cycle)) = ctx.sessions {
+                    let current = done % per_cycle.max(1);
+                    out.push_str(&format!("{current}/{per_cycle}"));
+                }
+            }
+            Placeholder::Dots => {
+                if let Some(dots) = &ctx.session_display {
+                    out.push_str(&dots.replace(' ', ""));
+                }
+            }
+            Placeholder::Bar => out.push_str(&progress_bar(remaining, ctx.total_secs)),
+            Placeholder::At => {
+                if let Some(at) = &ctx.alarm {
+                    out.push_str(at);
+                }
+            }
+            Placeholder::Phase => out.push_str(
+                ctx.segment
+                    .as_deref()
+                    .unwrap_or_else(|| phase_name(ctx.phase)),
+            ),
+        }
+        was_empty = out.len() <= before;
+        after_colon = false;
+    }
+    if was_empty && out.ends_with(' ') {
+        out.pop();
+    }
+    out
+}
+
+fn icon_for<'a>(icons: &'a PhaseIcons, ctx: &TitleContext) -> &'a str {
+    match ctx.phase {
+        None if ctx.round.is_some() => &icons.interval,
+        None if ctx.alarm.is_some() => &icons.alarm,
+        None => &icons.timer,
+        Some(Phase::Work) => &icons.work,
+        Some(Phase::ShortBreak) => &icons.short_break,
+        Some(Phase::LongBreak) => &icons.long_break,
+        Some(Phase::Other) => &icons.other,
+    }
+}
+
+fn phase_name(phase: Option<Phase>) -> &'static str {
+    match phase {
+        None => "Timer",
+        Some(Phase::Work) => "Work",
+        Some(Phase::ShortBreak) => "Short Break",
+        Some(Phase::LongBreak) => "Long Break",
+        Some(Phase::Other) => "Other",
+    }
+}
+
+/// Elapsed progress as filled/empty cells, e.g. `▰▰▱▱▱`.
+fn progress_bar(remaining_secs: u32, total_secs: u32) -> String {
+    let filled = if total_secs == 0 {
+        BAR_CELLS
+    } else {
+        let elapsed = total_secs.saturating_sub(remaining_secs) as u64;
+        (elapsed * BAR_CELLS as u64 / total_secs as u64) as u32
+    };
+    (0..BAR_CELLS)
+        .map(|i| if i < filled { '▰' } else { '▱' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pomodoro::PomodoroConfig;
+
+    fn settings(template: &str) -> TrayTitleSettings {
+        TrayTitleSettings {
+            template: template.to_string(),
+            ..TrayTitleSettings::default()
+        }
+    }
+
+    fn pomodoro_ctx(remaining_secs: u32, completed: u32) -> TitleContext {
+        TitleContext {
+            phase: Some(Phase::Work),
+            display_secs: remaining_secs,
+            remaining_secs,
+            total_secs: 25 * 60,
+            sessions: Some((completed, 4)),
+            session_display: Some("● ○ ○ ○".to_string()),
+            segment: None,
+            icon: None,
+            round: None,
+            alarm: None,
+        }
+    }
+
+    fn basic_ctx(remaining_secs: u32) -> TitleContext {
+        TitleContext::from_basic(&BasicTimer::new(remaining_secs), &Local::now())
+    }
+
+    #[test]
+    fn default_template_matches_previous_titles() {
+        let timer = PomodoroTimer::new(PomodoroConfig::default());
+        let ctx = TitleContext::from_pomodoro(&timer);
+        assert_eq!(
+            format_tray_title(&TrayTitleSettings::default(), &ctx),
+            "🍅 25:00"
+        );
+        assert_eq!(
+            format_tray_title(&TrayTitleSettings::default(), &basic_ctx(300)),
+            "⏱ 05:00"
+        );
+    }
+
+    #[test]
+    fn break_uses_break_icon() {
+        let mut timer = PomodoroTimer::new(PomodoroConfig {
+            work_secs: 3,
+            short_break_secs: 1,
+            long_break_secs: 2,
+            sessions_before_long_break: 4,
+        });
+        timer.start();
+        for _ in 0..3 {
+            timer.tick();
+        }
+        let ctx = TitleContext::from_pomodoro(&timer);
+        assert_eq!(
+            format_tray_title(&TrayTitleSettings::default(), &ctx),
+            "☕ 00:01"
+        );
+    }
+
+    #[test]
+    fn renders_all_placeholders() {
+        let s = settings("{icon} {mm}:{ss} {sessions} {dots} {bar} {phase}");
+        assert_eq!(
+            format_tray_title(&s, &pomodoro_ctx(10 * 60 + 7, 1)),
+            "🍅 10:07 1/4 ●○○○ ▰▰▱▱▱ Work"
+        );
+    }
+
+    #[test]
+    fn minutes_include_hours_unless_hours_shown() {
+        let ctx = basic_ctx(90 * 60 + 5);
+        assert_eq!(format_tray_title(&settings("{mm}:{ss}"), &ctx), "90:05");
+        assert_eq!(
+            format_tray_title(&settings("{hh}:{mm}:{ss}"), &ctx),
+            "1:30:05"
+        );
+    }
+
+    #[test]
+    fn hide_seconds_drops_seconds_and_separator() {
+        let s = TrayTitleSettings {
+            hide_seconds: true,
+            ..settings("{icon} {mm}:{ss} {sessions}")
+        };
+        assert_eq!(
+            format_tray_title(&s, &pomodoro_ctx(24 * 60 + 30, 0)),
+            "🍅 25 0/4"
+        );
+    }
+
+    #[test]
+    fn hide_seconds_keeps_a_colon_from_a_placeholder() {
+        let s = TrayTitleSettings {
+            hide_seconds: true,
+            ..settings("{phase}{ss}")
+        };
+        let ctx = TitleContext {
+            segment: Some("Set 1:".to_string()),
+            ..pomodoro_ctx(90, 0)
+        };
+        assert_eq!(format_tray_title(&s, &ctx), "Set 1:");
+    }
+
+    #[test]
+    fn spacing_outside_placeholders_is_kept() {
+        let s = settings("{icon}  {mm}:{ss}  |  {sessions}");
+        assert_eq!(
+            format_tray_title(&s, &pomodoro_ctx(90, 1)),
+            "🍅  01:30  |  1/4"
+        );
+        // Only the space next to an empty placeholder goes
+        assert_eq!(
+            format_tray_title(&settings("{sessions} {mm}:{ss} {sessions}"), &basic_ctx(90)),
+            "01:30"
+        );
+        assert_eq!(
+            format_tray_title(&settings("{icon} {sessions} {mm}:{ss}"), &basic_ctx(90)),
+            "⏱ 01:30"
+        );
+    }
+
+    #[test]
+    fn hide_seconds_time_shows_whole_minutes() {
+        let s = TrayTitleSettings {
+            hide_seconds: true,
+            ..TrayTitleSettings::default()
+        };
+        assert_eq!(format_tray_title(&s, &basic_ctx(61)), "⏱ 2m");
+        assert_eq!(format_tray_title(&s, &basic_ctx(0)), "⏱ 0m");
+    }
+
+    #[test]
+    fn time_style_changes_how_time_is_written() {
+        let mut s = settings("{icon} {time}");
+        s.time_style = DurationStyle::Compact;
+        assert_eq!(format_tray_title(&s, &basic_ctx(5400)), "⏱ 1h30m");
+        s.time_style = DurationStyle::Verbose;
+        assert_eq!(format_tray_title(&s, &basic_ctx(61)), "⏱ 1 minute 1 second");
+        s.time_style = DurationStyle::Clock;
+        assert_eq!(format_tray_title(&s, &basic_ctx(5400)), "⏱ 1:30:00");
+        // Hiding seconds wins over the style.
+        s.time_style = DurationStyle::Compact;
+        s.hide_seconds = true;
+        assert_eq!(format_tray_title(&s, &basic_ctx(5400)), "⏱ 90m");
+    }
+
+    #[test]
+    fn session_placeholders_are_empty_in_basic_mode() {
+        let s = settings("{icon} {time} {sessions} {dots}");
+        assert_eq!(format_tray_title(&s, &basic_ctx(300)), "⏱ 05:00");
+    }
+
+    #[test]
+    fn sessions_wrap_after_long_break() {
+        let s = settings("{sessions}");
+        assert_eq!(format_tray_title(&s, &pomodoro_ctx(60, 4)), "0/4");
+        assert_eq!(format_tray_title(&s, &pomodoro_ctx(60, 5)), "1/4");
+    }
+
+    #[test]
+    fn custom_icons_per_phase() {
+        let mut s = TrayTitleSettings::default();
+        s.icons.long_break = "🌴".to_string();
+        let ctx = TitleContext {
+            phase: Some(Phase::LongBreak),
+            ..pomodoro_ctx(60, 4)
+        };
+        assert_eq!(format_tray_title(&s, &ctx), "🌴 01:00");
+    }
+
+    #[test]
+    fn segment_name_and_icon_override_the_phase() {
+        let ctx = TitleContext {
+            phase: Some(Phase::Other),
+            segment: Some("Plan".to_string()),
+            ..pomodoro_ctx(60, 0)
+        };
+        assert_eq!(
+            format_tray_title(&settings("{icon} {phase}"), &ctx),
+            "⏳ Plan"
+        );
+        let ctx = TitleContext {
+            icon: Some("🧠".to_string()),
+            ..ctx
+        };
+        assert_eq!(
+            format_tray_title(&settings("{icon} {phase}"), &ctx),
+            "🧠 Plan"
+        );
+    }
+
+    #[test]
+    fn alarm_title_shows_target_and_remaining() {
+        let ctx = TitleContext {
+            alarm: Some("15:30".to_string()),
+            ..basic_ctx(25 * 60)
+        };
+        assert_eq!(
+            format_tray_title(&TrayTitleSettings::default(), &ctx),
+            "⏰ 15:30 · 25:00"
+        );
+        // `{at}` is empty for plain timers
+        assert_eq!(
+            format_tray_title(&settings("{at}{time}"), &basic_ctx(60)),
+            "01:00"
+        );
+    }
+
+    #[test]
+    fn flowtime_title_counts_work_up() {
+        let mut timer = FlowtimeTimer::new(crate::flowtime::FlowtimeSettings::default());
+        timer.start();
+        for _ in 0..90 {
+            timer.tick();
+        }
+        let ctx = TitleContext::from_flowtime(&timer);
+        assert_eq!(
+            format_tray_title(&TrayTitleSettings::default(), &ctx),
+            "🍅 01:30"
+        );
+    }
+
+    #[test]
+    fn interval_title_shows_the_round() {
+        let mut timer = IntervalTimer::new(crate::interval::IntervalConfig::default());
+        timer.skip();
+        timer.skip();
+        timer.skip();
+        let ctx = TitleContext::from_interval(&timer);
+        assert_eq!(
+            format_tray_title(&TrayTitleSettings::default(), &ctx),
+            "💪 2/8 00:20"
+        );
+        let s = TrayTitleSettings {
+            interval_template: "{phase} {sessions}".to_string(),
+            ..settings("{phase}")
+        };
+        assert_eq!(format_tray_title(&s, &ctx), "Work 2/8");
+    }
+
+    #[test]
+    fn bar_is_full_when_time_is_up() {
+        assert_eq!(progress_bar(0, 100), "▰▰▰▰▰");
+        assert_eq!(progress_bar(100, 100), "▱▱▱▱▱");
+        assert_eq!(progress_bar(0, 0), "▰▰▰▰▰");
+    }
+
+    #[test]
+    fn escaped_braces_are_literal() {
+        let s = settings("{{{mm}}}");
+        assert_eq!(format_tray_title(&s, &basic_ctx(120)), "{02}");
+    }
+
+    #[test]
+    fn parse_rejects_unknown_placeholder() {
+        assert_eq!(
+            TrayTemplate::parse("{icon} {minutes}"),
+            Err(TemplateError::UnknownPlaceholder("minutes".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_unbalanced_braces() {
+        assert_eq!(
+            TrayTemplate::parse("{icon} {mm"),
+            Err(TemplateError::UnclosedBrace(7))
+        );
+        assert_eq!(
+            TrayTemplate::parse("mm}"),
+            Err(TemplateError::UnmatchedCloseBrace(2))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_empty_template() {
+        assert_eq!(TrayTemplate::parse("  "), Err(TemplateError::Empty));
+    }
+
+    #[test]
+    fn placeholder_names_may_have_padding() {
+        assert!(TrayTemplate::parse("{ icon } { time }").is_ok());
+    }
+
+    #[test]
+    fn invalid_template_falls_back_to_default() {
+        assert_eq!(
+            format_tray_title(&settings("{nope}"), &basic_ctx(60)),
+            "⏱ 01:00"
+        );
+    }
+
+    #[test]
+    fn error_messages_are_readable() {
+        let err = TrayTemplate::parse("{x}").unwrap_err();
+        assert!(err.to_string().starts_with("unknown placeholder {x}"));
+    }
+}
@@ -0,0 +1,456 @@
+use crate::commands::TimerSnapshot;
+
+/// Width and height of the rendered tray icon in pixels.
+pub const ICON_SIZE: u32 = 32;
+
+/// Number of discrete positions the progress ring can show. The ring is
+/// quantized to these steps so the icon only changes when pixels would.
+pub const PROGRESS_STEPS: u32 = 60;
+
+const RING_OUTER_RADIUS: f32 = 15.5;
+const RING_INNER_RADIUS: f32 = 12.0;
+/// Text must fit in a circle this size to keep clear of the ring.
+const TEXT_MAX_RADIUS: f32 = RING_INNER_RADIUS - 1.5;
+
+const TRACK_COLOR: [u8; 4] = [128, 128, 128, 90];
+const TEXT_COLOR: [u8; 4] = [255, 255, 255, 255];
+
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+const MAX_MINUTES: u32 = 999;
+
+/// 3x5 bitmap glyphs for the digits 0-9, one row per entry, MSB on the left.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Colour scheme of the progress ring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconTone {
+    Work,
+    ShortBreak,
+    LongBreak,
+    Paused,
+}
+
+impl IconTone {
+    fn color(self) -> [u8; 4] {
+        match self {
+            IconTone::Work => [251, 146, 60, 255],
+            IconTone::ShortBreak => [52, 211, 153, 255],
+            IconTone::LongBreak => [96, 165, 250, 255],
+            IconTone::Paused => [156, 163, 175, 255],
+        }
+    }
+}
+
+/// Everything that determines the pixels of the tray icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrayIconSpec {
+    /// Filled part of the ring, in `0..=PROGRESS_STEPS`.
+    pub progress_steps: u32,
+    pub tone: IconTone,
+    /// Minute count drawn in the middle of the ring, if any.
+    pub minutes: Option<u32>,
+}
+
+impl TrayIconSpec {
+    pub fn new(remaining_secs: u32, total_secs: u32, tone: IconTone, show_minutes: bool) -> Self {
+        let progress_steps = if total_secs == 0 {
+            0
+        } else {
+            let remaining = remaining_secs.min(total_secs) as u64;
+            (remaining * PROGRESS_STEPS as u64).div_ceil(total_secs as u64) as u32
+        };
+        Self {
+            progress_steps,
+            tone,
+            minutes: show_minutes.then(|| remaining_secs.div_ceil(60).min(MAX_MINUTES)),
+        }
+    }
+
+    pub fn from_snapshot(snapshot: &TimerSnapshot, show_minutes: bool) -> Self {
+        let tone = if !snapshot.is_running {
+            IconTone::Paused
+        } else {
+            match snapshot.phase.as_deref() {
+                Some("ShortBreak") => IconTone::ShortBreak,
+                Some("LongBreak") => IconTone::LongBreak,
+                _ => IconTone::Work,
+            }
+        };
+        Self::new(
+            snapshot.remaining_secs,
+            snapshot.total_secs,
+            tone,
+            show_minutes,
+        )
+    }
+}
+
+/// Render the icon as a square RGBA buffer of `ICON_SIZE` x `ICON_SIZE` pixels.
+pub fn render(spec: &TrayIconSpec) -> Vec<u8> {
+    let mut canvas = Canvas::new(ICON_SIZE);
+    draw_ring(&mut canvas, spec.progress_steps, spec.tone.color());
+    if let Some(minutes) = spec.minutes {
+        draw_number(&mut canvas, minutes, TEXT_COLOR);
+    }
+    canvas.pixels
+}
+
+/// Remembers the last spec pushed to the tray so unchanged icons are not re-sent.
+#[derive(Debug, Default)]
+pub struct TrayIconCache {
+    last: Option<TrayIconSpec>,
+}
+
+impl TrayIconCache {
+    /// Returns true (and records `spec`) when it differs from the last icon shown.
+    pub fn needs_update(&mut self, spec: &TrayIconSpec) -> bool {
+        if self.last.as_ref() == Some(spec) {
+            return false;
+        }
+        self.last = Some(*spec);
+        true
+    }
+}
+
+struct Canvas {
+    size: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(size: u32) -> Self {
+        Self {
+            size,
+            pixels: vec![0; (size * size * 4) as usize],
+        }
+    }
+
+    fn set(&mut self, x: u32, y: u32, color: [u8; 4]) {
+        if x < self.size && y < self.size {
+            let i = ((y * self.size + x) * 4) as usize;
+            self.pixels[i..i + 4].copy_from_slice(&color);
+        }
+    }
+
+    fn fill_rect(&mut self, x: u32, y: u32, w: u32, h: u32, color: [u8; 4]) {
+        for py in y..y + h {
+            for px in x..x + w {
+                self.set(px, py, color);
+            }
+        }
+    }
+}
+
+fn draw_ring(canvas: &mut Canvas, progress_steps: u32, color: [u8; 4]) {
+    let center = canvas.size as f32 / 2.0;
+    let filled = progress_steps.min(PROGRESS_STEPS) as f32 / PROGRESS_STEPS as f32;
+
+    for y in 0..canvas.size {
+        for x in 0..canvas.size {
+            let dx = x as f32 + 0.5 - center;
+            let dy = y as f32 + 0.5 - center;
+            let dist = (dx * dx + dy * dy).sqrt();
+            // Anti-aliased coverage of the ring band at this pixel.
+            let coverage = (RING_OUTER_RADIUS - dist + 0.5).clamp(0.0, 1.0)
+                * (dist - RING_INNER_RADIUS + 0.5).clamp(0.0, 1.0);
+            if coverage <= 0.0 {
+                continue;
+            }
+
+            // Fraction of a turn, clockwise from 12 o'clock.
+            let turn = (dx.atan2(-dy) / std::f32::consts::TAU).rem_euclid(1.0);
+            let base = if turn < filled { color } else { TRACK_COLOR };
+            let alpha = (base[3] as f32 * coverage).round() as u8;
+            canvas.set(x, y, [base[0], base[1], base[2], alpha]);
+        }
+    }
+}
+
+fn draw_number(canvas: &mut Canvas, value: u32, color: [u8; 4]) {
+    let digits: Vec<usize> = value
+        .to_string()
+        .bytes()
+        .map(|b| (b - b'0') as usize)
+        .collect();
+    let count = digits.len() as u32;
+
+    let text_size = |scale: u32| {
+        (
+            count * GLYPH_WIDTH * scale + (count - 1) * scale,
+            GLYPH_HEIGHT * scale,
+        )
+    };
+    // Largest integer scale whose text box (with one-scale gaps) still fits inside the ring.
+    let scale = (1..=3)
+        .rev()
+        .find(|&s| {
+            let (w, h) = text_size(s);
+            (w as f32 / 2.0).hypot(h as f32 / 2.0) <= TEXT_MAX_RADIUS
+        })
+        .unwrap_or(1);
+    let (text_width, text_height) = text_size(scale);
+    let origin_x = canvas.size.saturating_sub(text_width) / 2;
+    let origin_y = canvas.size.saturating_sub(text_height) / 2;
+
+    for (n, digit) in digits.iter().enumerate() {
+        let glyph_x = origin_x + n as u32 * (GLYPH_WIDTH + 1) * scale;
+        for (row, bits) in DIGITS[*digit].iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                    canvas.fill_rect(
+                        glyph_x + col * scale,
+                        origin_y + row as u32 * scale,
+                        scale,
+                        scale,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pomodoro::{PomodoroConfig, PomodoroTimer};
+    use crate::timer::BasicTimer;
+    use crate::tray_format::TrayTitleSettings;
+    use chrono::Local;
+
+    /// One character per pixel: ' ' empty, '#' progress, '.' track, 'o' text.
+    fn ascii(spec: &TrayIconSpec) -> String {
+        let pixels = render(spec);
+        let tone = spec.tone.color();
+        pixels
+            .chunks(4)
+            .collect::<Vec<_>>()
+            .chunks(ICON_SIZE as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|p| match p {
+                        [_, _, _, 0] => ' ',
+                        [r, g, b, _] if [*r, *g, *b] == TEXT_COLOR[..3] => 'o',
+                        [r, g, b, _] if [*r, *g, *b] == tone[..3] => '#',
+                        _ => '.',
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn renders_square_rgba_buffer() {
+        let pixels = render(&TrayIconSpec::new(300, 600, IconTone::Work, true));
+        assert_eq!(pixels.len(), (ICON_SIZE * ICON_SIZE * 4) as usize);
+    }
+
+    #[test]
+    fn half_progress_snapshot() {
+        let spec = TrayIconSpec::new(750, 1500, IconTone::Work, true);
+        let expected = [
+            "            ....####",
+            "         .......#######",
+            "       .........#########",
+            "      ..........##########",
+            "     ...........###########",
+            "    .......          #######",
+            "   .......            #######",
+            "  ......                ######",
+            "  .....                  #####",
+            " ......                  ######",
+            " .....                    #####",
+            " ....      oo    oooooo    ####",
+            ".....      oo    oooooo    #####",
+            ".....    oooo        oo    #####",
+            ".....    oooo        oo    #####",
+            ".....      oo    oooooo    #####",
+            ".....      oo    oooooo    #####",
+            ".....      oo        oo    #####",
+            ".....      oo        oo    #####",
+            ".....    oooooo  oooooo    #####",
+            " ....    oooooo  oooooo    ####",
+            " .....                    #####",
+            " ......                  ######",
+            "  .....                  #####",
+            "  ......                ######",
+            "   .......            #######",
+            "    .......          #######",
+            "     ...........###########",
+            "      ..........##########",
+            "       .........#########",
+            "         .......#######",
+            "            ....####",
+        ]
+        .join("\n");
+        assert_eq!(ascii(&spec), expected);
+    }
+
+    #[test]
+    fn full_ring_without_minutes_snapshot() {
+        let spec = TrayIconSpec::new(60, 60, IconTone::ShortBreak, false);
+        let expected = [
+            "            ########",
+            "         ##############",
+            "       ##################",
+            "      ####################",
+            "     ######################",
+            "    #######          #######",
+            "   #######            #######",
+            "  ######                ######",
+            "  #####                  #####",
+            " ######                  ######",
+            " #####                    #####",
+            " ####                      ####",
+            "#####                      #####",
+            "#####                      #####",
+            "#####                      #####",
+            "#####                      #####",
+            "#####                      #####",
+            "#####                      #####",
+            "#####                      #####",
+            "#####                      #####",
+            " ####                      ####",
+            " #####                    #####",
+            " ######                  ######",
+            "  #####                  #####",
+            "  ######                ######",
+            "   #######            #######",
+            "    #######          #######",
+            "     ######################",
+            "      ####################",
+            "       ##################",
+            "         ##############",
+            "            ########",
+        ]
+        .join("\n");
+        assert_eq!(ascii(&spec), expected);
+    }
+
+    #[test]
+    fn progress_is_quantized_to_steps() {
+        assert_eq!(
+            TrayIconSpec::new(1500, 1500, IconTone::Work, false).progress_steps,
+            60
+        );
+        assert_eq!(
+            TrayIconSpec::new(750, 1500, IconTone::Work, false).progress_steps,
+            30
+        );
+        assert_eq!(
+            TrayIconSpec::new(1, 1500, IconTone::Work, false).progress_steps,
+            1
+        );
+        assert_eq!(
+            TrayIconSpec::new(0, 1500, IconTone::Work, false).progress_steps,
+            0
+        );
+        assert_eq!(
+            TrayIconSpec::new(0, 0, IconTone::Work, false).progress_steps,
+            0
+        );
+    }
+
+    #[test]
+    fn seconds_within_one_step_give_identical_spec() {
+        // 1500s / 60 steps = 25s per step
+        let a = TrayIconSpec::new(1499, 1500, IconTone::Work, false);
+        let b = TrayIconSpec::new(1476, 1500, IconTone::Work, false);
+        let c = TrayIconSpec::new(1475, 1500, IconTone::Work, false);
+        assert_eq!(a, b);
+        assert_ne!(b, c);
+    }
+
+    #[test]
+    fn minutes_round_up_and_are_capped() {
+        let spec = TrayIconSpec::new(61, 3600, IconTone::Work, true);
+        assert_eq!(spec.minutes, Some(2));
+        let spec = TrayIconSpec::new(100_000, 100_000, IconTone::Work, true);
+        assert_eq!(spec.minutes, Some(999));
+        let spec = TrayIconSpec::new(61, 3600, IconTone::Work, false);
+        assert_eq!(spec.minutes, None);
+    }
+
+    #[test]
+    fn three_digit_minutes_stay_inside_ring() {
+        let art = ascii(&TrayIconSpec::new(120 * 60, 120 * 60, IconTone::Work, true));
+        let text_rows: Vec<&str> = art.lines().filter(|l| l.contains('o')).collect();
+        assert!(!text_rows.is_empty());
+        for row in text_rows {
+            let first = row.find('o').unwrap();
+            let last = row.rfind('o').unwrap();
+            assert!(first >= 5 && last <= 26, "text overlaps ring: {row:?}");
+        }
+    }
+
+    #[test]
+    fn tone_changes_ring_color() {
+        let work = render(&TrayIconSpec::new(30, 60, IconTone::Work, false));
+        let paused = render(&TrayIconSpec::new(30, 60, IconTone::Paused, false));
+        assert_ne!(work, paused);
+    }
+
+    #[test]
+    fn idle_pomodoro_snapshot_is_paused_tone() {
+        let snap = TimerSnapshot::from_pomodoro(
+            &PomodoroTimer::new(PomodoroConfig::default()),
+            &TrayTitleSettings::default(),
+        );
+        let spec = TrayIconSpec::from_snapshot(&snap, false);
+        assert_eq!(spec.tone, IconTone::Paused);
+        assert_eq!(spec.progress_steps, PROGRESS_STEPS);
+    }
+
+    #[test]
+    fn running_break_snapshot_uses_break_tone() {
+        let mut timer = PomodoroTimer::new(PomodoroConfig {
+            work_secs: 1,
+            short_break_secs: 5,
+            long_break_secs: 10,
+            sessions_before_long_break: 4,
+        });
+        timer.start();
+        timer.tick();
+        let spec = TrayIconSpec::from_snapshot(
+            &TimerSnapshot::from_pomodoro(&timer, &TrayTitleSettings::default()),
+            true,
+        );
+        assert_eq!(spec.tone, IconTone::ShortBreak);
+        assert_eq!(spec.minutes, Some(1));
+    }
+
+    #[test]
+    fn running_basic_timer_uses_work_tone() {
+        let mut timer = BasicTimer::new(120);
+        timer.start();
+        let spec = TrayIconSpec::from_snapshot(
+            &TimerSnapshot::from_basic(&timer, &TrayTitleSettings::default(), &Local::now()),
+            false,
+        );
+        assert_eq!(spec.tone, IconTone::Work);
+    }
+
+    #[test]
+    fn cache_skips_identical_spec() {
+        let mut cache = TrayIconCache::default();
+        let spec = TrayIconSpec::new(30, 60, IconTone::Work, true);
+        assert!(cache.needs_update(&spec));
+        assert!(!cache.needs_update(&spec));
+        assert!(cache.needs_update(&TrayIconSpec::new(20, 60, IconTone::Work, true)));
+    }
+}
@@ -0,0 +1,679 @@
+use chrono::Local;
+
+use crate::commands::{self, ActiveTimer, AppState, TimerSnapshot};
+use crate::duration::{self, DurationStyle};
+use crate::flowtime::{FlowtimeStatus, FlowtimeTimer};
+use crate::interval::{IntervalStatus, IntervalTimer};
+use crate::pomodoro::{Phase, PomodoroStatus, PomodoroTimer};
+use crate::timer::TimerStatus;
+
+/// Platform-independent description of one tray menu entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuEntry {
+    Item {
+        id: String,
+        label: String,
+        enabled: bool,
+    },
+    Separator,
+    Submenu {
+        label: String,
+        items: Vec<MenuEntry>,
+    },
+}
+
+/// What a tray menu item does when clicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayAction {
+    Toggle,
+    Dismiss,
+    Reset,
+    Skip,
+    SwitchMode,
+    Preset(usize),
+    DoNotDisturb,
+    Show,
+    Quit,
+}
+
+impl TrayAction {
+    pub fn id(&self) -> String {
+        match self {
+            TrayAction::Toggle => "toggle".to_string(),
+            TrayAction::Dismiss => "dismiss".to_string(),
+            TrayAction::Reset => "reset".to_string(),
+            TrayAction::Skip => "skip".to_string(),
+            TrayAction::SwitchMode => "switch_mode".to_string(),
+            TrayAction::Preset(i) => format!("preset:{i}"),
+            TrayAction::DoNotDisturb => "do_not_disturb".to_string(),
+            TrayAction::Show => "show".to_string(),
+            TrayAction::Quit => "quit".to_string(),
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "toggle" => Some(TrayAction::Toggle),
+            "dismiss" => Some(TrayAction::Dismiss),
+            "reset" => Some(TrayAction::Reset),
+            "skip" => Some(TrayAction::Skip),
+            "switch_mode" => Some(TrayAction::SwitchMode),
+            "do_not_disturb" => Some(TrayAction::DoNotDisturb),
+            "show" => Some(TrayAction::Show),
+            "quit" => Some(TrayAction::Quit),
+            _ => id
+                .strip_prefix("preset:")
+                .and_then(|i| i.parse().ok())
+                .map(TrayAction::Preset),
+        }
+    }
+}
+
+fn item(action: TrayAction, label: &str, enabled: bool) -> MenuEntry {
+    MenuEntry::Item {
+        id: action.id(),
+        label: label.to_string(),
+        enabled,
+    }
+}
+
+fn phase_label(phase: Phase) -> &'static str {
+    match phase {
+        Phase::Work => "Work",
+        Phase::ShortBreak => "Short Break",
+        Phase::LongBreak => "Long Break",
+        Phase::Other => "Other",
+    }
+}
+
+/// The program segment's name, or the phase's for postponed extra work.
+fn segment_label(t: &PomodoroTimer) -> &str {
+    t.segment()
+        .map(|s| s.name.as_str())
+        .unwrap_or_else(|| phase_label(t.phase()))
+}
+
+/// "Flow · 2 sessions", or the break with its length.
+fn flowtime_label(t: &FlowtimeTimer) -> String {
+    let sessions = match t.completed_sessions() {
+        1 => "1 session".to_string(),
+        n => format!("{n} sessions"),
+    };
+    if t.phase() == Phase::Work {
+        format!("Flow · {sessions}")
+    } else {
+        format!(
+            "Break {} · {sessions}",
+            duration::format(t.phase_duration_secs(), DurationStyle::Minutes)
+        )
+    }
+}
+
+/// "Work · Round 3/8", with the set when there is more than one.
+fn interval_label(t: &IntervalTimer) -> String {
+    let progress = t.progress();
+    let mut label = format!("{} · Round {}", t.phase().label(), t.round_display());
+    if progress.sets > 1 {
+        label.push_str(&format!(" · Set {}/{}", progress.set, progress.sets));
+    }
+    label
+}
+
+/// Build the tray menu for the current state. `include_show` adds a
+/// "Show Timer" item for platforms where clicking the tray icon does nothing.
+pub fn menu_model(state: &AppState, include_show: bool) -> Vec<MenuEntry> {
+    let (status_line, toggle_label, toggle_enabled, reset_enabled) = match &state.active {
+        ActiveTimer::Basic(t) => {
+            let status = match t.status() {
+                TimerStatus::Idle => "Ready",
+                TimerStatus::Running => "Running",
+                TimerStatus::Paused => "Paused",
+                TimerStatus::Finished => "Finished",
+            };
+            let toggle = match t.status() {
+                TimerStatus::Running => "Pause",
+                TimerStatus::Paused => "Resume",
+                TimerStatus::Idle | TimerStatus::Finished => "Start",
+            }
+            .to_string();
+            let name = match t.alarm() {
+                Some(alarm) => {
+                    let at = alarm.label(Local::now().date_naive());
+                    match &alarm.name {
+                        Some(name) => format!("{name} at {at}"),
+                        None => format!("Alarm {at}"),
+                    }
+                }
+                None => state
+                    .preset
+                    .as_ref()
+                    .map_or("Timer", |p| p.display_name())
+                    .to_string(),
+            };
+            (
+                format!("{name} · {status}"),
+                toggle,
+                !t.is_finished(),
+                t.status() != TimerStatus::Idle,
+            )
+        }
+        ActiveTimer::Pomodoro(t) => {
+            let mut line = format!("{} · {}", segment_label(t), t.session_display());
+            match t.status() {
+                PomodoroStatus::Paused => line.push_str(" · Paused"),
+                PomodoroStatus::Waiting => line = format!("Next: {line}"),
+                PomodoroStatus::Idle | PomodoroStatus::Running => {}
+            }
+            let toggle = match t.status() {
+                PomodoroStatus::Running => "Pause".to_string(),
+                PomodoroStatus::Paused => "Resume".to_string(),
+                PomodoroStatus::Idle => "Start".to_string(),
+                PomodoroStatus::Waiting => format!("Start {}", segment_label(t)),
+            };
+            let pristine = t.status() == PomodoroStatus::Idle
+                && t.phase() == Phase::Work
+                && t.completed_sessions() == 0;
+            (line, toggle, true, !pristine)
+        }
+        ActiveTimer::Interval(t) => {
+            let line = match t.status() {
+                IntervalStatus::Finished => "Workout · Done".to_string(),
+                IntervalStatus::Paused => format!("{} · Paused", interval_label(t)),
+                IntervalStatus::Idle | IntervalStatus::Running => interval_label(t),
+            };
+            let toggle = match t.status() {
+                IntervalStatus::Running => "Pause",
+                IntervalStatus::Paused => "Resume",
+                IntervalStatus::Idle => "Start",
+                IntervalStatus::Finished => "Restart",
+            }
+            .to_string();
+            let pristine = t.status() == IntervalStatus::Idle && t.segment_index() == 0;
+            (line, toggle, true, !pristine)
+        }
+        ActiveTimer::Flowtime(t) => {
+            let mut line = flowtime_label(t);
+            if t.status() == FlowtimeStatus::Paused {
+                line.push_str(" · Paused");
+            }
+            let toggle = match t.status() {
+                FlowtimeStatus::Running => "Pause",
+                FlowtimeStatus::Paused => "Resume",
+                FlowtimeStatus::Idle => "Start",
+            }
+            .to_string();
+            let pristine = t.status() == FlowtimeStatus::Idle
+                && t.worked_secs() == 0
+                && t.completed_sessions() == 0;
+            (line, toggle, true, !pristine)
+        }
+    };
+    let (skip_label, can_skip) = match &state.active {
+        ActiveTimer::Basic(_) => ("Skip Phase", false),
+        ActiveTimer::Pomodoro(_) => ("Skip Phase", true),
+        ActiveTimer::Interval(t) => ("Skip Phase", !t.is_finished()),
+        // Taking a break is how a Flowtime work phase ends
+        ActiveTimer::Flowtime(t) if t.phase() == Phase::Work => ("Take Break", t.worked_secs() > 0),
+        ActiveTimer::Flowtime(_) => ("Skip Phase", true),
+    };
+
+    let mut entries = vec![
+        MenuEntry::Item {
+            id: "status".to_string(),
+            label: status_line,
+            enabled: false,
+        },
+        MenuEntry::Separator,
+    ];
+    if matches!(&state.active, ActiveTimer::Basic(t) if t.is_unacknowledged()) {
+        entries.push(item(TrayAction::Dismiss, "Dismiss Alert", true));
+    }
+    entries.extend([
+        item(TrayAction::Toggle, &toggle_label, toggle_enabled),
+        item(TrayAction::Reset, "Reset", reset_enabled),
+        item(TrayAction::Skip, skip_label, can_skip),
+        item(
+            TrayAction::SwitchMode,
+            if !matches!(state.active, ActiveTimer::Basic(_)) {
+                "Switch to Timer"
+            } else {
+                "Switch to Pomodoro"
+            },
+            true,
+        ),
+        MenuEntry::Submenu {
+            label: "Presets".to_string(),
+            items: state
+                .settings
+                .presets
+                .menu_order()
+                .into_iter()
+                .map(|(i, p)| {
+                    let label = if p.favorite {
+                        format!("★ {}", p.name)
+                    } else {
+                        p.name.clone()
+                    };
+                    item(TrayAction::Preset(i), &label, true)
+                })
+                .collect(),
+        },
+        item(
+            TrayAction::DoNotDisturb,
+            if state.do_not_disturb {
+                "Turn Off Do Not Disturb"
+            } else {
+                "Turn On Do Not Disturb"
+            },
+            true,
+        ),
+        MenuEntry::Separator,
+    ]);
+    if include_show {
+        entries.push(item(TrayAction::Show, "Show Timer", true));
+    }
+    entries.push(item(TrayAction::Quit, "Quit", true));
+    entries
+}
+
+/// Apply a state-changing menu action through the same `do_*` functions the
+/// commands use. Returns `None` for actions that don't touch `AppState`.
+pub fn apply(action: TrayAction, state: &mut AppState) -> Option<TimerSnapshot> {
+    match action {
+        TrayAction::Toggle => Some(commands::do_toggle(state)),
+        TrayAction::Dismiss => commands::do_acknowledge(state),
+        TrayAction::Reset => Some(commands::do_reset(state)),
+        TrayAction::Skip => commands::do_skip_phase(state),
+        TrayAction::SwitchMode => Some(commands::do_switch_mode(state)),
+        TrayAction::Preset(i) => {
+            let id = state.settings.presets.list().get(i)?.id.clone();
+            commands::do_start_preset(state, &id).ok()
+        }
+        TrayAction::DoNotDisturb => Some(commands::do_set_do_not_disturb(
+            state,
+            !state.do_not_disturb,
+        )),
+        TrayAction::Show | TrayAction::Quit => None,
+    }
+}
+
+/// Whether `action` ends or leaves a break in progress, so that it goes
+/// through `commands::do_leave_break`.
+pub fn leaves_break(action: TrayAction, state: &AppState) -> bool {
+    match action {
+        TrayAction::Toggle => commands::is_running(state),
+        TrayAction::Reset | TrayAction::Skip | TrayAction::SwitchMode | TrayAction::Preset(_) => {
+            true
+        }
+        TrayAction::Dismiss | TrayAction::DoNotDisturb | TrayAction::Show | TrayAction::Quit => {
+            false
+        }
+    }
+}
+
+/// Remembers the last menu pushed to the tray so it is only rebuilt on change.
+#[derive(Debug, Default)]
+pub struct TrayMenuCache {
+    last: Option<Vec<MenuEntry>>,
+}
+
+impl TrayMenuCache {
+    /// Returns true (and records `model`) when it differs from the last menu shown.
+    pub fn needs_update(&mut self, model: &[MenuEntry]) -> bool {
+        if self.last.as_deref() == Some(model) {
+            return false;
+        }
+        self.last = Some(model.to_vec());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interval::IntervalConfig;
+    use crate::pomodoro::{AutoStart, PomodoroConfig, PomodoroTimer};
+    use crate::timer::BasicTimer;
+
+    fn find<'a>(entries: &'a [MenuEntry], id: &str) -> Option<(&'a str, bool)> {
+        entries.iter().find_map(|e| match e {
+            MenuEntry::Item {
+                id: item_id,
+                label,
+                enabled,
+            } if item_id == id => Some((label.as_str(), *enabled)),
+            _ => None,
+        })
+    }
+
+    fn basic_state(secs: u32) -> AppState {
+        AppState {
+            active: ActiveTimer::Basic(BasicTimer::new(secs)),
+            ..AppState::default()
+        }
+    }
+
+    #[test]
+    fn action_ids_round_trip() {
+        for action in [
+            TrayAction::Toggle,
+            TrayAction::Dismiss,
+            TrayAction::Reset,
+            TrayAction::Skip,
+            TrayAction::SwitchMode,
+            TrayAction::Preset(2),
+            TrayAction::DoNotDisturb,
+            TrayAction::Show,
+            TrayAction::Quit,
+        ] {
+            assert_eq!(TrayAction::from_id(&action.id()), Some(action));
+        }
+    }
+
+    #[test]
+    fn pausing_and_moving_on_leave_a_break() {
+        let mut state = AppState::default();
+        assert!(!leaves_break(TrayAction::Toggle, &state));
+        commands::do_start(&mut state);
+        assert!(leaves_break(TrayAction::Toggle, &state));
+        assert!(leaves_break(TrayAction::Skip, &state));
+        assert!(leaves_break(TrayAction::Preset(0), &state));
+        assert!(!leaves_break(TrayAction::DoNotDisturb, &state));
+    }
+
+    #[test]
+    fn unknown_ids_are_ignored() {
+        assert_eq!(TrayAction::from_id("status"), None);
+        assert_eq!(TrayAction::from_id("preset:x"), None);
+        // Presets are looked up when applied; a stale index does nothing.
+        assert_eq!(
+            TrayAction::from_id("preset:99"),
+            Some(TrayAction::Preset(99))
+        );
+        assert!(apply(TrayAction::Preset(99), &mut AppState::default()).is_none());
+    }
+
+    #[test]
+    fn idle_pomodoro_menu() {
+        let model = menu_model(&AppState::default(), false);
+        assert_eq!(find(&model, "status"), Some(("Work · ○ ○ ○ ○", false)));
+        assert_eq!(find(&model, "toggle"), Some(("Start", true)));
+        assert_eq!(find(&model, "reset"), Some(("Reset", false)));
+        assert_eq!(find(&model, "skip"), Some(("Skip Phase", true)));
+        assert_eq!(find(&model, "switch_mode"), Some(("Switch to Timer", true)));
+        assert_eq!(find(&model, "show"), None);
+        assert_eq!(find(&model, "quit"), Some(("Quit", true)));
+    }
+
+    #[test]
+    fn do_not_disturb_item_toggles() {
+        let mut state = AppState::default();
+        assert_eq!(
+            find(&menu_model(&state, false), "do_not_disturb"),
+            Some(("Turn On Do Not Disturb", true))
+        );
+        apply(TrayAction::DoNotDisturb, &mut state);
+        assert!(state.do_not_disturb);
+        assert_eq!(
+            find(&menu_model(&state, false), "do_not_disturb"),
+            Some(("Turn Off Do Not Disturb", true))
+        );
+        apply(TrayAction::DoNotDisturb, &mut state);
+        assert!(!state.do_not_disturb);
+    }
+
+    #[test]
+    fn toggle_label_follows_status() {
+        let mut state = AppState::default();
+        commands::do_start(&mut state);
+        assert_eq!(
+            find(&menu_model(&state, false), "toggle"),
+            Some(("Pause", true))
+        );
+        commands::do_pause(&mut state);
+        let model = menu_model(&state, false);
+        assert_eq!(find(&model, "toggle"), Some(("Resume", true)));
+        assert_eq!(
+            find(&model, "status"),
+            Some(("Work · ○ ○ ○ ○ · Paused", false))
+        );
+        assert_eq!(find(&model, "reset"), Some(("Reset", true)));
+    }
+
+    #[test]
+    fn waiting_phase_offers_to_start_it() {
+        let mut timer = PomodoroTimer::new(PomodoroConfig {
+            work_secs: 1,
+            ..PomodoroConfig::default()
+        });
+        timer.start();
+        timer.tick_with(AutoStart {
+            breaks: false,
+            work: true,
+        });
+        let state = AppState {
+            active: ActiveTimer::Pomodoro(timer),
+            ..AppState::default()
+        };
+        let model = menu_model(&state, false);
+        assert_eq!(
+            find(&model, "status"),
+            Some(("Next: Short Break · ● ○ ○ ○", false))
+        );
+        assert_eq!(find(&model, "toggle"), Some(("Start Short Break", true)));
+    }
+
+    #[test]
+    fn status_line_shows_phase_and_sessions() {
+        let mut state = AppState::default();
+        commands::do_skip_phase(&mut state);
+        let model = menu_model(&state, false);
+        assert_eq!(
+            find(&model, "status"),
+            Some(("Short Break · ● ○ ○ ○", false))
+        );
+        assert_eq!(find(&model, "reset"), Some(("Reset", true)));
+    }
+
+    #[test]
+    fn basic_timer_menu_disables_skip() {
+        let model = menu_model(&basic_state(300), false);
+        assert_eq!(find(&model, "status"), Some(("Timer · Ready", false)));
+        assert_eq!(find(&model, "skip"), Some(("Skip Phase", false)));
+        assert_eq!(
+            find(&model, "switch_mode"),
+            Some(("Switch to Pomodoro", true))
+        );
+    }
+
+    #[test]
+    fn finished_basic_timer_cannot_start() {
+        let mut state = basic_state(1);
+        commands::do_start(&mut state);
+        if let ActiveTimer::Basic(t) = &mut state.active {
+            t.tick();
+        }
+        let model = menu_model(&state, false);
+        assert_eq!(find(&model, "status"), Some(("Timer · Finished", false)));
+        assert_eq!(find(&model, "toggle"), Some(("Start", false)));
+        assert_eq!(find(&model, "reset"), Some(("Reset", true)));
+    }
+
+    #[test]
+    fn dismiss_item_only_while_unacknowledged() {
+        let mut state = basic_state(1);
+        assert_eq!(find(&menu_model(&state, false), "dismiss"), None);
+        commands::do_start(&mut state);
+        if let ActiveTimer::Basic(t) = &mut state.active {
+            t.tick();
+        }
+        assert_eq!(
+            find(&menu_model(&state, false), "dismiss"),
+            Some(("Dismiss Alert", true))
+        );
+        let snap = apply(TrayAction::Dismiss, &mut state).unwrap();
+        assert!(!snap.unacknowledged);
+        assert_eq!(find(&menu_model(&state, false), "dismiss"), None);
+    }
+
+    #[test]
+    fn interval_menu_shows_round_and_set() {
+        let mut state = AppState::default();
+        commands::do_switch_to_interval(
+            &mut state,
+            Some(IntervalConfig {
+                sets: 2,
+                ..IntervalConfig::default()
+            }),
+        )
+        .unwrap();
+        let model = menu_model(&state, false);
+        assert_eq!(
+            find(&model, "status"),
+            Some(("Warm-up · Round 1/8 · Set 1/2", false))
+        );
+        assert_eq!(find(&model, "skip"), Some(("Skip Phase", true)));
+        assert_eq!(find(&model, "reset"), Some(("Reset", false)));
+        assert_eq!(find(&model, "switch_mode"), Some(("Switch to Timer", true)));
+        commands::do_skip_phase(&mut state);
+        commands::do_skip_phase(&mut state);
+        commands::do_skip_phase(&mut state);
+        commands::do_start(&mut state);
+        commands::do_pause(&mut state);
+        assert_eq!(
+            find(&menu_model(&state, false), "status"),
+            Some(("Work · Round 2/8 · Set 1/2 · Paused", false))
+        );
+    }
+
+    #[test]
+    fn flowtime_menu_offers_a_break_once_working() {
+        let mut state = AppState::default();
+        commands::do_switch_to_flowtime(&mut state);
+        let model = menu_model(&state, false);
+        assert_eq!(find(&model, "status"), Some(("Flow · 0 sessions", false)));
+        assert_eq!(find(&model, "skip"), Some(("Take Break", false)));
+        commands::do_start(&mut state);
+        if let ActiveTimer::Flowtime(t) = &mut state.active {
+            for _ in 0..30 * 60 {
+                t.tick();
+            }
+        }
+        assert_eq!(
+            find(&menu_model(&state, false), "skip"),
+            Some(("Take Break", true))
+        );
+        apply(TrayAction::Skip, &mut state).unwrap();
+        let model = menu_model(&state, false);
+        assert_eq!(
+            find(&model, "status"),
+            Some(("Break 6m · 1 session", false))
+        );
+        assert_eq!(find(&model, "skip"), Some(("Skip Phase", true)));
+    }
+
+    #[test]
+    fn tabata_preset_starts_a_workout() {
+        let mut state = AppState::default();
+        let snap = apply(TrayAction::Preset(6), &mut state).unwrap();
+        assert_eq!(snap.mode, "interval");
+        assert!(snap.is_running);
+        assert_eq!(snap.interval.unwrap().rounds, 8);
+    }
+
+    #[test]
+    fn show_item_is_optional() {
+        let model = menu_model(&AppState::default(), true);
+        assert_eq!(find(&model, "show"), Some(("Show Timer", true)));
+    }
+
+    #[test]
+    fn presets_submenu_lists_presets() {
+        let mut state = AppState::default();
+        let submenu = |state: &AppState| {
+            menu_model(state, false).into_iter().find_map(|e| match e {
+                MenuEntry::Submenu { label, items } if label == "Presets" => Some(items),
+                _ => None,
+            })
+        };
+        let presets = submenu(&state).unwrap();
+        assert_eq!(presets.len(), state.settings.presets.list().len());
+        assert_eq!(find(&presets, "preset:0"), Some(("Timer 5 min", true)));
+
+        state
+            .settings
+            .presets
+            .set_favorite("tabata-20-10", true)
+            .unwrap();
+        let presets = submenu(&state).unwrap();
+        assert_eq!(
+            presets.first(),
+            Some(&MenuEntry::Item {
+                id: "preset:6".to_string(),
+                label: "★ Tabata 20/10".to_string(),
+                enabled: true,
+            })
+        );
+    }
+
+    #[test]
+    fn preset_timer_is_named_in_the_status() {
+        let mut state = AppState::default();
+        apply(TrayAction::Preset(0), &mut state).unwrap();
+        let model = menu_model(&state, false);
+        assert_eq!(
+            find(&model, "status"),
+            Some(("Timer 5 min · Running", false))
+        );
+    }
+
+    #[test]
+    fn apply_toggle_goes_through_do_functions() {
+        let mut state = AppState::default();
+        let snap = apply(TrayAction::Toggle, &mut state).unwrap();
+        assert!(snap.is_running);
+        let snap = apply(TrayAction::Toggle, &mut state).unwrap();
+        assert!(!snap.is_running);
+    }
+
+    #[test]
+    fn apply_basic_preset_starts_timer() {
+        let mut state = AppState::default();
+        let snap = apply(TrayAction::Preset(1), &mut state).unwrap();
+        assert_eq!(snap.mode, "basic");
+        assert_eq!(snap.remaining_secs, 15 * 60);
+        assert!(snap.is_running);
+    }
+
+    #[test]
+    fn apply_pomodoro_preset_uses_config() {
+        let mut state = basic_state(300);
+        let snap = apply(TrayAction::Preset(5), &mut state).unwrap();
+        assert_eq!(snap.mode, "pomodoro");
+        assert_eq!(snap.remaining_secs, 50 * 60);
+        assert!(snap.is_running);
+    }
+
+    #[test]
+    fn apply_skip_on_basic_is_noop() {
+        let mut state = basic_state(300);
+        assert!(apply(TrayAction::Skip, &mut state).is_none());
+    }
+
+    #[test]
+    fn apply_window_actions_do_not_touch_state() {
+        let mut state = AppState::default();
+        assert!(apply(TrayAction::Show, &mut state).is_none());
+        assert!(apply(TrayAction::Quit, &mut state).is_none());
+    }
+
+    #[test]
+    fn menu_cache_detects_changes() {
+        let mut cache = TrayMenuCache::default();
+        let mut state = AppState::default();
+        assert!(cache.needs_update(&menu_model(&state, false)));
+        assert!(!cache.needs_update(&menu_model(&state, false)));
+        commands::do_start(&mut state);
+        assert!(cache.needs_update(&menu_model(&state, false)));
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{EventSound, SoundSource};
+use crate::audio_decode::BundledSound;
+use crate::commands::{ActiveTimer, AppState};
+use crate::flowtime::FlowtimeStatus;
+use crate::interval::IntervalStatus;
+use crate::pomodoro::{Phase, PomodoroStatus};
+use crate::timer::TimerStatus;
+
+/// Longest offset accepted for a warning (one day).
+pub const MAX_OFFSET_SECS: u32 = 24 * 60 * 60;
+pub const MAX_COUNTDOWN_SECS: u32 = 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarningSettings {
+    /// Seconds before the end at which to warn, e.g. `[300, 60]`.
+    pub offsets_secs: Vec<u32>,
+    /// Beep on each of the last N seconds; 0 disables the countdown.
+    pub countdown_secs: u32,
+    /// Also show a system notification for offset warnings.
+    pub notify: bool,
+    pub sound: EventSound,
+    pub countdown_sound: EventSound,
+}
+
+impl Default for WarningSettings {
+    fn default() -> Self {
+        Self {
+            offsets_secs: Vec::new(),
+            countdown_secs: 0,
+            notify: true,
+            sound: EventSound {
+                sound: Some(SoundSource::Bundled(BundledSound::Digital)),
+                volume: 0.6,
+                repeat: 1,
+            },
+            countdown_sound: EventSound {
+                sound: Some(SoundSource::Bundled(BundledSound::Blip)),
+                volume: 0.6,
+                repeat: 1,
+            },
+        }
+    }
+}
+
+impl WarningSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(bad) = self
+            .offsets_secs
+            .iter()
+            .find(|&&s| s == 0 || s > MAX_OFFSET_SECS)
+        {
+            return Err(format!(
+                "offsets_secs: {bad} must be between 1 and {MAX_OFFSET_SECS}"
+            ));
+        }
+        if self.countdown_secs > MAX_COUNTDOWN_SECS {
+            return Err(format!(
+                "countdown_secs: must be at most {MAX_COUNTDOWN_SECS}"
+            ));
+        }
+        self.sound.validate().map_err(|e| format!("sound: {e}"))?;
+        self.countdown_sound
+            .validate()
+            .map_err(|e| format!("countdown_sound: {e}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Warning {
+    /// A configured offset was reached.
+    BeforeEnd { offset_secs: u32 },
+    /// One of the final countdown seconds.
+    Countdown { secs: u32 },
+}
+
+/// Payload of the `timer:warning` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WarningPayload {
+    #[serde(flatten)]
+    pub warning: Warning,
+    /// `"timer"` for the basic timer, otherwise the pomodoro or interval phase.
+    pub phase: String,
+    pub remaining_secs: u32,
+}
+
+/// Identifies one countdown: a basic timer run, a single pomodoro phase,
+/// which is one program segment, one interval segment, or one Flowtime phase.
+/// A snooze starts a new countdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CountdownKey {
+    Basic {
+        duration_secs: u32,
+        snoozes: u32,
+    },
+    Pomodoro {
+        phase: Phase,
+        segment: usize,
+        completed_sessions: u32,
+        snoozes: u32,
+    },
+    Interval {
+        segment: usize,
+    },
+    Flowtime {
+        phase: Phase,
+        completed_sessions: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Countdown {
+    pub key: CountdownKey,
+    pub label: String,
+    pub remaining_secs: u32,
+    pub total_secs: u32,
+    /// Reset to the start and not running: warnings are re-armed.
+    pub fresh: bool,
+}
+
+impl Countdown {
+    pub fn from_state(state: &AppState) -> Self {
+        match &state.active {
+            ActiveTimer::Basic(t) => Self {
+                key: CountdownKey::Basic {
+                    duration_secs: t.duration_secs(),
+                    snoozes: t.snooze_count(),
+                },
+                label: "timer".to_string(),
+                remaining_secs: t.remaining_secs(),
+                total_secs: t.run_secs(),
+                fresh: t.status() == TimerStatus::Idle,
+            },
+            ActiveTimer::Pomodoro(t) => Self {
+                key: CountdownKey::Pomodoro {
+                    phase: t.phase(),
+                    segment: t.segment_index(),
+                    completed_sessions: t.completed_sessions(),
+                    snoozes: t.snooze_count(),
+                },
+                label: format!("{:?}", t.phase()),
+                remaining_secs: t.remaining_secs(),
+                total_secs: t.phase_duration_secs(),
+                fresh: t.status() == PomodoroStatus::Idle
+                    && t.remaining_secs() == t.phase_duration_secs(),
+            },
+            ActiveTimer::Interval(t) => Self {
+                key: CountdownKey::Interval {
+                    segment: t.segment_index(),
+                },
+                label: format!("{:?}", t.phase()),
+                remaining_secs: t.remaining_secs(),
+                total_secs: t.phase_duration_secs(),
+                fresh: t.status() == IntervalStatus::Idle
+                    && t.remaining_secs() == t.phase_duration_secs(),
+            },
+            // Work has no end, so only breaks count down to anything.
+            ActiveTimer::Flowtime(t) => Self {
+                key: CountdownKey::Flowtime {
+                    phase: t.phase(),
+                    completed_sessions: t.completed_sessions(),
+                },
+                label: format!("{:?}", t.phase()),
+                remaining_secs: t.remaining_secs(),
+                total_secs: t.phase_duration_secs(),
+                fresh: t.status() == FlowtimeStatus::Idle && t.worked_secs() == 0,
+            },
+        }
+    }
+}
+
+/// Remembers which thresholds already fired for the current countdown, so each
+/// fires exactly once however the countdown gets there.
+#[derive(Debug, Default)]
+pub struct WarningTracker {
+    key: Option<CountdownKey>,
+    fired_offsets: BTreeSet<u32>,
+    fired_countdown: BTreeSet<u32>,
+}
+
+impl WarningTracker {
+    pub fn check(&mut self, countdown: &Countdown, settings: &WarningSettings) -> Vec<Warning> {
+        if self.key.as_ref() != Some(&countdown.key) || countdown.fresh {
+            self.key = Some(countdown.key.clone());
+            self.fired_offsets.clear();
+            self.fired_countdown.clear();
+        }
+        let remaining = countdown.remaining_secs;
+        let mut warnings = Vec::new();
+        if remaining == 0 {
+            return warnings;
+        }
+
+        // Offsets at or past the full length can never be "before the end".
+        let crossed: Vec<u32> = settings
+            .offsets_secs
+            .iter()
+            .copied()
+            .filter(|&o| o < countdown.total_secs && remaining <= o)
+            .filter(|o| !self.fired_offsets.contains(o))
+            .collect();
+        // When several thresholds are crossed at once only the nearest is
+        // still meaningful; the others are marked fired without a warning.
+        if let Some(&nearest) = crossed.iter().min() {
+            warnings.push(Warning::BeforeEnd {
+                offset_secs: nearest,
+            });
+        }
+        self.fired_offsets.extend(crossed);
+
+        if remaining <= settings.countdown_secs
+            && remaining < countdown.total_secs
+            && self.fired_countdown.insert(remaining)
+        {
+            warnings.push(Warning::Countdown { secs: remaining });
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{do_reset, do_set_duration, do_start, do_switch_to_basic};
+
+    fn settings(offsets: &[u32], countdown: u32) -> WarningSettings {
+        WarningSettings {
+            offsets_secs: offsets.to_vec(),
+            countdown_secs: countdown,
+            ..WarningSettings::default()
+        }
+    }
+
+    fn basic(duration_secs: u32, remaining_secs: u32) -> Countdown {
+        Countdown {
+            key: CountdownKey::Basic {
+                duration_secs,
+                snoozes: 0,
+            },
+            label: "timer".to_string(),
+            remaining_secs,
+            total_secs: duration_secs,
+            fresh: false,
+        }
+    }
+
+    /// Run the countdown from `from` down to 1 and collect the warnings by remaining time.
+    fn run(
+        tracker: &mut WarningTracker,
+        settings: &WarningSettings,
+        from: u32,
+    ) -> Vec<(u32, Warning)> {
+        (1..=from)
+            .rev()
+            .flat_map(|r| {
+                tracker
+                    .check(&basic(600, r), settings)
+                    .into_iter()
+                    .map(move |w| (r, w))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn offsets_fire_once_at_their_threshold() {
+        let mut tracker = WarningTracker::default();
+        let fired = run(&mut tracker, &settings(&[300, 60], 0), 600);
+        assert_eq!(
+            fired,
+            vec![
+                (300, Warning::BeforeEnd { offset_secs: 300 }),
+                (60, Warning::BeforeEnd { offset_secs: 60 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn pause_does_not_refire() {
+        let mut tracker = WarningTracker::default();
+        let s = settings(&[300], 0);
+        assert_eq!(tracker.check(&basic(600, 300), &s).len(), 1);
+        // Paused: the runner keeps checking the same remaining time.
+        for _ in 0..5 {
+            assert!(tracker.check(&basic(600, 300), &s).is_empty());
+        }
+        assert!(tracker.check(&basic(600, 299), &s).is_empty());
+    }
+
+    #[test]
+    fn catching_up_fires_only_the_nearest() {
+        let mut tracker = WarningTracker::default();
+        let s = settings(&[300, 120, 60], 0);
+        assert!(tracker.check(&basic(600, 400), &s).is_empty());
+        assert_eq!(
+            tracker.check(&basic(600, 90), &s),
+            vec![Warning::BeforeEnd { offset_secs: 120 }]
+        );
+        assert_eq!(
+            tracker.check(&basic(600, 60), &s),
+            vec![Warning::BeforeEnd { offset_secs: 60 }]
+        );
+    }
+
+    #[test]
+    fn moving_back_above_a_threshold_does_not_refire() {
+        let mut tracker = WarningTracker::default();
+        let s = settings(&[60], 0);
+        assert_eq!(tracker.check(&basic(600, 60), &s).len(), 1);
+        assert!(tracker.check(&basic(600, 120), &s).is_empty());
+        assert!(tracker.check(&basic(600, 60), &s).is_empty());
+    }
+
+    #[test]
+    fn offsets_longer_than_the_countdown_never_fire() {
+        let mut tracker = WarningTracker::default();
+        let fired = run(&mut tracker, &settings(&[900, 600], 0), 600);
+        assert!(fired.is_empty());
+    }
+
+    #[test]
+    fn countdown_fires_each_final_second() {
+        let mut tracker = WarningTracker::default();
+        let fired = run(&mut tracker, &settings(&[], 3), 10);
+        assert_eq!(
+            fired,
+            vec![
+                (3, Warning::Countdown { secs: 3 }),
+                (2, Warning::Countdown { secs: 2 }),
+                (1, Warning::Countdown { secs: 1 }),
+            ]
+        );
+        assert!(tracker.check(&basic(600, 2), &settings(&[], 3)).is_empty());
+    }
+
+    #[test]
+    fn new_countdown_rearms() {
+        let mut tracker = WarningTracker::default();
+        let s = settings(&[60], 0);
+        assert_eq!(tracker.check(&basic(600, 60), &s).len(), 1);
+        // A different phase is a different countdown.
+        let work = Countdown {
+            key: CountdownKey::Pomodoro {
+                phase: Phase::Work,
+                segment: 2,
+                completed_sessions: 1,
+                snoozes: 0,
+            },
+            label: "Work".to_string(),
+            remaining_secs: 60,
+            total_secs: 1500,
+            fresh: false,
+        };
+        assert_eq!(tracker.check(&work, &s).len(), 1);
+    }
+
+    /// Tick the basic timer down to `secs` remaining, checking after every tick.
+    fn tick_to(
+        state: &mut AppState,
+        tracker: &mut WarningTracker,
+        settings: &WarningSettings,
+        secs: u32,
+    ) -> Vec<Warning> {
+        let mut fired = Vec::new();
+        while let ActiveTimer::Basic(t) = &mut state.active {
+            if t.remaining_secs() <= secs {
+                break;
+            }
+            t.tick();
+            fired.extend(tracker.check(&Countdown::from_state(state), settings));
+        }
+        fired
+    }
+
+    #[test]
+    fn reset_rearms_through_app_state() {
+        let mut state = AppState::default();
+        do_switch_to_basic(&mut state);
+        do_set_duration(&mut state, 120);
+        do_start(&mut state);
+        let mut tracker = WarningTracker::default();
+        let s = settings(&[60], 0);
+
+        assert_eq!(tick_to(&mut state, &mut tracker, &s, 30).len(), 1);
+        do_reset(&mut state);
+        assert!(tracker.check(&Countdown::from_state(&state), &s).is_empty());
+        do_start(&mut state);
+        assert_eq!(tick_to(&mut state, &mut tracker, &s, 30).len(), 1);
+    }
+
+    #[test]
+    fn pomodoro_phases_are_separate_countdowns() {
+        let state = AppState::default();
+        let countdown = Countdown::from_state(&state);
+        assert_eq!(countdown.label, "Work");
+        assert_eq!(countdown.total_secs, 25 * 60);
+        assert!(countdown.fresh);
+    }
+
+    #[test]
+    fn validation() {
+        assert!(WarningSettings::default().validate().is_ok());
+        assert!(settings(&[0], 0).validate().is_err());
+        assert!(settings(&[60], 61).validate().is_err());
+        assert!(settings(&[300, 60], 10).validate().is_ok());
+    }
+
+    #[test]
+    fn payload_serializes_flat() {
+        let payload = WarningPayload {
+            warning: Warning::BeforeEnd { offset_secs: 60 },
+            phase: "Work".to_string(),
+            remaining_secs: 60,
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            serde_json::json!({
+                "kind": "before_end",
+                "offset_secs": 60,
+                "phase": "Work",
+                "remaining_secs": 60,
+            })
+        );
+    }
+}
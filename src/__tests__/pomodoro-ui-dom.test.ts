@@ -12,8 +12,21 @@ function makeSnapshot(overrides: Partial<TimerSnapshot> = {}): TimerSnapshot {
     is_running: false,
     is_finished: false,
     phase: "Work",
+    program: null,
+    segment: null,
     session_display: "○ ○ ○ ○",
     tray_title: "🍅 25:00",
+    unacknowledged: false,
+    finished_secs_ago: null,
+    snooze_count: 0,
+    is_waiting: false,
+    waiting_secs: null,
+    total_waiting_secs: 0,
+    interval: null,
+    flowtime: null,
+    alarm: null,
+    alarm_name: null,
+    preset: null,
     ...overrides,
   };
 }
@@ -67,6 +80,112 @@ describe("renderPomodoroTimer", () => {
     expect(container.querySelector("#btn-pause")).toBeNull();
   });
 
+  it("names the next phase on the Start button while waiting", () => {
+    renderPomodoroTimer(
+      container,
+      makeSnapshot({ is_waiting: true, phase: "ShortBreak", waiting_secs: 3 }),
+      callbacks,
+    );
+    expect(container.querySelector("#btn-start")?.textContent).toBe(
+      "Start Short Break",
+    );
+  });
+
+  it("names program segments and custom programs", () => {
+    renderPomodoroTimer(
+      container,
+      makeSnapshot({
+        is_waiting: true,
+        phase: "Other",
+        program: "ultradian",
+        segment: "Plan",
+      }),
+      callbacks,
+    );
+    expect(container.querySelector("#btn-start")?.textContent).toBe(
+      "Start Plan",
+    );
+    expect(container.querySelector(".mode-label")?.textContent).toBe(
+      "ultradian",
+    );
+  });
+
+  it("shows program and segment names as text", () => {
+    const name = '<img src="x" onerror="alert(1)">';
+    renderPomodoroTimer(
+      container,
+      makeSnapshot({ is_waiting: true, program: name, segment: name }),
+      callbacks,
+    );
+    expect(container.querySelector("img")).toBeNull();
+    expect(container.querySelector(".mode-label")?.textContent).toBe(name);
+    expect(container.querySelector("#btn-start")?.textContent).toBe(
+      `Start ${name}`,
+    );
+  });
+
+  it("shows the round counter and time left in a workout", () => {
+    renderPomodoroTimer(
+      container,
+      makeSnapshot({
+        mode: "interval",
+        phase: "Work",
+        segment: "Work",
+        session_display: null,
+        interval: {
+          round: 3,
+          rounds: 8,
+          set: 1,
+          sets: 2,
+          total_remaining_secs: 290,
+        },
+      }),
+      callbacks,
+    );
+    expect(container.querySelector(".mode-label")?.textContent).toBe(
+      "Workout",
+    );
+    expect(container.querySelector(".session-dots")?.textContent).toBe(
+      "Round 3/8 · Set 1/2 · 04:50 left",
+    );
+  });
+
+  it("offers a break once Flowtime work has started", () => {
+    const flowtime = {
+      worked_secs: 0,
+      break_secs: 0,
+      completed_sessions: 1,
+      total_work_secs: 1500,
+    };
+    renderPomodoroTimer(
+      container,
+      makeSnapshot({ mode: "flowtime", session_display: null, flowtime }),
+      callbacks,
+    );
+    expect(container.querySelector(".mode-label")?.textContent).toBe(
+      "Flowtime",
+    );
+    expect(container.querySelector("#btn-take-break")).toBeNull();
+
+    resetPomodoroTimerState();
+    callbacks.onTakeBreak = vi.fn();
+    renderPomodoroTimer(
+      container,
+      makeSnapshot({
+        mode: "flowtime",
+        session_display: null,
+        is_running: true,
+        flowtime: { ...flowtime, worked_secs: 1800, break_secs: 360 },
+      }),
+      callbacks,
+    );
+    expect(container.querySelector(".session-dots")?.textContent).toBe(
+      "1 done · 06:00 break earned",
+    );
+    (container.querySelector("#btn-take-break") as HTMLElement).click();
+    expect(callbacks.onTakeBreak).toHaveBeenCalled();
+  });
+
   it("renders Pause button when running", () => {
     renderPomodoroTimer(
       container,
@@ -0,0 +1,122 @@
+// @vitest-environment jsdom
+import { beforeEach, describe, expect, it, vi } from "vitest";
+import {
+  closeQuickEntry,
+  openQuickEntry,
+  renderQuickEntry,
+} from "../quick-entry";
+import type { QuickEntryCallbacks } from "../quick-entry";
+
+function makeCallbacks(): QuickEntryCallbacks {
+  return {
+    onRun: vi.fn().mockResolvedValue(undefined),
+    onSuggest: vi.fn().mockResolvedValue([
+      { text: "pause", hint: "Pause the timer" },
+      { text: "pomodoro ", hint: "Pomodoro, e.g. pomodoro 50/10 x3" },
+    ]),
+  };
+}
+
+function type(input: HTMLInputElement, value: string) {
+  input.value = value;
+  input.dispatchEvent(new Event("input"));
+}
+
+function press(input: HTMLInputElement, key: string) {
+  input.dispatchEvent(new KeyboardEvent("keydown", { key, bubbles: true }));
+}
+
+function submit(container: HTMLElement) {
+  const form = container.querySelector(".quick-form") as HTMLFormElement;
+  form.dispatchEvent(new Event("submit", { cancelable: true }));
+}
+
+// Let pending callback promises settle
+const settle = () => new Promise((resolve) => setTimeout(resolve, 0));
+
+describe("renderQuickEntry", () => {
+  let container: HTMLElement;
+  let callbacks: QuickEntryCallbacks;
+  let input: HTMLInputElement;
+
+  beforeEach(() => {
+    document.body.innerHTML = "";
+    container = document.createElement("div");
+    document.body.appendChild(container);
+    callbacks = makeCallbacks();
+    renderQuickEntry(container, callbacks);
+    closeQuickEntry(container);
+    input = container.querySelector(".quick-input") as HTMLInputElement;
+  });
+
+  it("starts hidden and opens focused", () => {
+    expect(container.hidden).toBe(true);
+    openQuickEntry(container);
+    expect(container.hidden).toBe(false);
+    expect(document.activeElement).toBe(input);
+  });
+
+  it("lists suggestions for what is typed", async () => {
+    openQuickEntry(container);
+    type(input, "p");
+    await settle();
+    expect(callbacks.onSuggest).toHaveBeenCalledWith("p");
+    const items = container.querySelectorAll(".quick-suggestion");
+    expect(items).toHaveLength(2);
+    expect(items[0].querySelector(".quick-suggestion-text")?.textContent).toBe("pause");
+    expect(items[0].querySelector(".quick-suggestion-hint")?.textContent).toBe(
+      "Pause the timer",
+    );
+  });
+
+  it("takes the selected suggestion with Tab", async () => {
+    openQuickEntry(container);
+    type(input, "p");
+    await settle();
+    press(input, "ArrowDown");
+    press(input, "ArrowDown");
+    expect(
+      container.querySelectorAll(".quick-suggestion")[1].classList.contains("selected"),
+    ).toBe(true);
+    press(input, "Tab");
+    expect(input.value).toBe("pomodoro ");
+  });
+
+  it("runs the command and closes", async () => {
+    openQuickEntry(container);
+    type(input, "tea 4m");
+    await settle();
+    submit(container);
+    await settle();
+    expect(callbacks.onRun).toHaveBeenCalledWith("tea 4m");
+    expect(container.hidden).toBe(true);
+    expect(input.value).toBe("");
+  });
+
+  it("shows an error and marks where it is", async () => {
+    callbacks.onRun = vi
+      .fn()
+      .mockRejectedValue({ message: "unknown unit \"x\"", start: 4, end: 6 });
+    renderQuickEntry(container, callbacks);
+    input = container.querySelector(".quick-input") as HTMLInputElement;
+    openQuickEntry(container);
+    type(input, "tea 4x");
+    submit(container);
+    await settle();
+    expect(container.hidden).toBe(false);
+    expect(container.querySelector(".quick-error")?.textContent).toBe(
+      "unknown unit \"x\"",
+    );
+    expect([input.selectionStart, input.selectionEnd]).toEqual([4, 6]);
+  });
+
+  it("closes on Escape without reaching the document", () => {
+    const onDocumentKey = vi.fn();
+    document.addEventListener("keydown", onDocumentKey);
+    openQuickEntry(container);
+    press(input, "Escape");
+    expect(container.hidden).toBe(true);
+    expect(onDocumentKey).not.toHaveBeenCalled();
+    document.removeEventListener("keydown", onDocumentKey);
+  });
+});
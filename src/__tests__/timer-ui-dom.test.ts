@@ -19,8 +19,21 @@ function makeSnapshot(overrides: Partial<TimerSnapshot> = {}): TimerSnapshot {
     is_running: false,
     is_finished: false,
     phase: null,
+    program: null,
+    segment: null,
     session_display: null,
     tray_title: "⏱ 25:00",
+    unacknowledged: false,
+    finished_secs_ago: null,
+    snooze_count: 0,
+    is_waiting: false,
+    waiting_secs: null,
+    total_waiting_secs: 0,
+    interval: null,
+    flowtime: null,
+    alarm: null,
+    alarm_name: null,
+    preset: null,
     ...overrides,
   };
 }
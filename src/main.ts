@@ -1,17 +1,14 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
-import {
-  isPermissionGranted,
-  requestPermission,
-  sendNotification,
-} from "@tauri-apps/plugin-notification";
-import { getNotificationMessage } from "./notification";
 import { renderPomodoroTimer, resetPomodoroTimerState } from "./pomodoro-ui";
+import type { Suggestion } from "./quick-entry";
+import { openQuickEntry, renderQuickEntry } from "./quick-entry";
 import type { TimerSnapshot } from "./timer-ui";
 import { renderBasicTimer, resetBasicTimerState } from "./timer-ui";
 
 const app = document.getElementById("app")!;
+const quickEntry = document.getElementById("quick-entry")!;
 
 const callbacks = {
   onClose: () => hideWindow(),
@@ -19,6 +16,7 @@ const callbacks = {
   onPause: () => invoke("pause_timer"),
   onReset: () => invoke("reset_timer"),
   onSetDuration: (secs: number) => invoke("set_duration", { secs }),
+  onTakeBreak: () => invoke("take_break"),
   onSwitchMode: async () => {
     const current = (await invoke("get_snapshot")) as TimerSnapshot;
     if (current.mode === "basic") {
@@ -34,20 +32,20 @@ const callbacks = {
 };
 
 function renderSnapshot(snapshot: TimerSnapshot) {
-  if (snapshot.mode === "pomodoro") {
+  // Interval workouts and Flowtime share the pomodoro layout
+  if (snapshot.mode !== "basic") {
     renderPomodoroTimer(app, snapshot, callbacks);
   } else {
     renderBasicTimer(app, snapshot, callbacks);
   }
 }
 
-async function setupNotifications() {
-  let granted = await isPermissionGranted();
-  if (!granted) {
-    const permission = await requestPermission();
-    granted = permission === "granted";
-  }
-  return granted;
+// A quick command can change the mode, so the layout is rebuilt
+async function runQuickCommand(input: string) {
+  const snapshot = (await invoke("run_quick_command", { input })) as TimerSnapshot;
+  resetBasicTimerState();
+  resetPomodoroTimerState();
+  renderSnapshot(snapshot);
 }
 
 function hideWindow() {
@@ -55,35 +53,37 @@ function hideWindow() {
 }
 
 async function init() {
-  // Escape key to hide window
+  // Escape key to hide window, "/" to type a quick command
   document.addEventListener("keydown", (e) => {
     if (e.key === "Escape") hideWindow();
+    if (e.key === "/" && quickEntry.hidden) {
+      e.preventDefault();
+      openQuickEntry(quickEntry);
+    }
+  });
+
+  renderQuickEntry(quickEntry, {
+    onRun: runQuickCommand,
+    onSuggest: (input) =>
+      invoke("suggest_quick_commands", { input }) as Promise<Suggestion[]>,
   });
 
   // Hide window when it loses focus (click outside)
   await getCurrentWindow().onFocusChanged(({ payload: focused }) => {
     if (!focused) hideWindow();
   });
-  const notificationsGranted = await setupNotifications();
 
   // Initial render
   const snapshot = (await invoke("get_snapshot")) as TimerSnapshot;
   renderSnapshot(snapshot);
 
+  // The quick entry hotkey shows the window and asks for the entry box
+  await listen("quick:open", () => openQuickEntry(quickEntry));
+
   // Listen for tick events
   await listen<TimerSnapshot>("timer:tick", (event) => {
     renderSnapshot(event.payload);
   });
-
-  // Listen for phase change events (notifications)
-  await listen<{ from: string; to: string }>("timer:phase-change", (event) => {
-    if (notificationsGranted) {
-      const msg = getNotificationMessage(event.payload.from, event.payload.to);
-      if (msg) {
-        sendNotification({ title: msg.title, body: msg.body });
-      }
-    }
-  });
 }
 
 init();
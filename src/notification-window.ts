@@ -1,5 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
 import { getCurrentWindow } from "@tauri-apps/api/window";
-import { getNotificationMessage } from "./notification";
 
 const AUTO_CLOSE_MS = 5000;
 
@@ -20,7 +20,7 @@ style.textContent = `
   }
   #notification {
     width: 300px;
-    height: 120px;
+    height: 100vh;
     display: flex;
     flex-direction: column;
     align-items: center;
@@ -46,15 +46,69 @@ style.textContent = `
     font-weight: 400;
     color: rgba(250, 245, 240, 0.5);
   }
+  .notif-actions {
+    display: flex;
+    gap: 8px;
+    margin-top: 4px;
+  }
+  .notif-action {
+    font: inherit;
+    font-size: 12px;
+    font-weight: 500;
+    padding: 5px 12px;
+    border-radius: 999px;
+    border: 1px solid rgba(250, 245, 240, 0.12);
+    background: rgba(250, 245, 240, 0.06);
+    color: #FAF5F0;
+    cursor: pointer;
+  }
+  .notif-action:hover {
+    background: rgba(245, 158, 11, 0.2);
+  }
 `;
 document.head.appendChild(style);
 
 const container = document.getElementById("notification")!;
 
-function dismiss() {
+function close() {
   getCurrentWindow().close();
 }
 
+// Read the event (generated in Rust) from URL query params
+const params = new URLSearchParams(window.location.search);
+const eventId = Number(params.get("id"));
+const title = params.get("title");
+const body = params.get("body");
+
+interface ActionButton {
+  action: unknown;
+  label: string;
+}
+
+function parseActions(raw: string | null): ActionButton[] {
+  try {
+    const parsed = JSON.parse(raw ?? "[]");
+    return Array.isArray(parsed) ? parsed : [];
+  } catch {
+    return [];
+  }
+}
+
+const actions = parseActions(params.get("actions"));
+
+// Rust checks the event is still current and closes this window either way
+function runAction(action: unknown) {
+  invoke("notification_action", { eventId, action }).catch((err) => {
+    console.error("Notification action rejected:", err);
+    close();
+  });
+}
+
+// Dismissing acknowledges a finished timer
+function dismiss() {
+  runAction("dismiss");
+}
+
 // Click anywhere to dismiss
 container.addEventListener("click", dismiss);
 
@@ -63,39 +117,32 @@ document.addEventListener("keydown", (e) => {
   if (e.key === "Escape") dismiss();
 });
 
-// Read from/to from URL query params and show immediately
-const params = new URLSearchParams(window.location.search);
-const from = params.get("from");
-const to = params.get("to");
-
-function playNotificationSound() {
-  const ctx = new AudioContext();
-  const oscillator = ctx.createOscillator();
-  const gain = ctx.createGain();
-  oscillator.connect(gain);
-  gain.connect(ctx.destination);
-
-  oscillator.type = "sine";
-  oscillator.frequency.setValueAtTime(880, ctx.currentTime);
-  oscillator.frequency.setValueAtTime(1047, ctx.currentTime + 0.15);
-  oscillator.frequency.setValueAtTime(1319, ctx.currentTime + 0.3);
-
-  gain.gain.setValueAtTime(0.5, ctx.currentTime);
-  gain.gain.exponentialRampToValueAtTime(0.01, ctx.currentTime + 0.6);
-
-  oscillator.start(ctx.currentTime);
-  oscillator.stop(ctx.currentTime + 0.6);
-}
+// Show the message immediately
+if (title) {
+  const titleEl = document.createElement("div");
+  titleEl.className = "notif-title";
+  titleEl.textContent = title;
+  const bodyEl = document.createElement("div");
+  bodyEl.className = "notif-body";
+  bodyEl.textContent = body ?? "";
+  container.replaceChildren(titleEl, bodyEl);
 
-if (from && to) {
-  const msg = getNotificationMessage(from, to);
-  if (msg) {
-    container.innerHTML = `
-      <div class="notif-title">${msg.title}</div>
-      <div class="notif-body">${msg.body}</div>
-    `;
-    playNotificationSound();
+  if (actions.length > 0) {
+    const row = document.createElement("div");
+    row.className = "notif-actions";
+    for (const { action, label } of actions) {
+      const button = document.createElement("button");
+      button.className = "notif-action";
+      button.textContent = label;
+      button.addEventListener("click", (e) => {
+        e.stopPropagation();
+        runAction(action);
+      });
+      row.appendChild(button);
+    }
+    container.appendChild(row);
   }
 }
 
-setTimeout(dismiss, AUTO_CLOSE_MS);
+// Auto-close only hides the window; an unacknowledged timer keeps reminding
+setTimeout(close, AUTO_CLOSE_MS);
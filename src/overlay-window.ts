@@ -60,9 +60,70 @@ style.textContent = `
     opacity: 0.9;
     transform: scale(1.03);
   }
+  .overlay-button:disabled {
+    opacity: 0.4;
+    transform: none;
+    cursor: default;
+  }
   .overlay-button:active {
     transform: scale(0.97);
   }
+  .overlay-exercise {
+    font-size: 16px;
+    font-weight: 500;
+    color: rgba(250, 245, 240, 0.75);
+    padding: 10px 20px;
+    border-radius: 10px;
+    background: rgba(250, 245, 240, 0.06);
+  }
+  .overlay-secondary {
+    padding: 8px 24px;
+    font-size: 14px;
+    font-weight: 500;
+    font-family: inherit;
+    color: rgba(250, 245, 240, 0.6);
+    background: transparent;
+    border: 1px solid rgba(250, 245, 240, 0.2);
+    border-radius: 10px;
+    cursor: pointer;
+  }
+  .overlay-secondary:hover {
+    color: rgba(250, 245, 240, 0.85);
+  }
+  .overlay-actions {
+    display: flex;
+    gap: 8px;
+  }
+  .overlay-hint {
+    font-size: 14px;
+    color: rgba(250, 245, 240, 0.5);
+  }
+  body.banner #overlay {
+    flex-direction: row;
+    flex-wrap: wrap;
+    gap: 8px 16px;
+    padding: 16px;
+    border-radius: 16px;
+  }
+  body.banner .overlay-emoji {
+    font-size: 32px;
+  }
+  body.banner .overlay-title {
+    font-size: 18px;
+  }
+  body.banner .overlay-body,
+  body.banner .overlay-exercise {
+    display: none;
+  }
+  body.banner .overlay-button {
+    margin-top: 0;
+    padding: 8px 20px;
+    font-size: 14px;
+  }
+  body.banner .overlay-countdown {
+    position: static;
+    font-size: 24px;
+  }
   .overlay-countdown {
     position: fixed;
     bottom: 48px;
@@ -82,59 +143,118 @@ const container = document.getElementById("overlay")!;
 
 const params = new URLSearchParams(window.location.search);
 const to = params.get("to");
+// Banner style is a small corner window instead of a fullscreen cover
+if (params.get("banner") === "true") {
+  document.body.classList.add("banner");
+}
 
 const isLong = to === "LongBreak";
 const emoji = isLong ? "🎉" : "☕";
 const title = "お疲れさまでした！";
-const body = isLong ? "長めの休憩を取りましょう" : "少し休憩しましょう";
+const defaultBody = isLong ? "長めの休憩を取りましょう" : "少し休憩しましょう";
 
 container.innerHTML = `
   <div class="overlay-emoji">${emoji}</div>
   <div class="overlay-title">${title}</div>
-  <div class="overlay-body">${body}</div>
+  <div class="overlay-body" id="body"></div>
+  <div class="overlay-exercise" id="exercise" hidden></div>
   <button class="overlay-button">休憩を始める</button>
+  <div class="overlay-hint" id="hint" hidden></div>
+  <button class="overlay-secondary" id="postpone" hidden></button>
+  <div class="overlay-actions" id="actions"></div>
   <div class="overlay-countdown" id="countdown"></div>
 `;
 
-container.querySelector(".overlay-button")!.addEventListener("click", () => {
-  invoke("dismiss_overlay");
-});
+const bodyEl = document.getElementById("body")!;
+const exerciseEl = document.getElementById("exercise")!;
+const buttonEl = container.querySelector<HTMLButtonElement>(".overlay-button")!;
+const countdownEl = document.getElementById("countdown")!;
+const hintEl = document.getElementById("hint")!;
+const postponeEl = document.getElementById("postpone") as HTMLButtonElement;
+const actionsEl = document.getElementById("actions")!;
+bodyEl.textContent = defaultBody;
 
-function playNotificationSound() {
-  const ctx = new AudioContext();
-  const oscillator = ctx.createOscillator();
-  const gain = ctx.createGain();
-  oscillator.connect(gain);
-  gain.connect(ctx.destination);
+function formatSecs(secs: number): string {
+  const m = Math.floor(secs / 60);
+  const s = secs % 60;
+  return `${m}:${String(s).padStart(2, "0")}`;
+}
 
-  oscillator.type = "sine";
-  oscillator.frequency.setValueAtTime(880, ctx.currentTime);
-  oscillator.frequency.setValueAtTime(1047, ctx.currentTime + 0.15);
-  oscillator.frequency.setValueAtTime(1319, ctx.currentTime + 0.3);
+function showHint(text: string | null) {
+  hintEl.textContent = text ?? "";
+  hintEl.hidden = text === null;
+}
 
-  gain.gain.setValueAtTime(0.5, ctx.currentTime);
-  gain.gain.exponentialRampToValueAtTime(0.01, ctx.currentTime + 0.6);
+// Rust starts a break that is waiting for confirmation and closes every overlay,
+// unless strict mode says it is too early
+buttonEl.addEventListener("click", async () => {
+  const closed = await invoke<boolean>("dismiss_overlay");
+  if (!closed) {
+    showHint("まだ閉じられません");
+  }
+});
 
-  oscillator.start(ctx.currentTime);
-  oscillator.stop(ctx.currentTime + 0.6);
+// Rust closes the overlays on the next tick once the break is postponed
+postponeEl.addEventListener("click", () => {
+  invoke("postpone_break").catch((e) => showHint(String(e)));
+});
+
+// The overlay replaces the notification window, so it offers the same
+// actions; Rust checks the event is still current
+interface ActionButton {
+  action: unknown;
+  label: string;
 }
 
-playNotificationSound();
+let shownActions = "";
+
+function showActions(eventId: number | null, actions: ActionButton[]) {
+  const key = JSON.stringify([eventId, actions]);
+  if (key === shownActions) return;
+  shownActions = key;
+  actionsEl.replaceChildren(
+    ...actions.map(({ action, label }) => {
+      const button = document.createElement("button");
+      button.className = "overlay-secondary";
+      button.textContent = label;
+      button.addEventListener("click", () => {
+        invoke("notification_action", { eventId, action }).catch((e) => showHint(String(e)));
+      });
+      return button;
+    }),
+  );
+}
 
-interface TimerSnapshot {
+// Sent by Rust every second while the overlay is up; Rust also closes it
+interface OverlayPayload {
+  phase: string;
   display: string;
   remaining_secs: number;
-  phase: string | null;
+  total_secs: number;
+  waiting: boolean;
+  message: string | null;
+  exercise: string | null;
+  dismiss_in_secs: number;
+  postpones_left: number;
+  postpone_minutes: number;
+  opacity_percent: number;
+  event_id: number | null;
+  actions: ActionButton[];
 }
 
-const countdownEl = document.getElementById("countdown")!;
-
-listen<TimerSnapshot>("timer:tick", (event) => {
-  countdownEl.textContent = event.payload.display;
-});
-
-listen<{ from: string; to: string }>("timer:phase-change", (event) => {
-  if (event.payload.to === "Work") {
-    invoke("dismiss_overlay");
-  }
+listen<OverlayPayload>("overlay:update", (event) => {
+  const payload = event.payload;
+  countdownEl.textContent = payload.display;
+  container.style.background = `rgba(10, 8, 6, ${payload.opacity_percent / 100})`;
+  bodyEl.textContent = payload.message ?? defaultBody;
+  exerciseEl.textContent = payload.exercise ?? "";
+  exerciseEl.hidden = payload.exercise === null;
+  buttonEl.textContent = payload.waiting ? "休憩を始める" : "閉じる";
+  // Only a started break counts towards strict mode's minimum
+  const locked = !payload.waiting && payload.dismiss_in_secs > 0;
+  buttonEl.disabled = locked;
+  showHint(locked ? `あと ${formatSecs(payload.dismiss_in_secs)} は閉じられません` : null);
+  postponeEl.textContent = `${payload.postpone_minutes} 分後にする（残り ${payload.postpones_left} 回）`;
+  postponeEl.hidden = payload.postpones_left === 0;
+  showActions(payload.event_id, payload.actions);
 });
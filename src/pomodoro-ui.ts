@@ -1,62 +1,132 @@
 import type { TimerCallbacks, TimerSnapshot } from "./timer-ui";
-import { updateProgressRing, progressRingSvg } from "./timer-ui";
+import {
+  formatDisplay,
+  updateProgressRing,
+  progressRingSvg,
+} from "./timer-ui";
 
-let lastPomodoroState: { isRunning: boolean; phase: string | null } | null =
-  null;
+let lastPomodoroState: {
+  isRunning: boolean;
+  isWaiting: boolean;
+  phase: string | null;
+  segment: string | null;
+  canTakeBreak: boolean;
+} | null = null;
+
+const PHASE_LABELS: Record<string, string> = {
+  Work: "Work",
+  ShortBreak: "Short Break",
+  LongBreak: "Long Break",
+};
+
+/**
+ * Session dots, the round counter and time left in a workout, or the
+ * Flowtime sessions and earned break.
+ */
+function progressText(snapshot: TimerSnapshot): string {
+  const flowtime = snapshot.flowtime;
+  if (flowtime) {
+    const sessions = `${flowtime.completed_sessions} done`;
+    if (snapshot.phase !== "Work" || flowtime.break_secs === 0) return sessions;
+    return `${sessions} · ${formatDisplay(flowtime.break_secs)} break earned`;
+  }
+  const interval = snapshot.interval;
+  if (!interval) return snapshot.session_display || "";
+  const parts = [`Round ${interval.round}/${interval.rounds}`];
+  if (interval.sets > 1) parts.push(`Set ${interval.set}/${interval.sets}`);
+  parts.push(`${formatDisplay(interval.total_remaining_secs)} left`);
+  return parts.join(" · ");
+}
 
 export function renderPomodoroTimer(
   container: HTMLElement,
   snapshot: TimerSnapshot,
   callbacks: TimerCallbacks,
 ): void {
+  // Flowtime work runs until the user chooses to stop
+  const canTakeBreak =
+    snapshot.mode === "flowtime" &&
+    snapshot.phase === "Work" &&
+    (snapshot.flowtime?.worked_secs ?? 0) > 0;
+
   // If already mounted and state hasn't changed, just update text
   const existing = container.querySelector(".timer-display");
   if (
     existing &&
     lastPomodoroState &&
     lastPomodoroState.isRunning === snapshot.is_running &&
-    lastPomodoroState.phase === snapshot.phase
+    lastPomodoroState.isWaiting === snapshot.is_waiting &&
+    lastPomodoroState.phase === snapshot.phase &&
+    lastPomodoroState.segment === snapshot.segment &&
+    lastPomodoroState.canTakeBreak === canTakeBreak
   ) {
     existing.textContent = snapshot.display;
     updateProgressRing(container, snapshot.remaining_secs, snapshot.total_secs);
     const dots = container.querySelector(".session-dots");
-    if (dots) dots.textContent = snapshot.session_display || "";
+    if (dots) dots.textContent = progressText(snapshot);
     return;
   }
 
   lastPomodoroState = {
     isRunning: snapshot.is_running,
+    isWaiting: snapshot.is_waiting,
     phase: snapshot.phase,
+    segment: snapshot.segment,
+    canTakeBreak,
   };
 
+  // A phase waiting for confirmation is started explicitly by name
+  const phaseLabel =
+    snapshot.segment ?? PHASE_LABELS[snapshot.phase ?? ""] ?? snapshot.phase;
+  const startLabel = snapshot.is_waiting ? `Start ${phaseLabel}` : "Start";
+  // Programs other than the built-in classic one are shown by name
+  const modeLabel =
+    snapshot.mode === "interval"
+      ? "Workout"
+      : snapshot.mode === "flowtime"
+        ? "Flowtime"
+        : snapshot.program && snapshot.program !== "classic"
+          ? snapshot.program
+          : "Pomodoro";
+
   container.innerHTML = `
     <div class="timer-container" data-tauri-drag-region>
-      <div class="mode-label" data-tauri-drag-region>Pomodoro</div>
+      <div class="mode-label" data-tauri-drag-region></div>
       <div class="timer-ring-wrapper">
         ${progressRingSvg(snapshot.remaining_secs, snapshot.total_secs)}
         <div class="timer-ring-content">
           <div class="timer-display">${snapshot.display}</div>
         </div>
       </div>
-      <div class="session-dots">${snapshot.session_display || ""}</div>
+      <div class="session-dots">${progressText(snapshot)}</div>
       <div class="timer-controls">
         ${
           snapshot.is_running
             ? `<button id="btn-pause" class="btn">Pause</button>`
-            : `<button id="btn-start" class="btn btn-primary">Start</button>`
+            : `<button id="btn-start" class="btn btn-primary"></button>`
+        }
+        ${
+          canTakeBreak
+            ? `<button id="btn-take-break" class="btn">Take Break</button>`
+            : ""
         }
         <button id="btn-reset" class="btn">Reset</button>
       </div>
       <button id="btn-switch" class="btn btn-mode">Switch to Basic</button>
     </div>
   `;
+  // Program and segment names are the user's, so they are set as text
+  container.querySelector(".mode-label")!.textContent = modeLabel;
+  const start = container.querySelector("#btn-start");
+  if (start) start.textContent = startLabel;
 
-  container
-    .querySelector("#btn-start")
-    ?.addEventListener("click", callbacks.onStart);
+  start?.addEventListener("click", callbacks.onStart);
   container
     .querySelector("#btn-pause")
     ?.addEventListener("click", callbacks.onPause);
+  container
+    .querySelector("#btn-take-break")
+    ?.addEventListener("click", () => callbacks.onTakeBreak?.());
   container
     .querySelector("#btn-reset")
     ?.addEventListener("click", callbacks.onReset);
@@ -0,0 +1,143 @@
+/** A completion from `suggest_quick_commands`. */
+export interface Suggestion {
+  /** The whole input once the suggestion is taken. */
+  text: string;
+  hint: string;
+}
+
+/**
+ * Why a quick command was refused; `start`..`end` are what it is about, in
+ * UTF-16 units like a selection.
+ */
+export interface QuickError {
+  message: string;
+  start: number;
+  end: number;
+}
+
+export interface QuickEntryCallbacks {
+  /** Run the command; rejects with a `QuickError`. */
+  onRun: (input: string) => Promise<unknown>;
+  onSuggest: (input: string) => Promise<Suggestion[]>;
+}
+
+let suggestions: Suggestion[] = [];
+let selected = -1;
+// Answers to older inputs that arrive late are dropped
+let latestRequest = 0;
+
+export function renderQuickEntry(
+  container: HTMLElement,
+  callbacks: QuickEntryCallbacks,
+): void {
+  container.hidden = true;
+  container.innerHTML = `
+    <form class="quick-form">
+      <input class="quick-input" type="text" spellcheck="false" autocomplete="off"
+        placeholder="tea 4m, pomodoro 50/10 x3, pause…" />
+      <ul class="quick-suggestions"></ul>
+      <div class="quick-error"></div>
+    </form>
+  `;
+  const form = container.querySelector(".quick-form") as HTMLFormElement;
+  const input = container.querySelector(".quick-input") as HTMLInputElement;
+  const list = container.querySelector(".quick-suggestions") as HTMLElement;
+  const error = container.querySelector(".quick-error") as HTMLElement;
+
+  const showSuggestions = () => {
+    list.replaceChildren(
+      ...suggestions.map((suggestion, i) => {
+        const item = document.createElement("li");
+        item.className = i === selected ? "quick-suggestion selected" : "quick-suggestion";
+        const text = document.createElement("span");
+        text.className = "quick-suggestion-text";
+        text.textContent = suggestion.text;
+        const hint = document.createElement("span");
+        hint.className = "quick-suggestion-hint";
+        hint.textContent = suggestion.hint;
+        item.append(text, hint);
+        // Before the input loses focus
+        item.addEventListener("mousedown", (e) => {
+          e.preventDefault();
+          take(suggestion);
+        });
+        return item;
+      }),
+    );
+  };
+
+  const refresh = async () => {
+    const request = ++latestRequest;
+    const found = input.value.trim() ? await callbacks.onSuggest(input.value) : [];
+    if (request !== latestRequest) return;
+    suggestions = found;
+    selected = -1;
+    showSuggestions();
+  };
+
+  const take = (suggestion: Suggestion) => {
+    input.value = suggestion.text;
+    input.focus();
+    refresh();
+  };
+
+  input.addEventListener("input", () => {
+    error.textContent = "";
+    refresh();
+  });
+
+  input.addEventListener("keydown", (e) => {
+    if (e.key === "ArrowDown" || e.key === "ArrowUp") {
+      e.preventDefault();
+      if (suggestions.length === 0) return;
+      const step = e.key === "ArrowDown" ? 1 : suggestions.length - 1;
+      selected = (selected + step) % suggestions.length;
+      showSuggestions();
+    } else if (e.key === "Tab" && suggestions.length > 0) {
+      e.preventDefault();
+      take(suggestions[Math.max(selected, 0)]);
+    } else if (e.key === "Escape") {
+      // Close the entry only, not the window
+      e.stopPropagation();
+      closeQuickEntry(container);
+    }
+  });
+
+  form.addEventListener("submit", async (e) => {
+    e.preventDefault();
+    if (selected >= 0) {
+      take(suggestions[selected]);
+      return;
+    }
+    try {
+      await callbacks.onRun(input.value);
+      closeQuickEntry(container);
+    } catch (err) {
+      const quick = err as QuickError;
+      error.textContent = quick.message;
+      input.focus();
+      input.setSelectionRange(quick.start, quick.end);
+    }
+  });
+}
+
+/** Show the entry box, empty and focused. */
+export function openQuickEntry(container: HTMLElement): void {
+  const input = container.querySelector(".quick-input") as HTMLInputElement | null;
+  if (!input) return;
+  closeQuickEntry(container);
+  container.hidden = false;
+  input.focus();
+}
+
+export function closeQuickEntry(container: HTMLElement): void {
+  container.hidden = true;
+  const input = container.querySelector(".quick-input") as HTMLInputElement | null;
+  if (input) input.value = "";
+  const error = container.querySelector(".quick-error");
+  if (error) error.textContent = "";
+  suggestions = [];
+  selected = -1;
+  latestRequest++;
+  container.querySelector(".quick-suggestions")?.replaceChildren();
+}
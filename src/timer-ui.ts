@@ -18,8 +18,39 @@ export interface TimerSnapshot {
   is_running: boolean;
   is_finished: boolean;
   phase: string | null;
+  program: string | null;
+  segment: string | null;
   session_display: string | null;
   tray_title: string;
+  unacknowledged: boolean;
+  finished_secs_ago: number | null;
+  snooze_count: number;
+  is_waiting: boolean;
+  waiting_secs: number | null;
+  total_waiting_secs: number;
+  interval: IntervalProgress | null;
+  flowtime: FlowtimeProgress | null;
+  /** When the basic timer's alarm rings, e.g. "15:30". */
+  alarm: string | null;
+  /** What the alarm is for, e.g. "standup". */
+  alarm_name: string | null;
+  /** Label or name of the preset the timer was started from. */
+  preset: string | null;
+}
+
+export interface IntervalProgress {
+  round: number;
+  rounds: number;
+  set: number;
+  sets: number;
+  total_remaining_secs: number;
+}
+
+export interface FlowtimeProgress {
+  worked_secs: number;
+  break_secs: number;
+  completed_sessions: number;
+  total_work_secs: number;
 }
 
 export interface TimerCallbacks {
@@ -28,6 +59,8 @@ export interface TimerCallbacks {
   onReset: () => void;
   onSetDuration: (secs: number) => void;
   onSwitchMode: () => void;
+  /** Flowtime only: end the work phase and start the earned break. */
+  onTakeBreak?: () => void;
   onClose: () => void;
 }
 